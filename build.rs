@@ -1,6 +1,28 @@
+use std::process::Command;
+
 fn main() {
-    let config = slint_build::CompilerConfiguration::new().with_style("material".into());
+    let config = slint_build::CompilerConfiguration::new()
+        .with_style("material".into())
+        .with_bundled_translations("ui/translations");
     slint_build::compile_with_config("ui/app.slint", config).unwrap();
 
-
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_else(|| "unknown".into());
+    println!("cargo:rustc-env=NIMBUSSCROLL_COMMIT={commit}");
+    println!(
+        "cargo:rustc-env=NIMBUSSCROLL_TARGET={}",
+        std::env::var("TARGET").unwrap_or_default()
+    );
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    if let Ok(head) = std::fs::read_to_string(".git/HEAD") {
+        if let Some(reference) = head.trim().strip_prefix("ref: ") {
+            println!("cargo:rerun-if-changed=.git/{reference}");
+        }
+    }
 }
@@ -0,0 +1,44 @@
+/* C interface to the NimbusScroll engine (libnimbus.so / nimbus.dll). */
+#ifndef NIMBUS_H
+#define NIMBUS_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Wheel axes; the hi-res ones count 120 per notch. */
+#define NIMBUS_AXIS_WHEEL 0
+#define NIMBUS_AXIS_HWHEEL 1
+#define NIMBUS_AXIS_WHEEL_HIRES 2
+#define NIMBUS_AXIS_HWHEEL_HIRES 3
+
+typedef struct NimbusEngine NimbusEngine;
+
+typedef struct NimbusEvent {
+    int32_t axis;
+    int32_t value;
+} NimbusEvent;
+
+/* Creates an engine with default settings; it never touches the config file. */
+NimbusEngine *nimbus_init(void);
+void nimbus_free(NimbusEngine *engine);
+
+/* Sets one config file key, e.g. ("normal_wheel_gain", "0.1"); false for an unknown key. */
+bool nimbus_set_config(NimbusEngine *engine, const char *key, const char *value);
+
+/* Feeds one wheel event read from the device; false for an unknown axis. */
+bool nimbus_feed_wheel(NimbusEngine *engine, int32_t axis, int32_t value);
+
+/* Advances by dt_ms and writes up to capacity events to out, returning how many were
+ * written. Events that do not fit are returned by the next call. */
+size_t nimbus_tick(NimbusEngine *engine, double dt_ms, NimbusEvent *out, size_t capacity);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -1,10 +1,15 @@
 use crate::engine::{
-    self, EasingKind, InputEvent, MouseDeviceInfo, OutputEvent, ScrollConfig, ScrollController,
+    self, DecayModel, EasingKind, EngineState, InputEvent, MouseDeviceInfo, OutputCurve,
+    OutputEvent, ScrollAxis, ScrollConfig, ScrollController, WheelAction, WheelMode,
 };
-use crate::tray::{AboutWindow, SettingsWindow, UiHandles};
+use crate::i18n::{self, Lang};
+use crate::trace::Trace;
+use crate::tray::{AboutWindow, CalibrateWindow, PresetWindow, SettingsWindow, UiHandles};
 use slint::ComponentHandle;
 use slint::{ModelRc, SharedString, VecModel};
+use std::cell::RefCell;
 use std::error::Error;
+use std::path::Path;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -30,9 +35,26 @@ pub(crate) trait Backend {
     fn build_virtual_mouse() -> Result<Self::Output, Box<dyn Error>>;
     fn reset_virtual_mouse_buttons(out: &mut Self::Output) -> Result<(), Box<dyn Error>>;
     fn emit_output(out: &mut Self::Output, event: OutputEvent) -> Result<(), Box<dyn Error>>;
+    fn emit_passthrough(out: &mut Self::Output, event: InputEvent) -> Result<(), Box<dyn Error>>;
+
+    fn hold_pointer(out: &mut Self::Output, grace: Duration) -> PointerHold;
+    fn release_pointer(out: &mut Self::Output);
+    fn show_anchor(out: &mut Self::Output);
+    fn hide_anchor(out: &mut Self::Output);
+    fn play_scroll_sound(sound_file: Option<&str>);
+    fn scroll_edges() -> Option<(bool, bool)>;
 
     fn active_window_signature() -> Option<String>;
+    fn foreground_is_fullscreen() -> bool;
+    fn foreground_class() -> Option<String>;
     fn sleep(duration: Duration);
+    fn wait_for_input(mouse: &Self::Mouse, keyboards: &[Self::Keyboard], timeout: Duration);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PointerHold {
+    Held,
+    Contended,
 }
 
 #[cfg(target_os = "linux")]
@@ -41,22 +63,67 @@ type ActiveBackend = crate::wayland::WaylandBackend;
 #[cfg(target_os = "windows")]
 type ActiveBackend = crate::windows::WindowsBackend;
 
-pub(crate) fn run() -> Result<(), Box<dyn Error>> {
-    let config = Arc::new(ScrollConfig::new());
+pub(crate) fn list_mouse_devices() -> Vec<MouseDeviceInfo> {
+    ActiveBackend::list_mouse_devices().unwrap_or_default()
+}
+
+/// Starts the app; `safe` runs on built-in defaults that are never written back.
+pub(crate) fn run(safe: bool) -> Result<(), Box<dyn Error>> {
+    let config = Arc::new(if safe {
+        ScrollConfig::in_memory()
+    } else {
+        ScrollConfig::new()
+    });
     engine::init_config(config.clone());
+    if safe {
+        log_info!("safe mode: ignoring the config file and using defaults");
+        if crate::cli::confirm_reset() {
+            crate::cli::write_default_config(None);
+        }
+    }
+
+    if config.single_instance() && signal_running_instance() {
+        return Ok(());
+    }
+    if config.paused() || config.start_paused() {
+        engine::start_paused();
+    }
 
     let settings = SettingsWindow::new()?;
     let about = AboutWindow::new()?;
+    let calibrate = CalibrateWindow::new()?;
+    let preset = PresetWindow::new()?;
+    i18n::apply_ui_language(config.lang());
 
     #[cfg(target_os = "linux")]
     {
         let _ = slint::set_xdg_app_id("org.qwaq.NimbusScroll");
     }
 
-    settings
+    {
+        let cfg = config.clone();
+        let weak = settings.as_weak();
+        settings.window().on_close_requested(move || {
+            if cfg.preview_settings() {
+                if let Some(win) = weak.upgrade() {
+                    cancel_settings_preview(&win, &cfg);
+                }
+            }
+            slint::CloseRequestResponse::HideWindow
+        });
+    }
+    about
         .window()
         .on_close_requested(|| slint::CloseRequestResponse::HideWindow);
-    about
+    {
+        let cfg = config.clone();
+        calibrate.window().on_close_requested(move || {
+            cfg.cancel_preview();
+            slint::CloseRequestResponse::HideWindow
+        });
+    }
+
+    preset
         .window()
         .on_close_requested(|| slint::CloseRequestResponse::HideWindow);
 
@@ -103,22 +170,68 @@ pub(crate) fn run() -> Result<(), Box<dyn Error>> {
         settings.set_selected_easing(config.easing_kind().label().into());
     }
 
+    {
+        let curve_labels: Vec<SharedString> = OutputCurve::ALL
+            .iter()
+            .map(|curve| SharedString::from(curve.label()))
+            .collect();
+        let curve_model = Rc::new(VecModel::from(curve_labels));
+        settings.set_output_curves(ModelRc::from(curve_model));
+        settings.set_selected_output_curve(config.output_curve().label().into());
+    }
+
+    {
+        let model_labels: Vec<SharedString> = DecayModel::ALL
+            .iter()
+            .map(|model| SharedString::from(model.label()))
+            .collect();
+        let decay_model = Rc::new(VecModel::from(model_labels));
+        settings.set_decay_models(ModelRc::from(decay_model));
+        settings.set_selected_decay_model(config.decay_model().label().into());
+    }
+
+    {
+        let language_names: Vec<SharedString> = Lang::ALL
+            .iter()
+            .map(|lang| SharedString::from(lang.name()))
+            .collect();
+        let language_model = Rc::new(VecModel::from(language_names));
+        settings.set_languages(ModelRc::from(language_model));
+        settings.set_selected_language(config.lang().name().into());
+    }
+
     if let Some(path) = selected_path.clone() {
         config.set_mouse_device_path(Some(path));
     }
 
     {
         let cfg = config.clone();
-        settings.on_normal_gain_changed(move |v| cfg.set_normal_wheel_gain(v as f64));
+        let weak = settings.as_weak();
+        settings.on_normal_gain_changed(move |v| {
+            cfg.set_normal_wheel_gain(v as f64);
+            refresh_decay_curve(&weak, &cfg);
+        });
     }
     {
         let cfg = config.clone();
-        settings.on_normal_damping_changed(move |v| cfg.set_normal_wheel_damping(v as f64));
+        settings.on_normal_gain_h_changed(move |v| cfg.set_normal_wheel_gain_h(v as f64));
+    }
+    {
+        let cfg = config.clone();
+        let weak = settings.as_weak();
+        settings.on_normal_damping_changed(move |v| {
+            cfg.set_normal_wheel_damping(v as f64);
+            refresh_decay_curve(&weak, &cfg);
+        });
     }
     {
         let cfg = config.clone();
         settings.on_drag_gain_changed(move |v| cfg.set_drag_wheel_gain(v as f64));
     }
+    {
+        let cfg = config.clone();
+        settings.on_drag_gain_h_changed(move |v| cfg.set_drag_wheel_gain_h(v as f64));
+    }
     {
         let cfg = config.clone();
         settings.on_drag_damping_changed(move |v| cfg.set_drag_wheel_damping(v as f64));
@@ -133,29 +246,90 @@ pub(crate) fn run() -> Result<(), Box<dyn Error>> {
     }
     {
         let cfg = config.clone();
-        settings.on_loop_sleep_ms_changed(move |v| cfg.set_loop_sleep_ms(v as f64));
+        let weak = settings.as_weak();
+        settings.on_loop_sleep_ms_changed(move |v| {
+            cfg.set_loop_sleep_ms(v as f64);
+            refresh_decay_curve(&weak, &cfg);
+        });
     }
     {
         let cfg = config.clone();
-        settings.on_max_velocity_hires_changed(move |v| cfg.set_max_velocity_hires(v as f64));
+        let weak = settings.as_weak();
+        settings.on_max_velocity_hires_changed(move |v| {
+            cfg.set_max_velocity_hires(v as f64);
+            refresh_decay_curve(&weak, &cfg);
+        });
     }
     {
         let cfg = config.clone();
         settings.on_smooth_enabled_changed(move |v| cfg.set_smooth_enabled(v));
     }
+    {
+        let cfg = config.clone();
+        settings.on_smooth_horizontal_changed(move |v| cfg.set_smooth_horizontal(v));
+    }
+    {
+        let cfg = config.clone();
+        settings.on_smooth_vertical_changed(move |v| cfg.set_smooth_vertical(v));
+    }
     {
         let cfg = config.clone();
         settings.on_middle_scroll_enabled_changed(move |v| cfg.set_middle_scroll_enabled(v));
     }
     {
         let cfg = config.clone();
+        settings.on_auto_disable_fullscreen_changed(move |v| cfg.set_auto_disable_fullscreen(v));
+    }
+    {
+        let cfg = config.clone();
+        let weak = settings.as_weak();
         settings.on_easing_selected(move |value| {
             if let Some(kind) = EasingKind::from_label(value.as_str()) {
                 cfg.set_easing_kind(kind);
+                refresh_decay_curve(&weak, &cfg);
+            }
+        });
+    }
+    {
+        let cfg = config.clone();
+        let weak = settings.as_weak();
+        settings.on_output_curve_selected(move |value| {
+            if let Some(curve) = OutputCurve::from_label(value.as_str()) {
+                cfg.set_output_curve(curve);
+                refresh_decay_curve(&weak, &cfg);
+            }
+        });
+    }
+    {
+        let cfg = config.clone();
+        let weak = settings.as_weak();
+        settings.on_decay_model_selected(move |value| {
+            if let Some(model) = DecayModel::from_label(value.as_str()) {
+                cfg.set_decay_model(model);
+                refresh_decay_curve(&weak, &cfg);
             }
         });
     }
 
+    {
+        let cfg = config.clone();
+        settings.on_language_selected(move |value| {
+            if let Some(lang) = Lang::from_name(value.as_str()) {
+                cfg.set_lang(lang);
+                i18n::apply_ui_language(lang);
+            }
+        });
+    }
+
+    {
+        let cfg = config.clone();
+        settings.on_tray_icon_path_changed(move |value| {
+            let path = value.trim();
+            cfg.set_tray_icon_path((!path.is_empty()).then(|| path.to_string()));
+            crate::tray::refresh();
+        });
+    }
+
     {
         let cfg_mouse = config.clone();
         let cfg_reset = config.clone();
@@ -170,6 +344,7 @@ pub(crate) fn run() -> Result<(), Box<dyn Error>> {
                 .find(|d| label.as_str() == d.label)
             {
                 cfg_mouse.set_mouse_device_path(Some(device.path.clone()));
+                crate::tray::refresh();
             }
         });
 
@@ -188,8 +363,61 @@ pub(crate) fn run() -> Result<(), Box<dyn Error>> {
                 crate::tray::sync_settings(&win, &cfg);
                 #[cfg(target_os = "linux")]
                 win.set_selected_mouse(default_mouse_label.into());
-                win.set_selected_easing(cfg.easing_kind().label().into());
             });
+            crate::tray::refresh();
+        });
+    }
+
+    {
+        let cfg = config.clone();
+        let weak = settings.as_weak();
+        let timer = Rc::new(slint::Timer::default());
+        settings.on_test_scroll(move || {
+            let Some(win) = weak.upgrade() else {
+                return;
+            };
+
+            // Every run starts from rest so momentum from the previous one does not carry over.
+            let controller = Rc::new(RefCell::new(ScrollController::new()));
+            let value = if win.invoke_test_scroll_at_end() {
+                1
+            } else {
+                -1
+            };
+            let mut out = Vec::new();
+            for _ in 0..TEST_SCROLL_NOTCHES {
+                let event = InputEvent::Rel {
+                    axis: ScrollAxis::Wheel,
+                    value,
+                };
+                out.extend(controller.borrow_mut().handle_input(event, &cfg));
+            }
+            win.invoke_scroll_test_by(test_scroll_px(&out));
+
+            let cfg = cfg.clone();
+            let weak = weak.clone();
+            let stop = Rc::downgrade(&timer);
+            let started = Instant::now();
+            let mut last_tick = started;
+            timer.start(
+                slint::TimerMode::Repeated,
+                Duration::from_millis(cfg.loop_sleep_ms().max(1)),
+                move || {
+                    let now = Instant::now();
+                    let out = controller.borrow_mut().advance(&cfg, now - last_tick);
+                    last_tick = now;
+                    if let Some(win) = weak.upgrade() {
+                        win.invoke_scroll_test_by(test_scroll_px(&out));
+                    }
+
+                    let done = controller.borrow().is_idle() || now - started > TEST_SCROLL_SPAN;
+                    if done {
+                        if let Some(timer) = stop.upgrade() {
+                            timer.stop();
+                        }
+                    }
+                },
+            );
         });
     }
 
@@ -202,6 +430,39 @@ pub(crate) fn run() -> Result<(), Box<dyn Error>> {
         });
     }
 
+    {
+        let cfg = config.clone();
+        settings.on_preview_changes_changed(move |v| {
+            if v {
+                cfg.set_preview_settings(true);
+                cfg.begin_preview();
+            } else {
+                cfg.commit_preview();
+                cfg.set_preview_settings(false);
+            }
+        });
+    }
+    {
+        let cfg = config.clone();
+        let weak = settings.as_weak();
+        settings.on_save_changes(move || {
+            cfg.commit_preview();
+            if let Some(win) = weak.upgrade() {
+                let _ = win.hide();
+            }
+        });
+    }
+    {
+        let cfg = config.clone();
+        let weak = settings.as_weak();
+        settings.on_cancel_changes(move || {
+            if let Some(win) = weak.upgrade() {
+                cancel_settings_preview(&win, &cfg);
+                let _ = win.hide();
+            }
+        });
+    }
+
     {
         let weak = settings.as_weak();
         settings.on_request_close(move || {
@@ -232,16 +493,81 @@ pub(crate) fn run() -> Result<(), Box<dyn Error>> {
         });
     }
 
+    {
+        let cfg = config.clone();
+        calibrate.on_normal_gain_changed(move |v| cfg.set_normal_wheel_gain(v as f64));
+    }
+    {
+        let cfg = config.clone();
+        calibrate.on_normal_damping_changed(move |v| cfg.set_normal_wheel_damping(v as f64));
+    }
+    {
+        let cfg = config.clone();
+        let weak = calibrate.as_weak();
+        calibrate.on_apply(move || {
+            cfg.commit_preview();
+            let _ = weak.upgrade_in_event_loop(move |win| {
+                let _ = win.hide();
+            });
+        });
+    }
+    {
+        let cfg = config.clone();
+        let weak = calibrate.as_weak();
+        calibrate.on_cancel(move || {
+            cfg.cancel_preview();
+            let _ = weak.upgrade_in_event_loop(move |win| {
+                let _ = win.hide();
+            });
+        });
+    }
+
+    {
+        let cfg = config.clone();
+        let weak = preset.as_weak();
+        preset.on_save(move |name| {
+            if cfg.save_preset(name.as_str()) {
+                crate::tray::refresh();
+                let _ = weak.upgrade_in_event_loop(move |win| {
+                    let _ = win.hide();
+                });
+            }
+        });
+    }
+    {
+        let weak = preset.as_weak();
+        preset.on_cancel(move || {
+            let _ = weak.upgrade_in_event_loop(move |win| {
+                let _ = win.hide();
+            });
+        });
+    }
+
     let ui = UiHandles {
         settings: settings.as_weak(),
         about: about.as_weak(),
+        calibrate: calibrate.as_weak(),
+        preset: preset.as_weak(),
         config: config.clone(),
     };
 
     crate::tray::start(ui.clone())?;
 
+    #[cfg(target_os = "linux")]
+    if config.single_instance() {
+        crate::wayland::listen_for_instances();
+    }
+
     let engine_handle = spawn_engine();
 
+    // A repeating timer only fires while the event loop is being pumped.
+    let heartbeat = slint::Timer::default();
+    heartbeat.start(slint::TimerMode::Repeated, Duration::from_secs(1), || {
+        crate::watchdog::beat(crate::watchdog::Loop::Ui);
+    });
+    crate::watchdog::beat(crate::watchdog::Loop::Ui);
+    crate::watchdog::start();
+
     let _ = slint::run_event_loop_until_quit();
 
     engine::request_exit();
@@ -253,12 +579,121 @@ pub(crate) fn run() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Pixels the settings test list moves for a batch of controller output. Hi-res events are
+/// used when present; plain detents only arrive on their own when smoothing is off.
+fn test_scroll_px(events: &[OutputEvent]) -> f32 {
+    let mut hires = 0;
+    let mut detents = 0;
+    for event in events {
+        match event {
+            OutputEvent::Rel {
+                axis: ScrollAxis::WheelHiRes,
+                value,
+            } => hires += value,
+            OutputEvent::Rel {
+                axis: ScrollAxis::Wheel,
+                value,
+            } => detents += value,
+            _ => {}
+        }
+    }
+
+    let notches = if hires != 0 {
+        hires as f32 / 120.0
+    } else {
+        detents as f32
+    };
+    notches * TEST_SCROLL_PX_PER_NOTCH
+}
+
+/// Restores the settings captured when the window opened and brings the UI back in line.
+fn cancel_settings_preview(win: &SettingsWindow, cfg: &ScrollConfig) {
+    cfg.cancel_preview();
+    i18n::apply_ui_language(cfg.lang());
+    win.set_selected_language(cfg.lang().name().into());
+    crate::tray::sync_settings(win, cfg);
+    crate::tray::refresh();
+}
+
+fn refresh_decay_curve(weak: &slint::Weak<SettingsWindow>, cfg: &ScrollConfig) {
+    if let Some(win) = weak.upgrade() {
+        win.set_decay_curve(crate::tray::decay_curve(cfg));
+    }
+}
+
+/// True when another instance is running and was asked to show its settings instead.
+fn signal_running_instance() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        return crate::wayland::signal_running_instance();
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        return crate::windows::signal_running_instance();
+    }
+
+    #[allow(unreachable_code)]
+    false
+}
+
+/// Marks the engine suspended when the scroll loop ends for any reason, error or panic
+/// included, so a wheel hook that outlives it passes input through instead of eating it.
+struct SuspendOnExit;
+
+impl Drop for SuspendOnExit {
+    fn drop(&mut self) {
+        engine::set_suspended(true);
+    }
+}
+
 fn spawn_engine() -> std::thread::JoinHandle<()> {
     std::thread::spawn(move || {
-        let _ = run_backend::<ActiveBackend>();
+        let _suspend = SuspendOnExit;
+        if !wait_input_start_delay() {
+            return;
+        }
+        if let Err(err) = run_backend::<ActiveBackend>() {
+            log_error!("engine stopped: {}", err);
+        }
     })
 }
 
+/// Holds off `input_start_delay_ms` so other input tools starting at logon get their hooks in
+/// first. False when the app quits during the wait.
+fn wait_input_start_delay() -> bool {
+    let delay = Duration::from_millis(engine::config().input_start_delay_ms());
+    if delay.is_zero() {
+        return true;
+    }
+    log_info!("waiting {} ms before hooking input", delay.as_millis());
+    let deadline = Instant::now() + delay;
+    while !engine::should_exit() {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        std::thread::sleep(left.min(IDLE_WAIT));
+    }
+    false
+}
+
+const TEST_SCROLL_NOTCHES: usize = 3;
+const TEST_SCROLL_PX_PER_NOTCH: f32 = 72.0;
+const TEST_SCROLL_SPAN: Duration = Duration::from_secs(3);
+
+// Upper bound on an idle wait, so pause, focus and device changes are still picked up.
+const IDLE_WAIT: Duration = Duration::from_millis(100);
+
+// How often the `disable_file` kill switch is looked for; a stat a second needs no watcher.
+const DISABLE_FILE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Shortest gap between `scroll_sound` clicks, so a fast flick ticks instead of buzzing.
+const SCROLL_SOUND_INTERVAL: Duration = Duration::from_millis(50);
+
+// How often `edge_detect` reads the scroll bar while coasting; it is a cross-process call.
+const EDGE_POLL_INTERVAL: Duration = Duration::from_millis(30);
+
 fn run_backend<B: Backend>() -> Result<(), Box<dyn Error>> {
     let cfg = engine::config();
 
@@ -285,21 +720,85 @@ fn run_backend<B: Backend>() -> Result<(), Box<dyn Error>> {
     let _ = B::reset_virtual_mouse_buttons(&mut virtual_mouse);
 
     let mut controller = ScrollController::new();
+    let mut trace = Trace::open(cfg);
     let mut last_tick = Instant::now();
     let mut current_mouse_path = initial_mouse_path;
     let mut last_focus_signature = B::active_window_signature();
+    let mut fullscreen_foreground = B::foreground_is_fullscreen();
+    let mut foreground_class = B::foreground_class();
+    let mut suspended = false;
+    let mut anchor_shown = false;
+    let mut idle = false;
+    let mut disable_checked: Option<Instant> = None;
+    let mut last_sound: Option<Instant> = None;
+    let mut edge_checked: Option<Instant> = None;
 
     loop {
         if engine::should_exit() {
             break;
         }
+        crate::watchdog::beat(crate::watchdog::Loop::Engine);
 
         let mut saw_raw_input = false;
 
         let current_focus = B::active_window_signature();
         if current_focus != last_focus_signature {
             last_focus_signature = current_focus;
+            fullscreen_foreground = B::foreground_is_fullscreen();
+            foreground_class = B::foreground_class();
+            // The focus is polled every tick while coasting, so this catches the switch before
+            // any further momentum lands in the new window.
+            if cfg.stop_on_focus_change() {
+                controller.drop_momentum();
+            }
+        }
+
+        // A finished transition is remembered, so a restart comes back in the same state.
+        engine::resolve_pause();
+        let paused = match engine::engine_state() {
+            EngineState::Pausing => {
+                let paused = engine::transition_engine(EngineState::Pausing, EngineState::Paused);
+                if paused {
+                    cfg.set_paused(true);
+                }
+                paused
+            }
+            EngineState::Resuming => {
+                controller.reset_device_state();
+                let resumed =
+                    engine::transition_engine(EngineState::Resuming, EngineState::Running);
+                if resumed {
+                    cfg.set_paused(false);
+                }
+                !resumed
+            }
+            EngineState::Paused => true,
+            EngineState::Running => false,
+        };
+
+        if disable_checked.is_none_or(|at| at.elapsed() >= DISABLE_FILE_POLL_INTERVAL) {
+            disable_checked = Some(Instant::now());
+            let present = cfg
+                .disable_file()
+                .is_some_and(|path| Path::new(&path).exists());
+            engine::set_disabled(present);
+        }
+
+        let excluded = foreground_class
+            .as_deref()
+            .is_some_and(|class| cfg.is_class_disabled(class));
+        let inversion = cfg.scroll_inversion(foreground_class.as_deref());
+        let wheel_action = cfg.wheel_action(foreground_class.as_deref());
+        engine::set_wheel_action(wheel_action);
+        let suspend = paused
+            || engine::is_disabled()
+            || excluded
+            || (cfg.auto_disable_fullscreen() && fullscreen_foreground);
+        if suspend != suspended {
+            suspended = suspend;
+            engine::set_suspended(suspend);
             controller.clear_scroll_state();
+            B::release_pointer(&mut virtual_mouse);
         }
 
         if let Some(desired_path) = cfg.mouse_device_path() {
@@ -311,6 +810,7 @@ fn run_backend<B: Backend>() -> Result<(), Box<dyn Error>> {
                         mouse = new_mouse;
                         current_mouse_path = Some(desired_path);
                         controller.clear_scroll_state();
+                        controller.reset_device_state();
                         let _ = B::reset_virtual_mouse_buttons(&mut virtual_mouse);
                     }
                 }
@@ -322,24 +822,162 @@ fn run_backend<B: Backend>() -> Result<(), Box<dyn Error>> {
             for ev in events {
                 saw_raw_input = true;
                 let outputs = controller.handle_input(ev, cfg);
-                emit_all::<B>(&mut virtual_mouse, outputs)?;
+                emit_all::<B>(&mut virtual_mouse, outputs, controller.inversion(inversion))?;
             }
         }
 
+        let was_idle = controller.is_idle();
         let mouse_events = B::poll_mouse_events(&mut mouse)?;
         for ev in mouse_events {
             saw_raw_input = true;
+            if let Some(trace) = trace.as_mut() {
+                trace.input(ev);
+            }
+            if suspended {
+                B::emit_passthrough(&mut virtual_mouse, ev)?;
+                continue;
+            }
+            if ev.is_wheel() && wheel_action != WheelAction::Scroll {
+                // The wheel is taken over here, so none of it reaches the app as scrolling and
+                // the app's own Ctrl+wheel zoom cannot double up with the keystrokes.
+                let outputs = if wheel_action == WheelAction::Zoom {
+                    controller.zoom_steps(ev)
+                } else {
+                    Vec::new()
+                };
+                emit_all::<B>(&mut virtual_mouse, outputs, (false, false))?;
+                continue;
+            }
+            if ev.is_wheel() && cfg.wheel_mode() == WheelMode::Additive {
+                // The original notch goes out as-is; the controller only adds momentum on top,
+                // so its unsmoothed echo of the notch is dropped.
+                B::emit_passthrough(&mut virtual_mouse, ev)?;
+                let _ = controller.handle_input(ev, cfg);
+                continue;
+            }
             let outputs = controller.handle_input(ev, cfg);
-            emit_all::<B>(&mut virtual_mouse, outputs)?;
+            if let Some(trace) = trace.as_mut() {
+                trace.output(&outputs);
+            }
+            emit_all::<B>(&mut virtual_mouse, outputs, controller.inversion(inversion))?;
+        }
+
+        if controller.is_drag_scrolling(cfg) {
+            let grace = Duration::from_millis(cfg.clip_grace_ms());
+            if B::hold_pointer(&mut virtual_mouse, grace) == PointerHold::Contended {
+                log_info!("pointer clip is owned by another application, leaving drag mode");
+                controller.cancel_drag();
+                B::release_pointer(&mut virtual_mouse);
+            }
+        } else {
+            B::release_pointer(&mut virtual_mouse);
+        }
+
+        engine::set_auto_scrolling(controller.is_auto_scrolling());
+        let want_anchor = cfg.anchor_icon() && controller.is_drag_scrolling(cfg);
+        if want_anchor != anchor_shown {
+            anchor_shown = want_anchor;
+            if want_anchor {
+                B::show_anchor(&mut virtual_mouse);
+            } else {
+                B::hide_anchor(&mut virtual_mouse);
+            }
         }
 
         let now = Instant::now();
-        let dt = now.saturating_duration_since(last_tick);
+        let mut dt = now.saturating_duration_since(last_tick);
         last_tick = now;
+        if cfg.low_latency_first_emit() && was_idle && !controller.is_idle() {
+            // The notch just arrived, so dt is a sliver; step a whole tick to send a real first
+            // event now instead of on the next timer tick.
+            dt = dt.max(Duration::from_millis(cfg.loop_sleep_ms()));
+        }
 
-        let smooth_outputs = controller.advance(cfg, dt);
+        let smooth_outputs = if suspended {
+            Vec::new()
+        } else {
+            controller.advance(cfg, dt)
+        };
         let emitted_smooth = !smooth_outputs.is_empty();
-        emit_all::<B>(&mut virtual_mouse, smooth_outputs)?;
+        if emitted_smooth {
+            log_trace!(
+                "emitting {} smooth events after {:?}",
+                smooth_outputs.len(),
+                dt
+            );
+        }
+        let crossed_notch = smooth_outputs.iter().any(|ev| {
+            matches!(
+                ev,
+                OutputEvent::Rel {
+                    axis: ScrollAxis::Wheel | ScrollAxis::HWheel,
+                    ..
+                }
+            )
+        });
+        if cfg.pulse() && crossed_notch {
+            crate::tray::pulse();
+        }
+        if cfg.scroll_sound()
+            && crossed_notch
+            && last_sound.is_none_or(|at| at.elapsed() >= SCROLL_SOUND_INTERVAL)
+        {
+            last_sound = Some(Instant::now());
+            B::play_scroll_sound(cfg.scroll_sound_file().as_deref());
+        }
+        if cfg.edge_detect()
+            && emitted_smooth
+            && edge_checked.is_none_or(|at| at.elapsed() >= EDGE_POLL_INTERVAL)
+        {
+            edge_checked = Some(Instant::now());
+            if let Some((at_top, at_bottom)) = B::scroll_edges() {
+                // Positive wheel values scroll up, before any inversion is applied on output.
+                let (_, invert_y) = controller.inversion(inversion);
+                let velocity = controller.velocity().1;
+                let up = if invert_y {
+                    velocity < 0.0
+                } else {
+                    velocity > 0.0
+                };
+                let down = velocity != 0.0 && !up;
+                if (up && at_top) || (down && at_bottom) {
+                    log_trace!("scroll bar at its end, stopping momentum");
+                    controller.stop_vertical();
+                }
+            }
+        }
+        if let Some(active) = trace.as_mut() {
+            active.output(&smooth_outputs);
+            if let Err(err) = active.end_tick(controller.velocity()) {
+                log_error!("failed to write trace, stopping it: {}", err);
+                trace = None;
+            }
+        }
+        emit_all::<B>(
+            &mut virtual_mouse,
+            smooth_outputs,
+            controller.inversion(inversion),
+        )?;
+
+        let now_idle =
+            cfg.sleep_when_idle() && !saw_raw_input && !emitted_smooth && controller.is_idle();
+        if now_idle != idle {
+            idle = now_idle;
+            log_trace!(
+                "engine {}",
+                if idle {
+                    "idle, waiting for input"
+                } else {
+                    "active"
+                }
+            );
+        }
+        if idle {
+            B::wait_for_input(&mouse, &keyboards, IDLE_WAIT);
+            // The wait is not scroll time; keep it out of the next tick's dt.
+            last_tick = Instant::now();
+            continue;
+        }
 
         let sleep_ms = if saw_raw_input {
             1
@@ -349,18 +987,28 @@ fn run_backend<B: Backend>() -> Result<(), Box<dyn Error>> {
             cfg.loop_sleep_ms().max(1)
         };
 
-        B::sleep(Duration::from_millis(sleep_ms));
+        if cfg.low_latency_first_emit() && controller.is_idle() {
+            // Nothing is in flight, so let the next notch end the sleep as soon as it arrives.
+            B::wait_for_input(&mouse, &keyboards, Duration::from_millis(sleep_ms));
+        } else {
+            B::sleep(Duration::from_millis(sleep_ms));
+        }
     }
 
+    B::release_pointer(&mut virtual_mouse);
+    if anchor_shown {
+        B::hide_anchor(&mut virtual_mouse);
+    }
     Ok(())
 }
 
 fn emit_all<B: Backend>(
     out: &mut B::Output,
     events: Vec<OutputEvent>,
+    (invert_x, invert_y): (bool, bool),
 ) -> Result<(), Box<dyn Error>> {
     for event in events {
-        B::emit_output(out, event)?;
+        B::emit_output(out, event.inverted(invert_x, invert_y))?;
     }
     Ok(())
 }
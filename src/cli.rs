@@ -0,0 +1,88 @@
+use crate::engine::ScrollConfig;
+use std::path::PathBuf;
+
+pub(crate) enum Command {
+    Run,
+    /// `--safe` / `--reset`: start from built-in defaults without reading the config file.
+    Safe,
+    /// `--trace <file>`: run normally while recording the scroll loop to a CSV file.
+    Trace(PathBuf),
+    Version,
+    WriteDefaultConfig(Option<PathBuf>),
+}
+
+pub(crate) fn parse() -> Command {
+    let mut args = std::env::args_os().skip(1);
+
+    match args.next() {
+        Some(arg) if arg == "--version" || arg == "-V" => Command::Version,
+        Some(arg) if arg == "--safe" || arg == "--reset" => Command::Safe,
+        Some(arg) if arg == "--trace" => match args.next() {
+            Some(path) => Command::Trace(PathBuf::from(path)),
+            None => {
+                log_error!("--trace needs a file path, running without a trace");
+                Command::Run
+            }
+        },
+        Some(arg) if arg == "--write-default-config" => {
+            Command::WriteDefaultConfig(args.next().map(PathBuf::from))
+        }
+        _ => Command::Run,
+    }
+}
+
+/// Release builds on Windows have no console of their own; borrow the launching terminal's.
+pub(crate) fn attach_console() {
+    #[cfg(target_os = "windows")]
+    crate::windows::attach_parent_console();
+}
+
+pub(crate) fn print_version() -> i32 {
+    println!(
+        "NimbusScroll {} ({}, {})",
+        env!("CARGO_PKG_VERSION"),
+        env!("NIMBUSSCROLL_COMMIT"),
+        env!("NIMBUSSCROLL_TARGET")
+    );
+    0
+}
+
+pub(crate) fn write_default_config(path: Option<PathBuf>) -> i32 {
+    match ScrollConfig::write_default_config(path) {
+        Ok(path) => {
+            log_info!("default config written to {}", path.display());
+            0
+        }
+        Err(err) => {
+            log_error!("failed to write default config: {}", err);
+            1
+        }
+    }
+}
+
+/// Safe mode's offer to replace a possibly broken config file with the defaults.
+pub(crate) fn confirm_reset() -> bool {
+    let question = crate::i18n::tr("safe.reset_prompt");
+
+    #[cfg(target_os = "windows")]
+    {
+        return crate::windows::confirm("NimbusScroll", question);
+    }
+
+    #[allow(unreachable_code)]
+    {
+        use std::io::{BufRead, IsTerminal, Write};
+
+        let stdin = std::io::stdin();
+        if !stdin.is_terminal() {
+            return false;
+        }
+        print!("{} [y/N] ", question);
+        let _ = std::io::stdout().flush();
+        let mut answer = String::new();
+        if stdin.lock().read_line(&mut answer).is_err() {
+            return false;
+        }
+        matches!(answer.trim(), "y" | "Y" | "yes")
+    }
+}
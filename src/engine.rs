@@ -1,9 +1,11 @@
 use crate::easing;
+use crate::i18n::Lang;
+use crate::log::{self, LogFormat, LogLevel};
 use std::env;
 use std::fs;
 use std::io;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 
@@ -17,15 +19,74 @@ pub(crate) const DEFAULT_DRAG_DEADZONE_PX: f64 = 3.0;
 pub(crate) const DEFAULT_TAP_MAX_DURATION_MS: u64 = 220;
 pub(crate) const DEFAULT_LOOP_SLEEP_MS: u64 = 4;
 pub(crate) const DEFAULT_MAX_VELOCITY_HIRES: f64 = 18.0;
+pub(crate) const DEFAULT_CLIP_GRACE_MS: u64 = 50;
+pub(crate) const DEFAULT_SPRING_STIFFNESS: f64 = 0.04;
+pub(crate) const DEFAULT_SPRING_DAMPING: f64 = 0.4;
+pub(crate) const DEFAULT_STOP_THRESHOLD: f64 = 0.0001;
+pub(crate) const DEFAULT_EMIT_THRESHOLD: f64 = 0.0;
+pub(crate) const DEFAULT_SNAP_VELOCITY: f64 = 14.0;
+pub(crate) const DEFAULT_INPUT_TAG: u64 = 0x4E53_4352;
+pub(crate) const DEFAULT_TURBO_FACTOR: f64 = 4.0;
+pub(crate) const DEFAULT_MIN_NOTCH_TRAVEL: f64 = 0.0;
+pub(crate) const DEFAULT_DRAG_SMOOTHING: f64 = 0.0;
+pub(crate) const DEFAULT_DRAG_SOFT_START_PX: f64 = 0.0;
+pub(crate) const DEFAULT_TRAY_STATUS_DELAY_MS: u64 = 300;
+pub(crate) const DEFAULT_MAX_COAST_MS: u64 = 0;
+pub(crate) const DEFAULT_BATCH_MS: u64 = 0;
+pub(crate) const DEFAULT_MAX_CONTINUOUS_SCROLL_MS: u64 = 0;
+pub(crate) const DEFAULT_TEMP_INVERT_KEY: u64 = 0;
+pub(crate) const DEFAULT_INERTIA_CARRYOVER: f64 = 1.0;
+pub(crate) const DEFAULT_AUTO_SCROLL_SPEED: f64 = 20.0;
+pub(crate) const DEFAULT_SOFT_LAND_VELOCITY: f64 = 3.0;
+pub(crate) const DEFAULT_DECAY_VELOCITY_SCALE: f64 = 0.0;
+pub(crate) const DEFAULT_FLICK_NOTCH_RATE: f64 = 0.0;
+pub(crate) const DEFAULT_MAX_NOTCHES_PER_TICK: f64 = 20.0;
+pub(crate) const DEFAULT_FINE_KEY: u64 = 0;
+pub(crate) const DEFAULT_FINE_FACTOR: f64 = 0.25;
+pub(crate) const DEFAULT_STARTUP_DELAY_MS: u64 = 0;
+pub(crate) const DEFAULT_INPUT_START_DELAY_MS: u64 = 0;
+pub(crate) const DEFAULT_PAUSE_COOLDOWN_MS: u64 = 300;
 
 pub(crate) const VELOCITY_EPSILON: f64 = 0.0001;
 pub(crate) const ACCUM_EPSILON: f64 = 0.0001;
+// Momentum is tuned for ticks of 1/144 s. A single decay step covers at most
+// `MAX_TICK_SCALE` of them; a longer tick is stepped in several slices.
+pub(crate) const BASE_TICK_MS: f64 = 1000.0 / 144.0;
+pub(crate) const MAX_TICK_SCALE: f64 = 4.0;
+// Longest tick stepped in full. Past it, such as after system sleep, the rest of the stall is
+// dropped instead of caught up in one burst.
+pub(crate) const MAX_STEPPED_MS: u64 = 250;
+// Slowest glide, in hi-res units per base tick, used to finish a notch's minimum travel.
+pub(crate) const FLOOR_CREEP_HIRES: f64 = 2.0;
+// Largest drag step, in pixels, that drag_smoothing filters; bigger steps pass through.
+pub(crate) const DRAG_SMOOTHING_MAX_DELTA: f64 = 3.0;
 
 const CONFIG_DIR_NAME: &str = "NimbusScroll";
 const CONFIG_FILE_NAME: &str = "config.txt";
+const PRESET_SECTION_PREFIX: &str = "preset:";
+// Offered in the preset menu until a preset of the same name is saved over it.
+const PRESENTATION_PRESET: &str = "Presentation";
+// `NIMBUS_` plus the upper-cased config key, e.g. `NIMBUS_NORMAL_WHEEL_GAIN`.
+const ENV_OVERRIDE_PREFIX: &str = "NIMBUS_";
 
 static CONFIG: OnceLock<Arc<ScrollConfig>> = OnceLock::new();
 static EXIT_REQUESTED: AtomicBool = AtomicBool::new(false);
+static ENGINE_STATE: AtomicU8 = AtomicU8::new(EngineState::Running as u8);
+// The pause state last asked for; the engine catches up once `pause_cooldown_ms` allows.
+static PAUSE_WANTED: AtomicBool = AtomicBool::new(false);
+// When the engine last started pausing or resuming.
+static PAUSE_SWITCHED_AT: Mutex<Option<Instant>> = Mutex::new(None);
+// Set while the backend passes input through untouched (paused, excluded app or fullscreen).
+static SUSPENDED: AtomicBool = AtomicBool::new(false);
+// Set while the `disable_file` sentinel exists.
+static DISABLED: AtomicBool = AtomicBool::new(false);
+// Set while autoscroll runs, so the Windows mouse hook can keep the click that ends it.
+static AUTO_SCROLLING: AtomicBool = AtomicBool::new(false);
+// What the wheel does in the focused window, from the `*_for_classes` lists.
+static WHEEL_ACTION: AtomicU64 = AtomicU64::new(WheelAction::Scroll as u64);
+// Lines and characters the OS scrolls per notch; 0 where the platform has no such setting.
+static WHEEL_LINES: AtomicU32 = AtomicU32::new(0);
+static WHEEL_CHARS: AtomicU32 = AtomicU32::new(0);
 
 #[repr(u64)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -248,536 +309,4242 @@ impl EasingKind {
     }
 }
 
-#[derive(Debug, Clone)]
-struct ConfigSnapshot {
-    normal_wheel_gain: f64,
-    normal_wheel_damping: f64,
-    drag_wheel_gain: f64,
-    drag_wheel_damping: f64,
-    drag_deadzone_px: f64,
-    tap_max_duration_ms: u64,
-    loop_sleep_ms: u64,
-    max_velocity_hires: f64,
-    easing_kind: EasingKind,
-    smooth_enabled: bool,
-    middle_scroll_enabled: bool,
-    mouse_device_path: Option<String>,
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutputCurve {
+    Identity = 0,
+    EaseOut = 1,
+    Clamp = 2,
 }
 
-impl ConfigSnapshot {
-    fn defaults() -> Self {
-        Self {
-            normal_wheel_gain: DEFAULT_NORMAL_WHEEL_GAIN,
-            normal_wheel_damping: DEFAULT_NORMAL_WHEEL_DAMPING,
-            drag_wheel_gain: DEFAULT_DRAG_WHEEL_GAIN,
-            drag_wheel_damping: DEFAULT_DRAG_WHEEL_DAMPING,
-            drag_deadzone_px: DEFAULT_DRAG_DEADZONE_PX,
-            tap_max_duration_ms: DEFAULT_TAP_MAX_DURATION_MS,
-            loop_sleep_ms: DEFAULT_LOOP_SLEEP_MS,
-            max_velocity_hires: DEFAULT_MAX_VELOCITY_HIRES,
-            easing_kind: EasingKind::Linear,
-            smooth_enabled: true,
-            middle_scroll_enabled: true,
-            mouse_device_path: None,
+impl OutputCurve {
+    pub(crate) const ALL: [Self; 3] = [Self::Identity, Self::EaseOut, Self::Clamp];
+
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::Identity => "identity",
+            Self::EaseOut => "ease_out",
+            Self::Clamp => "clamp",
         }
     }
-}
 
-#[derive(Debug)]
-pub(crate) struct ScrollConfig {
-    normal_wheel_gain: AtomicU64,
-    normal_wheel_damping: AtomicU64,
-    drag_wheel_gain: AtomicU64,
-    drag_wheel_damping: AtomicU64,
-    drag_deadzone_px: AtomicU64,
-    tap_max_duration_ms: AtomicU64,
-    loop_sleep_ms: AtomicU64,
-    max_velocity_hires: AtomicU64,
-    easing_kind_bits: AtomicU64,
-    smooth_enabled: AtomicBool,
-    middle_scroll_enabled: AtomicBool,
-    mouse_device_path: Mutex<Option<String>>,
-}
+    pub(crate) fn from_label(value: &str) -> Option<Self> {
+        match value {
+            "identity" => Some(Self::Identity),
+            "ease_out" => Some(Self::EaseOut),
+            "clamp" => Some(Self::Clamp),
+            _ => None,
+        }
+    }
 
-impl ScrollConfig {
-    pub fn new() -> Self {
-        let this = Self {
-            normal_wheel_gain: AtomicU64::new(DEFAULT_NORMAL_WHEEL_GAIN.to_bits()),
-            normal_wheel_damping: AtomicU64::new(DEFAULT_NORMAL_WHEEL_DAMPING.to_bits()),
-            drag_wheel_gain: AtomicU64::new(DEFAULT_DRAG_WHEEL_GAIN.to_bits()),
-            drag_wheel_damping: AtomicU64::new(DEFAULT_DRAG_WHEEL_DAMPING.to_bits()),
-            drag_deadzone_px: AtomicU64::new(DEFAULT_DRAG_DEADZONE_PX.to_bits()),
-            tap_max_duration_ms: AtomicU64::new((DEFAULT_TAP_MAX_DURATION_MS as f64).to_bits()),
-            loop_sleep_ms: AtomicU64::new((DEFAULT_LOOP_SLEEP_MS as f64).to_bits()),
-            max_velocity_hires: AtomicU64::new(DEFAULT_MAX_VELOCITY_HIRES.to_bits()),
-            easing_kind_bits: AtomicU64::new(EasingKind::Linear.to_u64()),
-            smooth_enabled: AtomicBool::new(true),
-            middle_scroll_enabled: AtomicBool::new(true),
-            mouse_device_path: Mutex::new(None),
-        };
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            1 => Self::EaseOut,
+            2 => Self::Clamp,
+            _ => Self::Identity,
+        }
+    }
 
-        let _ = this.load_from_disk();
-        let _ = this.save_to_disk();
-        this
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
     }
 
-    pub fn reset_defaults(&self) {
-        self.set_normal_wheel_gain_raw(DEFAULT_NORMAL_WHEEL_GAIN);
-        self.set_normal_wheel_damping_raw(DEFAULT_NORMAL_WHEEL_DAMPING);
-        self.set_drag_wheel_gain_raw(DEFAULT_DRAG_WHEEL_GAIN);
-        self.set_drag_wheel_damping_raw(DEFAULT_DRAG_WHEEL_DAMPING);
-        self.set_drag_deadzone_px_raw(DEFAULT_DRAG_DEADZONE_PX);
-        self.set_tap_max_duration_ms_raw(DEFAULT_TAP_MAX_DURATION_MS as f64);
-        self.set_loop_sleep_ms_raw(DEFAULT_LOOP_SLEEP_MS as f64);
-        self.set_max_velocity_hires_raw(DEFAULT_MAX_VELOCITY_HIRES);
-        self.set_easing_kind_raw(EasingKind::Linear);
-        self.set_smooth_enabled_raw(true);
-        self.set_middle_scroll_enabled_raw(true);
-        self.set_mouse_device_path_raw(None);
-        let _ = self.save_to_disk();
+    pub(crate) fn apply(self, magnitude: f64, limit: f64) -> f64 {
+        if limit <= 0.0 {
+            return magnitude;
+        }
+
+        match self {
+            Self::Identity => magnitude,
+            Self::EaseOut => limit * (magnitude / limit).tanh(),
+            Self::Clamp => magnitude.min(limit),
+        }
     }
+}
 
-    fn load_f64(atom: &AtomicU64) -> f64 {
-        f64::from_bits(atom.load(Ordering::Relaxed))
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DecayModel {
+    Exp = 0,
+    Spring = 1,
+}
+
+impl DecayModel {
+    pub(crate) const ALL: [Self; 2] = [Self::Exp, Self::Spring];
+
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::Exp => "exp",
+            Self::Spring => "spring",
+        }
     }
 
-    fn store_f64(atom: &AtomicU64, value: f64) {
-        atom.store(value.to_bits(), Ordering::Relaxed);
+    pub(crate) fn from_label(value: &str) -> Option<Self> {
+        match value {
+            "exp" => Some(Self::Exp),
+            "spring" => Some(Self::Spring),
+            _ => None,
+        }
     }
 
-    fn snapshot(&self) -> ConfigSnapshot {
-        ConfigSnapshot {
-            normal_wheel_gain: self.normal_wheel_gain(),
-            normal_wheel_damping: self.normal_wheel_damping(),
-            drag_wheel_gain: self.drag_wheel_gain(),
-            drag_wheel_damping: self.drag_wheel_damping(),
-            drag_deadzone_px: self.drag_deadzone_px(),
-            tap_max_duration_ms: self.tap_max_duration_ms(),
-            loop_sleep_ms: self.loop_sleep_ms(),
-            max_velocity_hires: self.max_velocity_hires(),
-            easing_kind: self.easing_kind(),
-            smooth_enabled: self.smooth_enabled(),
-            middle_scroll_enabled: self.middle_scroll_enabled(),
-            mouse_device_path: self.mouse_device_path(),
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            1 => Self::Spring,
+            _ => Self::Exp,
         }
     }
 
-    fn apply_snapshot(&self, snap: ConfigSnapshot) {
-        self.set_normal_wheel_gain_raw(snap.normal_wheel_gain);
-        self.set_normal_wheel_damping_raw(snap.normal_wheel_damping);
-        self.set_drag_wheel_gain_raw(snap.drag_wheel_gain);
-        self.set_drag_wheel_damping_raw(snap.drag_wheel_damping);
-        self.set_drag_deadzone_px_raw(snap.drag_deadzone_px);
-        self.set_tap_max_duration_ms_raw(snap.tap_max_duration_ms as f64);
-        self.set_loop_sleep_ms_raw(snap.loop_sleep_ms as f64);
-        self.set_max_velocity_hires_raw(snap.max_velocity_hires);
-        self.set_easing_kind_raw(snap.easing_kind);
-        self.set_smooth_enabled_raw(snap.smooth_enabled);
-        self.set_middle_scroll_enabled_raw(snap.middle_scroll_enabled);
-        self.set_mouse_device_path_raw(snap.mouse_device_path);
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
     }
+}
 
-    fn config_path() -> Option<PathBuf> {
-        #[cfg(target_os = "windows")]
-        {
-            let home = env::var_os("USERPROFILE").map(PathBuf::from)?;
-            return Some(
-                home.join("Documents")
-                    .join(CONFIG_DIR_NAME)
-                    .join(CONFIG_FILE_NAME),
-            );
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RawInputMode {
+    InputSink = 0,
+    InputSinkNoLegacy = 1,
+    ExInputSink = 2,
+}
+
+impl RawInputMode {
+    pub(crate) const ALL: [Self; 3] = [Self::InputSink, Self::InputSinkNoLegacy, Self::ExInputSink];
+
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::InputSink => "inputsink",
+            Self::InputSinkNoLegacy => "inputsink_nolegacy",
+            Self::ExInputSink => "exinputsink",
         }
+    }
 
-        #[cfg(not(target_os = "windows"))]
-        {
-            let home = env::var_os("HOME").map(PathBuf::from)?;
-            return Some(
-                home.join(".config")
-                    .join(CONFIG_DIR_NAME)
-                    .join(CONFIG_FILE_NAME),
-            );
+    pub(crate) fn from_label(value: &str) -> Option<Self> {
+        match value {
+            "inputsink" => Some(Self::InputSink),
+            "inputsink_nolegacy" => Some(Self::InputSinkNoLegacy),
+            "exinputsink" => Some(Self::ExInputSink),
+            _ => None,
         }
     }
 
-    fn format_f64(value: f64) -> String {
-        format!("{:.17}", value)
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            1 => Self::InputSinkNoLegacy,
+            2 => Self::ExInputSink,
+            _ => Self::InputSink,
+        }
     }
 
-    fn escape_string(value: &str) -> String {
-        let mut out = String::with_capacity(value.len() + 8);
-        for ch in value.chars() {
-            match ch {
-                '\\' => out.push_str("\\\\"),
-                '"' => out.push_str("\\\""),
-                '\n' => out.push_str("\\n"),
-                '\r' => out.push_str("\\r"),
-                '\t' => out.push_str("\\t"),
-                c => out.push(c),
-            }
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
+    }
+
+    pub(crate) const fn flags(self) -> u32 {
+        match self {
+            Self::InputSink => 0x0000_0100,
+            Self::InputSinkNoLegacy => 0x0000_0130,
+            Self::ExInputSink => 0x0000_1000,
         }
-        out
     }
+}
 
-    fn unescape_string(value: &str) -> Option<String> {
-        let mut out = String::with_capacity(value.len());
-        let mut chars = value.chars();
-        while let Some(ch) = chars.next() {
-            if ch != '\\' {
-                out.push(ch);
-                continue;
-            }
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutputMethod {
+    SendInput = 0,
+    /// Reaches apps that filter injected input, but skips low-level hooks and raw-input readers.
+    PostMessage = 1,
+}
 
-            let next = chars.next()?;
-            match next {
-                '\\' => out.push('\\'),
-                '"' => out.push('"'),
-                'n' => out.push('\n'),
-                'r' => out.push('\r'),
-                't' => out.push('\t'),
-                other => out.push(other),
-            }
+impl OutputMethod {
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::SendInput => "sendinput",
+            Self::PostMessage => "postmessage",
         }
-        Some(out)
     }
 
-    fn parse_bool(value: &str) -> Option<bool> {
-        match value.trim().to_ascii_lowercase().as_str() {
-            "1" | "true" | "yes" | "on" => Some(true),
-            "0" | "false" | "no" | "off" => Some(false),
+    pub(crate) fn from_label(value: &str) -> Option<Self> {
+        match value {
+            "sendinput" => Some(Self::SendInput),
+            "postmessage" => Some(Self::PostMessage),
             _ => None,
         }
     }
 
-    fn parse_f64(value: &str) -> Option<f64> {
-        value.trim().parse::<f64>().ok()
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            1 => Self::PostMessage,
+            _ => Self::SendInput,
+        }
     }
 
-    fn parse_u64(value: &str) -> Option<u64> {
-        value.trim().parse::<u64>().ok()
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
     }
+}
 
-    fn parse_optional_string(value: &str) -> Option<Option<String>> {
-        let trimmed = value.trim();
-        if trimmed.is_empty() {
-            return Some(None);
-        }
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WheelMode {
+    /// The physical wheel is swallowed and only the smoothed stream reaches apps.
+    Replace = 0,
+    /// The physical wheel passes through unchanged and momentum is layered on top.
+    Additive = 1,
+}
 
-        if trimmed == "\"\"" {
-            return Some(None);
+impl WheelMode {
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::Replace => "replace",
+            Self::Additive => "additive",
         }
+    }
 
-        if trimmed.starts_with('"') && trimmed.ends_with('"') && trimmed.len() >= 2 {
-            let inner = &trimmed[1..trimmed.len() - 1];
-            return Self::unescape_string(inner).map(|s| if s.is_empty() { None } else { Some(s) });
+    pub(crate) fn from_label(value: &str) -> Option<Self> {
+        match value {
+            "replace" => Some(Self::Replace),
+            "additive" => Some(Self::Additive),
+            _ => None,
         }
-
-        Some(Some(trimmed.to_string()))
     }
 
-    fn parse_snapshot(text: &str) -> ConfigSnapshot {
-        let mut snap = ConfigSnapshot::defaults();
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            1 => Self::Additive,
+            _ => Self::Replace,
+        }
+    }
 
-        for raw_line in text.lines() {
-            let line = raw_line.trim();
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
+    }
+}
 
-            let Some((key, value)) = line.split_once('=') else {
-                continue;
-            };
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StackMode {
+    /// Each notch adds to momentum already heading the same way, up to the velocity cap.
+    Accumulate = 0,
+    /// Each notch tops momentum up to its own speed, so steady spinning holds a steady pace.
+    Target = 1,
+}
 
-            let key = key.trim();
-            let value = value.trim();
+impl StackMode {
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::Accumulate => "accumulate",
+            Self::Target => "target",
+        }
+    }
 
-            match key {
-                "normal_wheel_gain" => {
-                    if let Some(v) = Self::parse_f64(value) {
-                        snap.normal_wheel_gain = v;
-                    }
-                }
-                "normal_wheel_damping" => {
-                    if let Some(v) = Self::parse_f64(value) {
-                        snap.normal_wheel_damping = v;
-                    }
-                }
-                "drag_wheel_gain" => {
-                    if let Some(v) = Self::parse_f64(value) {
-                        snap.drag_wheel_gain = v;
-                    }
-                }
-                "drag_wheel_damping" => {
-                    if let Some(v) = Self::parse_f64(value) {
-                        snap.drag_wheel_damping = v;
-                    }
-                }
-                "drag_deadzone_px" => {
-                    if let Some(v) = Self::parse_f64(value) {
-                        snap.drag_deadzone_px = v;
-                    }
-                }
-                "tap_max_duration_ms" => {
-                    if let Some(v) = Self::parse_u64(value) {
-                        snap.tap_max_duration_ms = v;
-                    } else if let Some(v) = Self::parse_f64(value) {
-                        snap.tap_max_duration_ms = v.max(1.0) as u64;
-                    }
-                }
-                "loop_sleep_ms" => {
-                    if let Some(v) = Self::parse_u64(value) {
-                        snap.loop_sleep_ms = v;
-                    } else if let Some(v) = Self::parse_f64(value) {
-                        snap.loop_sleep_ms = v.max(1.0) as u64;
-                    }
-                }
-                "max_velocity_hires" => {
-                    if let Some(v) = Self::parse_f64(value) {
-                        snap.max_velocity_hires = v;
-                    }
-                }
-                "easing_kind" => {
-                    if let Some(kind) = EasingKind::from_label(value.trim_matches('"')) {
-                        snap.easing_kind = kind;
-                    } else if let Ok(raw) = value.trim().parse::<u64>() {
-                        snap.easing_kind = EasingKind::from_u64(raw);
-                    }
-                }
-                "smooth_enabled" => {
-                    if let Some(v) = Self::parse_bool(value) {
-                        snap.smooth_enabled = v;
-                    }
-                }
-                "middle_scroll_enabled" => {
-                    if let Some(v) = Self::parse_bool(value) {
-                        snap.middle_scroll_enabled = v;
-                    }
-                }
-                "mouse_device_path" => {
-                    if let Some(v) = Self::parse_optional_string(value) {
-                        snap.mouse_device_path = v;
-                    }
-                }
-                _ => {}
-            }
+    pub(crate) fn from_label(value: &str) -> Option<Self> {
+        match value {
+            "accumulate" => Some(Self::Accumulate),
+            "target" => Some(Self::Target),
+            _ => None,
+        }
+    }
+
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            1 => Self::Target,
+            _ => Self::Accumulate,
         }
+    }
 
-        snap
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
     }
+}
 
-    fn load_from_disk(&self) -> io::Result<()> {
-        let Some(path) = Self::config_path() else {
-            return Ok(());
-        };
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TrayAction {
+    None = 0,
+    Show = 1,
+    Toggle = 2,
+    Quit = 3,
+}
 
-        match fs::read_to_string(&path) {
-            Ok(text) => {
-                let snap = Self::parse_snapshot(&text);
-                self.apply_snapshot(snap);
-                Ok(())
-            }
-            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
-            Err(err) => Err(err),
+impl TrayAction {
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Show => "show",
+            Self::Toggle => "toggle",
+            Self::Quit => "quit",
         }
     }
 
-    fn save_to_disk(&self) -> io::Result<()> {
-        let Some(path) = Self::config_path() else {
-            return Ok(());
-        };
+    pub(crate) fn from_label(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(Self::None),
+            "show" => Some(Self::Show),
+            "toggle" => Some(Self::Toggle),
+            "quit" => Some(Self::Quit),
+            _ => None,
+        }
+    }
 
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            1 => Self::Show,
+            2 => Self::Toggle,
+            3 => Self::Quit,
+            _ => Self::None,
         }
+    }
 
-        let snap = self.snapshot();
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
+    }
+}
 
-        let mut text = String::new();
-        text.push_str("# NimbusScroll configuration\n");
-        text.push_str("# This file is rewritten automatically by the application.\n");
-        text.push_str("# Values are loaded on startup.\n\n");
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WheelAction {
+    Scroll = 0,
+    Zoom = 1,
+    None = 2,
+}
 
-        text.push_str(&format!(
-            "normal_wheel_gain={}\n",
-            Self::format_f64(snap.normal_wheel_gain)
-        ));
-        text.push_str(&format!(
-            "normal_wheel_damping={}\n",
-            Self::format_f64(snap.normal_wheel_damping)
-        ));
-        text.push_str(&format!(
-            "drag_wheel_gain={}\n",
-            Self::format_f64(snap.drag_wheel_gain)
-        ));
-        text.push_str(&format!(
-            "drag_wheel_damping={}\n",
-            Self::format_f64(snap.drag_wheel_damping)
-        ));
-        text.push_str(&format!(
-            "drag_deadzone_px={}\n",
-            Self::format_f64(snap.drag_deadzone_px)
-        ));
-        text.push_str(&format!(
-            "tap_max_duration_ms={}\n",
-            snap.tap_max_duration_ms
-        ));
-        text.push_str(&format!("loop_sleep_ms={}\n", snap.loop_sleep_ms));
-        text.push_str(&format!(
-            "max_velocity_hires={}\n",
-            Self::format_f64(snap.max_velocity_hires)
-        ));
-        text.push_str(&format!("easing_kind={}\n", snap.easing_kind.label()));
-        text.push_str(&format!("smooth_enabled={}\n", snap.smooth_enabled));
-        text.push_str(&format!(
-            "middle_scroll_enabled={}\n",
-            snap.middle_scroll_enabled
-        ));
-        text.push_str(&format!(
-            "mouse_device_path=\"{}\"\n",
-            Self::escape_string(snap.mouse_device_path.as_deref().unwrap_or(""))
-        ));
+impl WheelAction {
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            1 => Self::Zoom,
+            2 => Self::None,
+            _ => Self::Scroll,
+        }
+    }
+
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
+    }
+}
+
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TurboButton {
+    Off = 0,
+    Back = 1,
+    Forward = 2,
+}
+
+impl TurboButton {
+    pub(crate) const ALL: [Self; 3] = [Self::Off, Self::Back, Self::Forward];
+
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Back => "back",
+            Self::Forward => "forward",
+        }
+    }
+
+    pub(crate) fn from_label(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(Self::Off),
+            "back" => Some(Self::Back),
+            "forward" => Some(Self::Forward),
+            _ => None,
+        }
+    }
+
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            1 => Self::Back,
+            2 => Self::Forward,
+            _ => Self::Off,
+        }
+    }
+
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
+    }
+
+    pub(crate) const fn matches(self, key: ScrollKey) -> bool {
+        match self {
+            Self::Off => false,
+            Self::Back => matches!(key, ScrollKey::Side | ScrollKey::Back),
+            Self::Forward => matches!(key, ScrollKey::Extra | ScrollKey::Forward),
+        }
+    }
+}
+
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ButtonAction {
+    None = 0,
+    Home = 1,
+    End = 2,
+    PageUp = 3,
+    PageDown = 4,
+}
+
+impl ButtonAction {
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Home => "home",
+            Self::End => "end",
+            Self::PageUp => "pageup",
+            Self::PageDown => "pagedown",
+        }
+    }
+
+    pub(crate) fn from_label(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(Self::None),
+            "home" => Some(Self::Home),
+            "end" => Some(Self::End),
+            "pageup" => Some(Self::PageUp),
+            "pagedown" => Some(Self::PageDown),
+            _ => None,
+        }
+    }
+
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            1 => Self::Home,
+            2 => Self::End,
+            3 => Self::PageUp,
+            4 => Self::PageDown,
+            _ => Self::None,
+        }
+    }
+
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
+    }
+
+    /// The key tapped in place of the button, if any.
+    pub(crate) const fn key(self) -> Option<ScrollKey> {
+        match self {
+            Self::None => None,
+            Self::Home => Some(ScrollKey::Home),
+            Self::End => Some(ScrollKey::End),
+            Self::PageUp => Some(ScrollKey::PageUp),
+            Self::PageDown => Some(ScrollKey::PageDown),
+        }
+    }
+}
+
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AxisOrder {
+    VerticalFirst = 0,
+    HorizontalFirst = 1,
+    /// Swaps which axis leads on every tick that emits, so neither is always sent second.
+    Alternate = 2,
+}
+
+impl AxisOrder {
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::VerticalFirst => "vertical_first",
+            Self::HorizontalFirst => "horizontal_first",
+            Self::Alternate => "alternate",
+        }
+    }
+
+    pub(crate) fn from_label(value: &str) -> Option<Self> {
+        match value {
+            "vertical_first" => Some(Self::VerticalFirst),
+            "horizontal_first" => Some(Self::HorizontalFirst),
+            "alternate" => Some(Self::Alternate),
+            _ => None,
+        }
+    }
+
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            1 => Self::HorizontalFirst,
+            2 => Self::Alternate,
+            _ => Self::VerticalFirst,
+        }
+    }
+
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
+    }
+}
+
+fn config_key_doc(key: &str) -> Option<&'static str> {
+    let doc = match key {
+        "normal_wheel_gain" => "Velocity added per wheel notch (negative reverses the axis).",
+        "normal_wheel_gain_h" => {
+            "Velocity added per horizontal wheel notch (negative reverses the axis)."
+        }
+        "normal_wheel_damping" => "Wheel velocity kept each tick (0..1, 1 = no friction).",
+        "drag_wheel_gain" => "Velocity added per pixel of middle-button drag.",
+        "drag_wheel_gain_h" => "Velocity added per pixel of horizontal drag.",
+        "drag_wheel_damping" => "Drag velocity kept each tick (0..1, 1 = no friction).",
+        "drag_deadzone_px" => "Pointer travel before a middle press turns into a drag.",
+        "tap_max_duration_ms" => "Longest middle press still treated as a click.",
+        "loop_sleep_ms" => "Engine loop interval while idle.",
+        "max_velocity_hires" => "Upper bound for momentum velocity in hi-res units per tick.",
+        "easing_kind" => "Easing applied to frame time (linear, quad_in, ...).",
+        "smooth_enabled" => "Enable smooth scrolling.",
+        "middle_scroll_enabled" => "Enable middle-button drag scrolling.",
+        "output_curve" => "Shaping of per-tick output: identity, ease_out or clamp.",
+        "broadcast_scroll" => "Mirror wheel scrolling to the top window of every other monitor.",
+        "clip_grace_ms" => "Minimum time between cursor re-clips while dragging.",
+        "lang" => "Interface language: en or ru.",
+        "auto_disable_fullscreen" => "Pass input through while a fullscreen app has focus.",
+        "decay_model" => "Momentum model: exp or spring.",
+        "spring_stiffness" => "Spring model: pull toward the scroll target.",
+        "spring_damping" => "Spring model: resistance against velocity.",
+        "stop_threshold" => "Momentum velocity below which scrolling stops.",
+        "emit_threshold" => "Pending delta below which nothing is sent yet; the rest is deferred.",
+        "anchor_icon" => "Show a marker at the drag origin while drag scrolling.",
+        "raw_input_mode" => "Raw input registration: inputsink, inputsink_nolegacy or exinputsink.",
+        "scroll_under_cursor" => {
+            "Send wheel input to the window under the cursor when it isn't focused."
+        }
+        "tray_icon_path" => "Custom tray icon file; empty uses the default icon.",
+        "log_level" => "Log verbosity: error, info or trace.",
+        "hires_timer" => "Pace the engine loop with a high-resolution waitable timer (Windows).",
+        "smooth_horizontal" => "Smooth horizontal scrolling; false passes it through.",
+        "output_method" => {
+            "Windows wheel output: sendinput, or postmessage to the window under the cursor."
+        }
+        "snap_to_edge" => "Turn a fast middle-drag flick into Ctrl+Home or Ctrl+End.",
+        "snap_velocity" => "Release velocity (hi-res units per tick) that counts as a flick.",
+        "input_tag" => {
+            "Extra-info tag stamped on injected input, 1 to 4294967295 (Windows)."
+        }
+        "turbo_button" => "Side button that boosts scrolling while held: off, back or forward.",
+        "turbo_factor" => "Speed multiplier applied while the turbo button is held.",
+        "min_notch_travel" => "Minimum travel per wheel notch, in notches (0 = off).",
+        "sleep_when_idle" => "Wait for input instead of polling while nothing is scrolling.",
+        "disable_for_classes" => "Comma-separated window classes where scrolling is left alone.",
+        "drag_smoothing" => "Low-pass for small middle-drag steps, e.g. trackpoints (0 = off).",
+        "stop_on_focus_change" => "Drop remaining momentum when the focused window changes.",
+        "drag_soft_start_px" => "Drag travel over which sensitivity ramps up to full (0 = off).",
+        "preview_settings" => "Hold settings edits as a preview until Save; Cancel reverts.",
+        "invert_x" => "Reverse horizontal scrolling.",
+        "invert_y" => "Reverse vertical scrolling.",
+        "invert_x_for_classes" => {
+            "Comma-separated window classes where horizontal direction is flipped from invert_x."
+        }
+        "invert_y_for_classes" => {
+            "Comma-separated window classes where vertical direction is flipped from invert_y."
+        }
+        "tray_status_delay_ms" => {
+            "How long pause state must hold before the tray tooltip follows it."
+        }
+        "single_instance" => {
+            "A second launch opens the running instance's settings instead of starting."
+        }
+        "max_coast_ms" => {
+            "Longest a coast may run after the last input before it stops (0 = unlimited)."
+        }
+        "quantize_to_lines" => {
+            "Emit whole OS scroll lines per event for apps without smooth scrolling (Windows)."
+        }
+        "batch_ms" => "Collect smooth output for this long between injections (0 = every tick).",
+        "axis_order" => {
+            "Which wheel axis is sent first in a tick: vertical_first, horizontal_first or alternate."
+        }
+        "pulse" => "Flash the tray icon each time smoothed output crosses a whole wheel notch.",
+        "last_preset" => {
+            "Preset re-applied at startup, set when a preset is chosen; empty loads the base settings."
+        }
+        "back_button_action" => {
+            "Key sent when the back side button is pressed: none, home, end, pageup or pagedown."
+        }
+        "forward_button_action" => {
+            "Key sent when the forward side button is pressed: none, home, end, pageup or pagedown."
+        }
+        "max_continuous_scroll_ms" => {
+            "Stop momentum after scrolling one way this long, as if at a document edge (0 = unlimited)."
+        }
+        "low_latency_first_emit" => {
+            "Send the first notch after a rest at once instead of on the next engine tick."
+        }
+        "temp_invert_key" => {
+            "Key that reverses scrolling while held: a VK code on Windows, an evdev code on Linux (0 = off)."
+        }
+        "inertia_carryover" => {
+            "Share of leftover same-direction momentum kept when a new notch arrives (1 = add as is)."
+        }
+        "log_format" => "Log line format: text, or json for one object per line.",
+        "auto_scroll" => {
+            "A quick middle click starts autoscroll, paced by the pointer's distance from the click."
+        }
+        "auto_scroll_speed" => {
+            "Autoscroll speed in hi-res units per second for each pixel past the dead zone."
+        }
+        "wheel_mode" => {
+            "replace swallows the physical wheel; additive passes it through and adds momentum on top."
+        }
+        "soft_land" => {
+            "Below soft_land_velocity, ramp momentum linearly to zero instead of snapping it off."
+        }
+        "soft_land_velocity" => {
+            "Momentum velocity, in hi-res units per tick, below which soft_land takes over."
+        }
+        "per_axis_momentum" => {
+            "Shape each axis's momentum on its own instead of as one diagonal vector."
+        }
+        "decay_velocity_scale" => {
+            "Speeds up decay at high velocity: loss = (1 - damping) * (1 + k * |velocity|)."
+        }
+        "tray_single" => {
+            "Left click on the tray icon: none, show (settings), toggle (pause) or quit."
+        }
+        "tray_double" => "Double click on the tray icon: none, show, toggle or quit. Windows only.",
+        "disable_file" => {
+            "Sentinel file; while it exists, input passes through untouched as if paused."
+        }
+        "flick_notch_rate" => {
+            "Wheel speed (notches/s) needed for momentum; slower notches scroll one step (0 = off)"
+        }
+        "scroll_sound" => {
+            "Play a short click each time smoothed output crosses a whole wheel notch."
+        }
+        "scroll_sound_file" => "WAV file for scroll_sound; empty uses the system navigation click.",
+        "max_notches_per_tick" => {
+            "Most output per axis in one tick, in notches; the rest follows on later ticks (0 = off)"
+        }
+        "fine_key" => {
+            "Key that slows scrolling by fine_factor while held: a VK code on Windows, an evdev code on Linux (0 = off)."
+        }
+        "fine_factor" => "Speed multiplier applied while fine_key is held.",
+        "zoom_for_classes" => {
+            "Comma-separated window classes where the wheel zooms with Ctrl+=/Ctrl+- instead of scrolling."
+        }
+        "wheel_off_for_classes" => {
+            "Comma-separated window classes where the wheel is swallowed and does nothing."
+        }
+        "startup_delay_ms" => {
+            "Longest wait for the taskbar before adding the tray icon; stops early once it is up."
+        }
+        "edge_detect" => {
+            "Stop momentum once the standard scroll bar under the cursor reaches its end (Windows only)."
+        }
+        "input_start_delay_ms" => {
+            "Wait before hooking the mouse and keyboard, for startups crowded with other input tools."
+        }
+        "stack_mode" => {
+            "Same-direction notches while coasting: accumulate adds to the speed, target tops it up to the notch's."
+        }
+        "conflict_check" => {
+            "Warn at startup when other smooth-scroll software or a slow mouse hook is found (Windows)."
+        }
+        "conflict_apps" => {
+            "Exe names, window classes or mutex names conflict_check looks for; empty uses a built-in list."
+        }
+        "smooth_vertical" => "Smooth vertical scrolling; false passes it through.",
+        "paused" => {
+            "Scrolling was paused at exit; set automatically, and the app starts paused when true."
+        }
+        "start_paused" => "Always start paused, whatever the state at exit.",
+        "pause_cooldown_ms" => {
+            "Toggles this soon after a pause or resume are collapsed into their final state."
+        }
+        "mouse_device_path" => "Input device to read from; empty selects automatically.",
+        _ => return None,
+    };
+    Some(doc)
+}
+
+#[derive(Debug, Clone)]
+struct ConfigSnapshot {
+    normal_wheel_gain: f64,
+    normal_wheel_gain_h: f64,
+    normal_wheel_damping: f64,
+    drag_wheel_gain: f64,
+    drag_wheel_gain_h: f64,
+    drag_wheel_damping: f64,
+    drag_deadzone_px: f64,
+    tap_max_duration_ms: u64,
+    loop_sleep_ms: u64,
+    max_velocity_hires: f64,
+    easing_kind: EasingKind,
+    smooth_enabled: bool,
+    middle_scroll_enabled: bool,
+    output_curve: OutputCurve,
+    broadcast_scroll: bool,
+    clip_grace_ms: u64,
+    lang: Lang,
+    auto_disable_fullscreen: bool,
+    decay_model: DecayModel,
+    spring_stiffness: f64,
+    spring_damping: f64,
+    stop_threshold: f64,
+    emit_threshold: f64,
+    anchor_icon: bool,
+    raw_input_mode: RawInputMode,
+    scroll_under_cursor: bool,
+    tray_icon_path: Option<String>,
+    log_level: LogLevel,
+    hires_timer: bool,
+    smooth_horizontal: bool,
+    output_method: OutputMethod,
+    snap_to_edge: bool,
+    snap_velocity: f64,
+    input_tag: u64,
+    turbo_button: TurboButton,
+    turbo_factor: f64,
+    min_notch_travel: f64,
+    sleep_when_idle: bool,
+    disable_for_classes: Option<String>,
+    drag_smoothing: f64,
+    stop_on_focus_change: bool,
+    drag_soft_start_px: f64,
+    preview_settings: bool,
+    invert_x: bool,
+    invert_y: bool,
+    invert_x_for_classes: Option<String>,
+    invert_y_for_classes: Option<String>,
+    tray_status_delay_ms: u64,
+    single_instance: bool,
+    max_coast_ms: u64,
+    quantize_to_lines: bool,
+    batch_ms: u64,
+    axis_order: AxisOrder,
+    pulse: bool,
+    last_preset: Option<String>,
+    back_button_action: ButtonAction,
+    forward_button_action: ButtonAction,
+    max_continuous_scroll_ms: u64,
+    low_latency_first_emit: bool,
+    temp_invert_key: u64,
+    inertia_carryover: f64,
+    log_format: LogFormat,
+    auto_scroll: bool,
+    auto_scroll_speed: f64,
+    wheel_mode: WheelMode,
+    soft_land: bool,
+    soft_land_velocity: f64,
+    per_axis_momentum: bool,
+    decay_velocity_scale: f64,
+    tray_single: TrayAction,
+    tray_double: TrayAction,
+    disable_file: Option<String>,
+    flick_notch_rate: f64,
+    scroll_sound: bool,
+    scroll_sound_file: Option<String>,
+    max_notches_per_tick: f64,
+    fine_key: u64,
+    fine_factor: f64,
+    zoom_for_classes: Option<String>,
+    wheel_off_for_classes: Option<String>,
+    startup_delay_ms: u64,
+    edge_detect: bool,
+    input_start_delay_ms: u64,
+    stack_mode: StackMode,
+    conflict_check: bool,
+    conflict_apps: Option<String>,
+    smooth_vertical: bool,
+    paused: bool,
+    start_paused: bool,
+    pause_cooldown_ms: u64,
+    mouse_device_path: Option<String>,
+}
+
+impl ConfigSnapshot {
+    fn defaults() -> Self {
+        Self {
+            normal_wheel_gain: DEFAULT_NORMAL_WHEEL_GAIN,
+            normal_wheel_gain_h: DEFAULT_NORMAL_WHEEL_GAIN,
+            normal_wheel_damping: DEFAULT_NORMAL_WHEEL_DAMPING,
+            drag_wheel_gain: DEFAULT_DRAG_WHEEL_GAIN,
+            drag_wheel_gain_h: DEFAULT_DRAG_WHEEL_GAIN,
+            drag_wheel_damping: DEFAULT_DRAG_WHEEL_DAMPING,
+            drag_deadzone_px: DEFAULT_DRAG_DEADZONE_PX,
+            tap_max_duration_ms: DEFAULT_TAP_MAX_DURATION_MS,
+            loop_sleep_ms: DEFAULT_LOOP_SLEEP_MS,
+            max_velocity_hires: DEFAULT_MAX_VELOCITY_HIRES,
+            easing_kind: EasingKind::Linear,
+            smooth_enabled: true,
+            middle_scroll_enabled: true,
+            output_curve: OutputCurve::Identity,
+            broadcast_scroll: false,
+            clip_grace_ms: DEFAULT_CLIP_GRACE_MS,
+            lang: Lang::English,
+            auto_disable_fullscreen: false,
+            decay_model: DecayModel::Exp,
+            spring_stiffness: DEFAULT_SPRING_STIFFNESS,
+            spring_damping: DEFAULT_SPRING_DAMPING,
+            stop_threshold: DEFAULT_STOP_THRESHOLD,
+            emit_threshold: DEFAULT_EMIT_THRESHOLD,
+            anchor_icon: false,
+            raw_input_mode: RawInputMode::InputSink,
+            scroll_under_cursor: false,
+            tray_icon_path: None,
+            log_level: LogLevel::Info,
+            hires_timer: false,
+            smooth_horizontal: true,
+            output_method: OutputMethod::SendInput,
+            snap_to_edge: false,
+            snap_velocity: DEFAULT_SNAP_VELOCITY,
+            input_tag: DEFAULT_INPUT_TAG,
+            turbo_button: TurboButton::Off,
+            turbo_factor: DEFAULT_TURBO_FACTOR,
+            min_notch_travel: DEFAULT_MIN_NOTCH_TRAVEL,
+            sleep_when_idle: true,
+            disable_for_classes: None,
+            drag_smoothing: DEFAULT_DRAG_SMOOTHING,
+            stop_on_focus_change: true,
+            drag_soft_start_px: DEFAULT_DRAG_SOFT_START_PX,
+            preview_settings: false,
+            invert_x: false,
+            invert_y: false,
+            invert_x_for_classes: None,
+            invert_y_for_classes: None,
+            tray_status_delay_ms: DEFAULT_TRAY_STATUS_DELAY_MS,
+            single_instance: true,
+            max_coast_ms: DEFAULT_MAX_COAST_MS,
+            quantize_to_lines: false,
+            batch_ms: DEFAULT_BATCH_MS,
+            axis_order: AxisOrder::VerticalFirst,
+            pulse: false,
+            last_preset: None,
+            back_button_action: ButtonAction::None,
+            forward_button_action: ButtonAction::None,
+            max_continuous_scroll_ms: DEFAULT_MAX_CONTINUOUS_SCROLL_MS,
+            low_latency_first_emit: false,
+            temp_invert_key: DEFAULT_TEMP_INVERT_KEY,
+            inertia_carryover: DEFAULT_INERTIA_CARRYOVER,
+            log_format: LogFormat::Text,
+            auto_scroll: false,
+            auto_scroll_speed: DEFAULT_AUTO_SCROLL_SPEED,
+            wheel_mode: WheelMode::Replace,
+            soft_land: false,
+            soft_land_velocity: DEFAULT_SOFT_LAND_VELOCITY,
+            per_axis_momentum: false,
+            decay_velocity_scale: DEFAULT_DECAY_VELOCITY_SCALE,
+            tray_single: TrayAction::None,
+            tray_double: TrayAction::None,
+            disable_file: None,
+            flick_notch_rate: DEFAULT_FLICK_NOTCH_RATE,
+            scroll_sound: false,
+            scroll_sound_file: None,
+            max_notches_per_tick: DEFAULT_MAX_NOTCHES_PER_TICK,
+            fine_key: DEFAULT_FINE_KEY,
+            fine_factor: DEFAULT_FINE_FACTOR,
+            zoom_for_classes: None,
+            wheel_off_for_classes: None,
+            startup_delay_ms: DEFAULT_STARTUP_DELAY_MS,
+            edge_detect: false,
+            input_start_delay_ms: DEFAULT_INPUT_START_DELAY_MS,
+            stack_mode: StackMode::Accumulate,
+            conflict_check: true,
+            conflict_apps: None,
+            smooth_vertical: true,
+            paused: false,
+            start_paused: false,
+            pause_cooldown_ms: DEFAULT_PAUSE_COOLDOWN_MS,
+            mouse_device_path: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ScrollConfig {
+    normal_wheel_gain: AtomicU64,
+    normal_wheel_gain_h: AtomicU64,
+    normal_wheel_damping: AtomicU64,
+    drag_wheel_gain: AtomicU64,
+    drag_wheel_gain_h: AtomicU64,
+    drag_wheel_damping: AtomicU64,
+    drag_deadzone_px: AtomicU64,
+    tap_max_duration_ms: AtomicU64,
+    loop_sleep_ms: AtomicU64,
+    max_velocity_hires: AtomicU64,
+    easing_kind_bits: AtomicU64,
+    smooth_enabled: AtomicBool,
+    middle_scroll_enabled: AtomicBool,
+    output_curve_bits: AtomicU64,
+    broadcast_scroll: AtomicBool,
+    clip_grace_ms: AtomicU64,
+    lang_bits: AtomicU64,
+    auto_disable_fullscreen: AtomicBool,
+    decay_model_bits: AtomicU64,
+    spring_stiffness: AtomicU64,
+    spring_damping: AtomicU64,
+    stop_threshold: AtomicU64,
+    emit_threshold: AtomicU64,
+    anchor_icon: AtomicBool,
+    raw_input_mode_bits: AtomicU64,
+    scroll_under_cursor: AtomicBool,
+    tray_icon_path: Mutex<Option<String>>,
+    log_level_bits: AtomicU64,
+    hires_timer: AtomicBool,
+    smooth_horizontal: AtomicBool,
+    output_method_bits: AtomicU64,
+    snap_to_edge: AtomicBool,
+    snap_velocity: AtomicU64,
+    input_tag: AtomicU64,
+    turbo_button_bits: AtomicU64,
+    turbo_factor: AtomicU64,
+    min_notch_travel: AtomicU64,
+    sleep_when_idle: AtomicBool,
+    disable_for_classes: Mutex<Option<String>>,
+    drag_smoothing: AtomicU64,
+    stop_on_focus_change: AtomicBool,
+    drag_soft_start_px: AtomicU64,
+    preview_settings: AtomicBool,
+    invert_x: AtomicBool,
+    invert_y: AtomicBool,
+    invert_x_for_classes: Mutex<Option<String>>,
+    invert_y_for_classes: Mutex<Option<String>>,
+    tray_status_delay_ms: AtomicU64,
+    single_instance: AtomicBool,
+    max_coast_ms: AtomicU64,
+    quantize_to_lines: AtomicBool,
+    batch_ms: AtomicU64,
+    axis_order_bits: AtomicU64,
+    pulse: AtomicBool,
+    last_preset: Mutex<Option<String>>,
+    back_button_action_bits: AtomicU64,
+    forward_button_action_bits: AtomicU64,
+    max_continuous_scroll_ms: AtomicU64,
+    low_latency_first_emit: AtomicBool,
+    temp_invert_key: AtomicU64,
+    inertia_carryover: AtomicU64,
+    log_format_bits: AtomicU64,
+    auto_scroll: AtomicBool,
+    auto_scroll_speed: AtomicU64,
+    wheel_mode_bits: AtomicU64,
+    soft_land: AtomicBool,
+    soft_land_velocity: AtomicU64,
+    per_axis_momentum: AtomicBool,
+    decay_velocity_scale: AtomicU64,
+    tray_single_bits: AtomicU64,
+    tray_double_bits: AtomicU64,
+    disable_file: Mutex<Option<String>>,
+    flick_notch_rate: AtomicU64,
+    scroll_sound: AtomicBool,
+    scroll_sound_file: Mutex<Option<String>>,
+    max_notches_per_tick: AtomicU64,
+    fine_key: AtomicU64,
+    fine_factor: AtomicU64,
+    zoom_for_classes: Mutex<Option<String>>,
+    wheel_off_for_classes: Mutex<Option<String>>,
+    startup_delay_ms: AtomicU64,
+    edge_detect: AtomicBool,
+    input_start_delay_ms: AtomicU64,
+    stack_mode_bits: AtomicU64,
+    conflict_check: AtomicBool,
+    conflict_apps: Mutex<Option<String>>,
+    smooth_vertical: AtomicBool,
+    paused: AtomicBool,
+    start_paused: AtomicBool,
+    pause_cooldown_ms: AtomicU64,
+    mouse_device_path: Mutex<Option<String>>,
+    preview_backup: Mutex<Option<ConfigSnapshot>>,
+    // Settings in use before the active preset: what `restore_pre_preset` goes back to, and
+    // what the file keeps as its base section meanwhile.
+    preset_backup: Mutex<Option<ConfigSnapshot>>,
+    presets: Mutex<Vec<(String, ConfigSnapshot)>>,
+    // Serializes multi-field writers and disk saves against each other. Getters read the atomics
+    // without it, so the scroll loop can still see a half-applied update for one tick.
+    write_lock: Mutex<()>,
+    save_failure_reported: AtomicBool,
+    // File lines for keys overridden from the environment, written back in their place.
+    env_base: Mutex<Vec<String>>,
+    persistent: bool,
+}
+
+impl ScrollConfig {
+    pub fn new() -> Self {
+        let this = Self::with_defaults(true);
+        let _ = this.load_from_disk();
+        let _ = this.save_to_disk();
+        this
+    }
+
+    /// Settings that never touch the config file, for embedding the engine.
+    pub fn in_memory() -> Self {
+        Self::with_defaults(false)
+    }
+
+    fn with_defaults(persistent: bool) -> Self {
+        Self {
+            normal_wheel_gain: AtomicU64::new(DEFAULT_NORMAL_WHEEL_GAIN.to_bits()),
+            normal_wheel_gain_h: AtomicU64::new(DEFAULT_NORMAL_WHEEL_GAIN.to_bits()),
+            normal_wheel_damping: AtomicU64::new(DEFAULT_NORMAL_WHEEL_DAMPING.to_bits()),
+            drag_wheel_gain: AtomicU64::new(DEFAULT_DRAG_WHEEL_GAIN.to_bits()),
+            drag_wheel_gain_h: AtomicU64::new(DEFAULT_DRAG_WHEEL_GAIN.to_bits()),
+            drag_wheel_damping: AtomicU64::new(DEFAULT_DRAG_WHEEL_DAMPING.to_bits()),
+            drag_deadzone_px: AtomicU64::new(DEFAULT_DRAG_DEADZONE_PX.to_bits()),
+            tap_max_duration_ms: AtomicU64::new((DEFAULT_TAP_MAX_DURATION_MS as f64).to_bits()),
+            loop_sleep_ms: AtomicU64::new((DEFAULT_LOOP_SLEEP_MS as f64).to_bits()),
+            max_velocity_hires: AtomicU64::new(DEFAULT_MAX_VELOCITY_HIRES.to_bits()),
+            easing_kind_bits: AtomicU64::new(EasingKind::Linear.to_u64()),
+            smooth_enabled: AtomicBool::new(true),
+            middle_scroll_enabled: AtomicBool::new(true),
+            output_curve_bits: AtomicU64::new(OutputCurve::Identity.to_u64()),
+            broadcast_scroll: AtomicBool::new(false),
+            clip_grace_ms: AtomicU64::new((DEFAULT_CLIP_GRACE_MS as f64).to_bits()),
+            lang_bits: AtomicU64::new(Lang::English.to_u64()),
+            auto_disable_fullscreen: AtomicBool::new(false),
+            decay_model_bits: AtomicU64::new(DecayModel::Exp.to_u64()),
+            spring_stiffness: AtomicU64::new(DEFAULT_SPRING_STIFFNESS.to_bits()),
+            spring_damping: AtomicU64::new(DEFAULT_SPRING_DAMPING.to_bits()),
+            stop_threshold: AtomicU64::new(DEFAULT_STOP_THRESHOLD.to_bits()),
+            emit_threshold: AtomicU64::new(DEFAULT_EMIT_THRESHOLD.to_bits()),
+            anchor_icon: AtomicBool::new(false),
+            raw_input_mode_bits: AtomicU64::new(RawInputMode::InputSink.to_u64()),
+            scroll_under_cursor: AtomicBool::new(false),
+            tray_icon_path: Mutex::new(None),
+            log_level_bits: AtomicU64::new(LogLevel::Info.to_u64()),
+            hires_timer: AtomicBool::new(false),
+            smooth_horizontal: AtomicBool::new(true),
+            output_method_bits: AtomicU64::new(OutputMethod::SendInput.to_u64()),
+            snap_to_edge: AtomicBool::new(false),
+            snap_velocity: AtomicU64::new(DEFAULT_SNAP_VELOCITY.to_bits()),
+            input_tag: AtomicU64::new((DEFAULT_INPUT_TAG as f64).to_bits()),
+            turbo_button_bits: AtomicU64::new(TurboButton::Off.to_u64()),
+            turbo_factor: AtomicU64::new(DEFAULT_TURBO_FACTOR.to_bits()),
+            min_notch_travel: AtomicU64::new(DEFAULT_MIN_NOTCH_TRAVEL.to_bits()),
+            sleep_when_idle: AtomicBool::new(true),
+            disable_for_classes: Mutex::new(None),
+            drag_smoothing: AtomicU64::new(DEFAULT_DRAG_SMOOTHING.to_bits()),
+            stop_on_focus_change: AtomicBool::new(true),
+            drag_soft_start_px: AtomicU64::new(DEFAULT_DRAG_SOFT_START_PX.to_bits()),
+            preview_settings: AtomicBool::new(false),
+            invert_x: AtomicBool::new(false),
+            invert_y: AtomicBool::new(false),
+            invert_x_for_classes: Mutex::new(None),
+            invert_y_for_classes: Mutex::new(None),
+            tray_status_delay_ms: AtomicU64::new((DEFAULT_TRAY_STATUS_DELAY_MS as f64).to_bits()),
+            single_instance: AtomicBool::new(true),
+            max_coast_ms: AtomicU64::new((DEFAULT_MAX_COAST_MS as f64).to_bits()),
+            quantize_to_lines: AtomicBool::new(false),
+            batch_ms: AtomicU64::new((DEFAULT_BATCH_MS as f64).to_bits()),
+            axis_order_bits: AtomicU64::new(AxisOrder::VerticalFirst.to_u64()),
+            pulse: AtomicBool::new(false),
+            last_preset: Mutex::new(None),
+            back_button_action_bits: AtomicU64::new(ButtonAction::None.to_u64()),
+            forward_button_action_bits: AtomicU64::new(ButtonAction::None.to_u64()),
+            max_continuous_scroll_ms: AtomicU64::new(
+                (DEFAULT_MAX_CONTINUOUS_SCROLL_MS as f64).to_bits(),
+            ),
+            low_latency_first_emit: AtomicBool::new(false),
+            temp_invert_key: AtomicU64::new((DEFAULT_TEMP_INVERT_KEY as f64).to_bits()),
+            inertia_carryover: AtomicU64::new(DEFAULT_INERTIA_CARRYOVER.to_bits()),
+            log_format_bits: AtomicU64::new(LogFormat::Text.to_u64()),
+            auto_scroll: AtomicBool::new(false),
+            auto_scroll_speed: AtomicU64::new(DEFAULT_AUTO_SCROLL_SPEED.to_bits()),
+            wheel_mode_bits: AtomicU64::new(WheelMode::Replace.to_u64()),
+            soft_land: AtomicBool::new(false),
+            soft_land_velocity: AtomicU64::new(DEFAULT_SOFT_LAND_VELOCITY.to_bits()),
+            per_axis_momentum: AtomicBool::new(false),
+            decay_velocity_scale: AtomicU64::new(DEFAULT_DECAY_VELOCITY_SCALE.to_bits()),
+            tray_single_bits: AtomicU64::new(TrayAction::None.to_u64()),
+            tray_double_bits: AtomicU64::new(TrayAction::None.to_u64()),
+            disable_file: Mutex::new(None),
+            flick_notch_rate: AtomicU64::new(DEFAULT_FLICK_NOTCH_RATE.to_bits()),
+            scroll_sound: AtomicBool::new(false),
+            scroll_sound_file: Mutex::new(None),
+            max_notches_per_tick: AtomicU64::new(DEFAULT_MAX_NOTCHES_PER_TICK.to_bits()),
+            fine_key: AtomicU64::new((DEFAULT_FINE_KEY as f64).to_bits()),
+            fine_factor: AtomicU64::new(DEFAULT_FINE_FACTOR.to_bits()),
+            zoom_for_classes: Mutex::new(None),
+            wheel_off_for_classes: Mutex::new(None),
+            startup_delay_ms: AtomicU64::new((DEFAULT_STARTUP_DELAY_MS as f64).to_bits()),
+            edge_detect: AtomicBool::new(false),
+            input_start_delay_ms: AtomicU64::new((DEFAULT_INPUT_START_DELAY_MS as f64).to_bits()),
+            stack_mode_bits: AtomicU64::new(StackMode::Accumulate.to_u64()),
+            conflict_check: AtomicBool::new(true),
+            conflict_apps: Mutex::new(None),
+            smooth_vertical: AtomicBool::new(true),
+            paused: AtomicBool::new(false),
+            start_paused: AtomicBool::new(false),
+            pause_cooldown_ms: AtomicU64::new((DEFAULT_PAUSE_COOLDOWN_MS as f64).to_bits()),
+            mouse_device_path: Mutex::new(None),
+            preview_backup: Mutex::new(None),
+            preset_backup: Mutex::new(None),
+            presets: Mutex::new(Vec::new()),
+            write_lock: Mutex::new(()),
+            save_failure_reported: AtomicBool::new(false),
+            env_base: Mutex::new(Vec::new()),
+            persistent,
+        }
+    }
+
+    pub fn reset_defaults(&self) {
+        let guard = self.write_lock.lock();
+        self.set_normal_wheel_gain_raw(DEFAULT_NORMAL_WHEEL_GAIN);
+        self.set_normal_wheel_gain_h_raw(DEFAULT_NORMAL_WHEEL_GAIN);
+        self.set_normal_wheel_damping_raw(DEFAULT_NORMAL_WHEEL_DAMPING);
+        self.set_drag_wheel_gain_raw(DEFAULT_DRAG_WHEEL_GAIN);
+        self.set_drag_wheel_gain_h_raw(DEFAULT_DRAG_WHEEL_GAIN);
+        self.set_drag_wheel_damping_raw(DEFAULT_DRAG_WHEEL_DAMPING);
+        self.set_drag_deadzone_px_raw(DEFAULT_DRAG_DEADZONE_PX);
+        self.set_tap_max_duration_ms_raw(DEFAULT_TAP_MAX_DURATION_MS as f64);
+        self.set_loop_sleep_ms_raw(DEFAULT_LOOP_SLEEP_MS as f64);
+        self.set_max_velocity_hires_raw(DEFAULT_MAX_VELOCITY_HIRES);
+        self.set_easing_kind_raw(EasingKind::Linear);
+        self.set_smooth_enabled_raw(true);
+        self.set_middle_scroll_enabled_raw(true);
+        self.set_output_curve_raw(OutputCurve::Identity);
+        self.set_broadcast_scroll_raw(false);
+        self.set_clip_grace_ms_raw(DEFAULT_CLIP_GRACE_MS as f64);
+        self.set_auto_disable_fullscreen_raw(false);
+        self.set_decay_model_raw(DecayModel::Exp);
+        self.set_spring_stiffness_raw(DEFAULT_SPRING_STIFFNESS);
+        self.set_spring_damping_raw(DEFAULT_SPRING_DAMPING);
+        self.set_stop_threshold_raw(DEFAULT_STOP_THRESHOLD);
+        self.set_emit_threshold_raw(DEFAULT_EMIT_THRESHOLD);
+        self.set_anchor_icon_raw(false);
+        self.set_raw_input_mode_raw(RawInputMode::InputSink);
+        self.set_scroll_under_cursor_raw(false);
+        self.set_tray_icon_path_raw(None);
+        self.set_log_level_raw(LogLevel::Info);
+        self.set_hires_timer_raw(false);
+        self.set_smooth_horizontal_raw(true);
+        self.set_output_method_raw(OutputMethod::SendInput);
+        self.set_snap_to_edge_raw(false);
+        self.set_snap_velocity_raw(DEFAULT_SNAP_VELOCITY);
+        self.set_input_tag_raw(DEFAULT_INPUT_TAG as f64);
+        self.set_turbo_button_raw(TurboButton::Off);
+        self.set_turbo_factor_raw(DEFAULT_TURBO_FACTOR);
+        self.set_min_notch_travel_raw(DEFAULT_MIN_NOTCH_TRAVEL);
+        self.set_sleep_when_idle_raw(true);
+        self.set_disable_for_classes_raw(None);
+        self.set_drag_smoothing_raw(DEFAULT_DRAG_SMOOTHING);
+        self.set_stop_on_focus_change_raw(true);
+        self.set_drag_soft_start_px_raw(DEFAULT_DRAG_SOFT_START_PX);
+        self.set_preview_settings_raw(false);
+        self.set_invert_x_raw(false);
+        self.set_invert_y_raw(false);
+        self.set_invert_x_for_classes_raw(None);
+        self.set_invert_y_for_classes_raw(None);
+        self.set_tray_status_delay_ms_raw(DEFAULT_TRAY_STATUS_DELAY_MS as f64);
+        self.set_single_instance_raw(true);
+        self.set_max_coast_ms_raw(DEFAULT_MAX_COAST_MS as f64);
+        self.set_quantize_to_lines_raw(false);
+        self.set_batch_ms_raw(DEFAULT_BATCH_MS as f64);
+        self.set_axis_order_raw(AxisOrder::VerticalFirst);
+        self.set_pulse_raw(false);
+        self.set_last_preset_raw(None);
+        self.set_back_button_action_raw(ButtonAction::None);
+        self.set_forward_button_action_raw(ButtonAction::None);
+        self.set_max_continuous_scroll_ms_raw(DEFAULT_MAX_CONTINUOUS_SCROLL_MS as f64);
+        self.set_low_latency_first_emit_raw(false);
+        self.set_temp_invert_key_raw(DEFAULT_TEMP_INVERT_KEY as f64);
+        self.set_inertia_carryover_raw(DEFAULT_INERTIA_CARRYOVER);
+        self.set_log_format_raw(LogFormat::Text);
+        self.set_auto_scroll_raw(false);
+        self.set_auto_scroll_speed_raw(DEFAULT_AUTO_SCROLL_SPEED);
+        self.set_wheel_mode_raw(WheelMode::Replace);
+        self.set_soft_land_raw(false);
+        self.set_soft_land_velocity_raw(DEFAULT_SOFT_LAND_VELOCITY);
+        self.set_per_axis_momentum_raw(false);
+        self.set_decay_velocity_scale_raw(DEFAULT_DECAY_VELOCITY_SCALE);
+        self.set_tray_single_raw(TrayAction::None);
+        self.set_tray_double_raw(TrayAction::None);
+        self.set_disable_file_raw(None);
+        self.set_flick_notch_rate_raw(DEFAULT_FLICK_NOTCH_RATE);
+        self.set_scroll_sound_raw(false);
+        self.set_scroll_sound_file_raw(None);
+        self.set_max_notches_per_tick_raw(DEFAULT_MAX_NOTCHES_PER_TICK);
+        self.set_fine_key_raw(DEFAULT_FINE_KEY as f64);
+        self.set_fine_factor_raw(DEFAULT_FINE_FACTOR);
+        self.set_zoom_for_classes_raw(None);
+        self.set_wheel_off_for_classes_raw(None);
+        self.set_startup_delay_ms_raw(DEFAULT_STARTUP_DELAY_MS as f64);
+        self.set_edge_detect_raw(false);
+        self.set_input_start_delay_ms_raw(DEFAULT_INPUT_START_DELAY_MS as f64);
+        self.set_stack_mode_raw(StackMode::Accumulate);
+        self.set_conflict_check_raw(true);
+        self.set_conflict_apps_raw(None);
+        self.set_smooth_vertical_raw(true);
+        // `paused` mirrors the engine state rather than a preference; the reset leaves it alone.
+        self.set_start_paused_raw(false);
+        self.set_pause_cooldown_ms_raw(DEFAULT_PAUSE_COOLDOWN_MS as f64);
+        self.set_mouse_device_path_raw(None);
+        drop(guard);
+        let _ = self.save_to_disk();
+    }
+
+    pub fn begin_preview(&self) {
+        if let Ok(mut guard) = self.preview_backup.lock() {
+            if guard.is_none() {
+                *guard = Some(self.snapshot());
+            }
+        }
+    }
+
+    pub fn commit_preview(&self) {
+        if let Ok(mut guard) = self.preview_backup.lock() {
+            *guard = None;
+        }
+        let _ = self.save_to_disk();
+    }
+
+    pub fn cancel_preview(&self) {
+        let backup = self
+            .preview_backup
+            .lock()
+            .ok()
+            .and_then(|mut guard| guard.take());
+        if let Some(mut snap) = backup {
+            snap.paused = self.paused();
+            self.apply_snapshot(snap);
+        }
+    }
+
+    pub fn preset_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .presets
+            .lock()
+            .map(|guard| guard.iter().map(|(name, _)| name.clone()).collect())
+            .unwrap_or_default();
+        if !names.iter().any(|name| name == PRESENTATION_PRESET) {
+            names.push(PRESENTATION_PRESET.to_string());
+        }
+        names
+    }
+
+    /// Slow, long glides that cover a few notches each, easy to follow on a projector. Only
+    /// the glide is changed; everything else stays as in `base`.
+    fn presentation_preset(base: &ConfigSnapshot) -> ConfigSnapshot {
+        ConfigSnapshot {
+            normal_wheel_gain: 0.05,
+            normal_wheel_gain_h: 0.05,
+            normal_wheel_damping: 0.985,
+            max_velocity_hires: 6.0,
+            min_notch_travel: 4.0,
+            ..base.clone()
+        }
+    }
+
+    /// The settings preset `name` gives when applied over `base`.
+    fn find_preset(
+        presets: &[(String, ConfigSnapshot)],
+        name: &str,
+        base: &ConfigSnapshot,
+    ) -> Option<ConfigSnapshot> {
+        presets
+            .iter()
+            .find(|(preset_name, _)| preset_name == name)
+            .map(|(_, snap)| snap.clone())
+            .or_else(|| (name == PRESENTATION_PRESET).then(|| Self::presentation_preset(base)))
+    }
+
+    /// Loads a named preset, keeping the current device and language. The settings it
+    /// replaces are kept for `restore_pre_preset` unless a preset is already in use.
+    pub fn apply_preset(&self, name: &str) -> bool {
+        let preset = self
+            .presets
+            .lock()
+            .ok()
+            .and_then(|guard| Self::find_preset(&guard, name, &self.snapshot()));
+        let Some(mut snap) = preset else {
+            return false;
+        };
+
+        if let Ok(mut guard) = self.preset_backup.lock() {
+            if guard.is_none() {
+                *guard = Some(self.snapshot());
+            }
+        }
+        snap.lang = self.lang();
+        snap.mouse_device_path = self.mouse_device_path();
+        snap.paused = self.paused();
+        snap.last_preset = Some(name.to_string());
+        self.apply_snapshot(snap);
+        let _ = self.save_to_disk();
+        true
+    }
+
+    pub fn can_restore_pre_preset(&self) -> bool {
+        self.preset_backup
+            .lock()
+            .map(|guard| guard.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Goes back to the settings in use before the first preset this session; false when
+    /// no preset has been applied.
+    pub fn restore_pre_preset(&self) -> bool {
+        let backup = self
+            .preset_backup
+            .lock()
+            .ok()
+            .and_then(|mut guard| guard.take());
+        let Some(mut snap) = backup else {
+            return false;
+        };
+
+        snap.lang = self.lang();
+        snap.mouse_device_path = self.mouse_device_path();
+        snap.paused = self.paused();
+        snap.last_preset = None;
+        self.apply_snapshot(snap);
+        let _ = self.save_to_disk();
+        true
+    }
+
+    pub fn save_preset(&self, name: &str) -> bool {
+        let name = name.trim();
+        if !Self::is_valid_preset_name(name) {
+            return false;
+        }
+
+        let mut snap = self.snapshot();
+        snap.last_preset = None;
+        if let Ok(mut guard) = self.presets.lock() {
+            match guard
+                .iter_mut()
+                .find(|(preset_name, _)| preset_name == name)
+            {
+                Some((_, existing)) => *existing = snap,
+                None => guard.push((name.to_string(), snap)),
+            }
+        }
+        let _ = self.save_to_disk();
+        true
+    }
+
+    /// Applies one `key=value` setting as it would appear in the config file; false for an
+    /// unknown key.
+    pub fn set_value(&self, key: &str, value: &str) -> bool {
+        if config_key_doc(key).is_none() {
+            return false;
+        }
+
+        let mut text = Self::snapshot_text(&self.snapshot());
+        text.push_str(&format!("{}={}\n", key, value));
+        self.apply_snapshot(Self::parse_snapshot(&text));
+        self.save_edit();
+        true
+    }
+
+    /// The settings in effect, in config file form.
+    pub(crate) fn config_text(&self) -> String {
+        Self::snapshot_text(&self.snapshot())
+    }
+
+    fn is_valid_preset_name(name: &str) -> bool {
+        !name.is_empty() && !name.contains(['[', ']', '\n'])
+    }
+
+    fn is_previewing(&self) -> bool {
+        self.preview_backup
+            .lock()
+            .map(|guard| guard.is_some())
+            .unwrap_or(false)
+    }
+
+    fn load_f64(atom: &AtomicU64) -> f64 {
+        f64::from_bits(atom.load(Ordering::Relaxed))
+    }
+
+    fn store_f64(atom: &AtomicU64, value: f64) {
+        atom.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> ConfigSnapshot {
+        ConfigSnapshot {
+            normal_wheel_gain: self.normal_wheel_gain(),
+            normal_wheel_gain_h: self.normal_wheel_gain_h(),
+            normal_wheel_damping: self.normal_wheel_damping(),
+            drag_wheel_gain: self.drag_wheel_gain(),
+            drag_wheel_gain_h: self.drag_wheel_gain_h(),
+            drag_wheel_damping: self.drag_wheel_damping(),
+            drag_deadzone_px: self.drag_deadzone_px(),
+            tap_max_duration_ms: self.tap_max_duration_ms(),
+            loop_sleep_ms: self.loop_sleep_ms(),
+            max_velocity_hires: self.max_velocity_hires(),
+            easing_kind: self.easing_kind(),
+            smooth_enabled: self.smooth_enabled(),
+            middle_scroll_enabled: self.middle_scroll_enabled(),
+            output_curve: self.output_curve(),
+            broadcast_scroll: self.broadcast_scroll(),
+            clip_grace_ms: self.clip_grace_ms(),
+            lang: self.lang(),
+            auto_disable_fullscreen: self.auto_disable_fullscreen(),
+            decay_model: self.decay_model(),
+            spring_stiffness: self.spring_stiffness(),
+            spring_damping: self.spring_damping(),
+            stop_threshold: self.stop_threshold(),
+            emit_threshold: self.emit_threshold(),
+            anchor_icon: self.anchor_icon(),
+            raw_input_mode: self.raw_input_mode(),
+            scroll_under_cursor: self.scroll_under_cursor(),
+            tray_icon_path: self.tray_icon_path(),
+            log_level: self.log_level(),
+            hires_timer: self.hires_timer(),
+            smooth_horizontal: self.smooth_horizontal(),
+            output_method: self.output_method(),
+            snap_to_edge: self.snap_to_edge(),
+            snap_velocity: self.snap_velocity(),
+            input_tag: self.input_tag(),
+            turbo_button: self.turbo_button(),
+            turbo_factor: self.turbo_factor(),
+            min_notch_travel: self.min_notch_travel(),
+            sleep_when_idle: self.sleep_when_idle(),
+            disable_for_classes: self.disable_for_classes(),
+            drag_smoothing: self.drag_smoothing(),
+            stop_on_focus_change: self.stop_on_focus_change(),
+            drag_soft_start_px: self.drag_soft_start_px(),
+            preview_settings: self.preview_settings(),
+            invert_x: self.invert_x(),
+            invert_y: self.invert_y(),
+            invert_x_for_classes: self.invert_x_for_classes(),
+            invert_y_for_classes: self.invert_y_for_classes(),
+            tray_status_delay_ms: self.tray_status_delay_ms(),
+            single_instance: self.single_instance(),
+            max_coast_ms: self.max_coast_ms(),
+            quantize_to_lines: self.quantize_to_lines(),
+            batch_ms: self.batch_ms(),
+            axis_order: self.axis_order(),
+            pulse: self.pulse(),
+            last_preset: self.last_preset(),
+            back_button_action: self.back_button_action(),
+            forward_button_action: self.forward_button_action(),
+            max_continuous_scroll_ms: self.max_continuous_scroll_ms(),
+            low_latency_first_emit: self.low_latency_first_emit(),
+            temp_invert_key: self.temp_invert_key(),
+            inertia_carryover: self.inertia_carryover(),
+            log_format: self.log_format(),
+            auto_scroll: self.auto_scroll(),
+            auto_scroll_speed: self.auto_scroll_speed(),
+            wheel_mode: self.wheel_mode(),
+            soft_land: self.soft_land(),
+            soft_land_velocity: self.soft_land_velocity(),
+            per_axis_momentum: self.per_axis_momentum(),
+            decay_velocity_scale: self.decay_velocity_scale(),
+            tray_single: self.tray_single(),
+            tray_double: self.tray_double(),
+            disable_file: self.disable_file(),
+            flick_notch_rate: self.flick_notch_rate(),
+            scroll_sound: self.scroll_sound(),
+            scroll_sound_file: self.scroll_sound_file(),
+            max_notches_per_tick: self.max_notches_per_tick(),
+            fine_key: self.fine_key(),
+            fine_factor: self.fine_factor(),
+            zoom_for_classes: self.zoom_for_classes(),
+            wheel_off_for_classes: self.wheel_off_for_classes(),
+            startup_delay_ms: self.startup_delay_ms(),
+            edge_detect: self.edge_detect(),
+            input_start_delay_ms: self.input_start_delay_ms(),
+            stack_mode: self.stack_mode(),
+            conflict_check: self.conflict_check(),
+            conflict_apps: self.conflict_apps(),
+            smooth_vertical: self.smooth_vertical(),
+            paused: self.paused(),
+            start_paused: self.start_paused(),
+            pause_cooldown_ms: self.pause_cooldown_ms(),
+            mouse_device_path: self.mouse_device_path(),
+        }
+    }
+
+    fn apply_snapshot(&self, snap: ConfigSnapshot) {
+        let _guard = self.write_lock.lock();
+        self.set_normal_wheel_gain_raw(snap.normal_wheel_gain);
+        self.set_normal_wheel_gain_h_raw(snap.normal_wheel_gain_h);
+        self.set_normal_wheel_damping_raw(snap.normal_wheel_damping);
+        self.set_drag_wheel_gain_raw(snap.drag_wheel_gain);
+        self.set_drag_wheel_gain_h_raw(snap.drag_wheel_gain_h);
+        self.set_drag_wheel_damping_raw(snap.drag_wheel_damping);
+        self.set_drag_deadzone_px_raw(snap.drag_deadzone_px);
+        self.set_tap_max_duration_ms_raw(snap.tap_max_duration_ms as f64);
+        self.set_loop_sleep_ms_raw(snap.loop_sleep_ms as f64);
+        self.set_max_velocity_hires_raw(snap.max_velocity_hires);
+        self.set_easing_kind_raw(snap.easing_kind);
+        self.set_smooth_enabled_raw(snap.smooth_enabled);
+        self.set_middle_scroll_enabled_raw(snap.middle_scroll_enabled);
+        self.set_output_curve_raw(snap.output_curve);
+        self.set_broadcast_scroll_raw(snap.broadcast_scroll);
+        self.set_clip_grace_ms_raw(snap.clip_grace_ms as f64);
+        self.set_lang_raw(snap.lang);
+        self.set_auto_disable_fullscreen_raw(snap.auto_disable_fullscreen);
+        self.set_decay_model_raw(snap.decay_model);
+        self.set_spring_stiffness_raw(snap.spring_stiffness);
+        self.set_spring_damping_raw(snap.spring_damping);
+        self.set_stop_threshold_raw(snap.stop_threshold);
+        self.set_emit_threshold_raw(snap.emit_threshold);
+        self.set_anchor_icon_raw(snap.anchor_icon);
+        self.set_raw_input_mode_raw(snap.raw_input_mode);
+        self.set_scroll_under_cursor_raw(snap.scroll_under_cursor);
+        self.set_tray_icon_path_raw(snap.tray_icon_path);
+        self.set_log_level_raw(snap.log_level);
+        self.set_hires_timer_raw(snap.hires_timer);
+        self.set_smooth_horizontal_raw(snap.smooth_horizontal);
+        self.set_output_method_raw(snap.output_method);
+        self.set_snap_to_edge_raw(snap.snap_to_edge);
+        self.set_snap_velocity_raw(snap.snap_velocity);
+        self.set_input_tag_raw(snap.input_tag as f64);
+        self.set_turbo_button_raw(snap.turbo_button);
+        self.set_turbo_factor_raw(snap.turbo_factor);
+        self.set_min_notch_travel_raw(snap.min_notch_travel);
+        self.set_sleep_when_idle_raw(snap.sleep_when_idle);
+        self.set_disable_for_classes_raw(snap.disable_for_classes);
+        self.set_drag_smoothing_raw(snap.drag_smoothing);
+        self.set_stop_on_focus_change_raw(snap.stop_on_focus_change);
+        self.set_drag_soft_start_px_raw(snap.drag_soft_start_px);
+        self.set_preview_settings_raw(snap.preview_settings);
+        self.set_invert_x_raw(snap.invert_x);
+        self.set_invert_y_raw(snap.invert_y);
+        self.set_invert_x_for_classes_raw(snap.invert_x_for_classes);
+        self.set_invert_y_for_classes_raw(snap.invert_y_for_classes);
+        self.set_tray_status_delay_ms_raw(snap.tray_status_delay_ms as f64);
+        self.set_single_instance_raw(snap.single_instance);
+        self.set_max_coast_ms_raw(snap.max_coast_ms as f64);
+        self.set_quantize_to_lines_raw(snap.quantize_to_lines);
+        self.set_batch_ms_raw(snap.batch_ms as f64);
+        self.set_axis_order_raw(snap.axis_order);
+        self.set_pulse_raw(snap.pulse);
+        self.set_last_preset_raw(snap.last_preset);
+        self.set_back_button_action_raw(snap.back_button_action);
+        self.set_forward_button_action_raw(snap.forward_button_action);
+        self.set_max_continuous_scroll_ms_raw(snap.max_continuous_scroll_ms as f64);
+        self.set_low_latency_first_emit_raw(snap.low_latency_first_emit);
+        self.set_temp_invert_key_raw(snap.temp_invert_key as f64);
+        self.set_inertia_carryover_raw(snap.inertia_carryover);
+        self.set_log_format_raw(snap.log_format);
+        self.set_auto_scroll_raw(snap.auto_scroll);
+        self.set_auto_scroll_speed_raw(snap.auto_scroll_speed);
+        self.set_wheel_mode_raw(snap.wheel_mode);
+        self.set_soft_land_raw(snap.soft_land);
+        self.set_soft_land_velocity_raw(snap.soft_land_velocity);
+        self.set_per_axis_momentum_raw(snap.per_axis_momentum);
+        self.set_decay_velocity_scale_raw(snap.decay_velocity_scale);
+        self.set_tray_single_raw(snap.tray_single);
+        self.set_tray_double_raw(snap.tray_double);
+        self.set_disable_file_raw(snap.disable_file);
+        self.set_flick_notch_rate_raw(snap.flick_notch_rate);
+        self.set_scroll_sound_raw(snap.scroll_sound);
+        self.set_scroll_sound_file_raw(snap.scroll_sound_file);
+        self.set_max_notches_per_tick_raw(snap.max_notches_per_tick);
+        self.set_fine_key_raw(snap.fine_key as f64);
+        self.set_fine_factor_raw(snap.fine_factor);
+        self.set_zoom_for_classes_raw(snap.zoom_for_classes);
+        self.set_wheel_off_for_classes_raw(snap.wheel_off_for_classes);
+        self.set_startup_delay_ms_raw(snap.startup_delay_ms as f64);
+        self.set_edge_detect_raw(snap.edge_detect);
+        self.set_input_start_delay_ms_raw(snap.input_start_delay_ms as f64);
+        self.set_stack_mode_raw(snap.stack_mode);
+        self.set_conflict_check_raw(snap.conflict_check);
+        self.set_conflict_apps_raw(snap.conflict_apps);
+        self.set_smooth_vertical_raw(snap.smooth_vertical);
+        self.set_paused_raw(snap.paused);
+        self.set_start_paused_raw(snap.start_paused);
+        self.set_pause_cooldown_ms_raw(snap.pause_cooldown_ms as f64);
+        self.set_mouse_device_path_raw(snap.mouse_device_path);
+    }
+
+    fn config_path() -> Option<PathBuf> {
+        #[cfg(target_os = "windows")]
+        {
+            let home = env::var_os("USERPROFILE").map(PathBuf::from)?;
+            return Some(
+                home.join("Documents")
+                    .join(CONFIG_DIR_NAME)
+                    .join(CONFIG_FILE_NAME),
+            );
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            let home = env::var_os("HOME").map(PathBuf::from)?;
+            Some(
+                home.join(".config")
+                    .join(CONFIG_DIR_NAME)
+                    .join(CONFIG_FILE_NAME),
+            )
+        }
+    }
+
+    /// Per-user location used when the primary config file cannot be written.
+    fn fallback_config_path() -> Option<PathBuf> {
+        #[cfg(target_os = "windows")]
+        {
+            let appdata = env::var_os("APPDATA").map(PathBuf::from)?;
+            return Some(appdata.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME));
+        }
+
+        #[cfg(not(target_os = "windows"))]
+        {
+            let data = env::var_os("XDG_DATA_HOME")
+                .map(PathBuf::from)
+                .or_else(|| {
+                    env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+                })?;
+            Some(data.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+        }
+    }
+
+    /// Whichever of the primary and fallback files was written last.
+    fn load_path() -> Option<PathBuf> {
+        let primary = Self::config_path();
+        let fallback = Self::fallback_config_path();
+        let modified = |path: &Option<PathBuf>| {
+            path.as_ref()
+                .and_then(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+        };
+
+        match (modified(&primary), modified(&fallback)) {
+            (Some(primary_time), Some(fallback_time)) if fallback_time > primary_time => fallback,
+            (None, Some(_)) => fallback,
+            _ => primary,
+        }
+    }
+
+    fn format_f64(value: f64) -> String {
+        format!("{:.17}", value)
+    }
+
+    fn escape_string(value: &str) -> String {
+        let mut out = String::with_capacity(value.len() + 8);
+        for ch in value.chars() {
+            match ch {
+                '\\' => out.push_str("\\\\"),
+                '"' => out.push_str("\\\""),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c => out.push(c),
+            }
+        }
+        out
+    }
+
+    fn unescape_string(value: &str) -> Option<String> {
+        let mut out = String::with_capacity(value.len());
+        let mut chars = value.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '\\' {
+                out.push(ch);
+                continue;
+            }
+
+            let next = chars.next()?;
+            match next {
+                '\\' => out.push('\\'),
+                '"' => out.push('"'),
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                other => out.push(other),
+            }
+        }
+        Some(out)
+    }
+
+    fn parse_bool(value: &str) -> Option<bool> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Some(true),
+            "0" | "false" | "no" | "off" => Some(false),
+            _ => None,
+        }
+    }
+
+    fn parse_f64(value: &str) -> Option<f64> {
+        // `inf` and `NaN` parse as floats but would poison the momentum math.
+        value.trim().parse::<f64>().ok().filter(|v| v.is_finite())
+    }
+
+    fn parse_u64(value: &str) -> Option<u64> {
+        value.trim().parse::<u64>().ok()
+    }
+
+    fn parse_optional_string(value: &str) -> Option<Option<String>> {
+        let trimmed = value.trim();
+        if trimmed.is_empty() {
+            return Some(None);
+        }
+
+        if trimmed == "\"\"" {
+            return Some(None);
+        }
+
+        if trimmed.starts_with('"') && trimmed.ends_with('"') && trimmed.len() >= 2 {
+            let inner = &trimmed[1..trimmed.len() - 1];
+            return Self::unescape_string(inner).map(|s| if s.is_empty() { None } else { Some(s) });
+        }
+
+        Some(Some(trimmed.to_string()))
+    }
+
+    /// Drops a trailing `#` or `;` comment that sits outside a quoted value.
+    fn strip_comment(line: &str) -> &str {
+        let mut in_quotes = false;
+        let mut escaped = false;
+        let mut prev_blank = true;
+
+        for (i, ch) in line.char_indices() {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' if in_quotes => escaped = true,
+                '"' => in_quotes = !in_quotes,
+                '#' | ';' if !in_quotes && prev_blank => return line[..i].trim_end(),
+                _ => {}
+            }
+            prev_blank = ch.is_whitespace();
+        }
+
+        line
+    }
+
+    fn parse_presets(text: &str) -> Vec<(String, ConfigSnapshot)> {
+        let mut sections: Vec<(String, String)> = Vec::new();
+        let mut current: Option<(String, String)> = None;
+
+        for raw_line in text.lines() {
+            let line = Self::strip_comment(raw_line.trim());
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                sections.extend(current.take());
+                current = header
+                    .strip_prefix(PRESET_SECTION_PREFIX)
+                    .map(|name| (name.trim().to_string(), String::new()));
+            } else if let Some((_, body)) = current.as_mut() {
+                body.push_str(line);
+                body.push('\n');
+            }
+        }
+        sections.extend(current);
+
+        sections
+            .into_iter()
+            .filter(|(name, _)| Self::is_valid_preset_name(name))
+            .map(|(name, body)| (name, Self::parse_snapshot(&body)))
+            .collect()
+    }
+
+    fn parse_snapshot(text: &str) -> ConfigSnapshot {
+        let mut snap = ConfigSnapshot::defaults();
+        // Files written before the horizontal gains existed keep one gain for both axes.
+        let mut saw_normal_gain_h = false;
+        let mut saw_drag_gain_h = false;
+        // A negative wheel gain asks for that axis reversed. It is stored as the invert flag,
+        // set rather than toggled, so parsing the same text again changes nothing.
+        let mut reversed_x = false;
+        let mut reversed_y = false;
+
+        for raw_line in text.lines() {
+            let line = Self::strip_comment(raw_line.trim());
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                break;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                log_error!("ignoring malformed config line: {}", raw_line.trim());
+                continue;
+            };
+
+            let key = key.trim();
+            let value = value.trim();
+
+            let accepted = match key {
+                "normal_wheel_gain" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.normal_wheel_gain = v.abs();
+                        reversed_y = v < 0.0;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "normal_wheel_gain_h" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.normal_wheel_gain_h = v.abs();
+                        reversed_x = v < 0.0;
+                        saw_normal_gain_h = true;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "normal_wheel_damping" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.normal_wheel_damping = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "drag_wheel_gain" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.drag_wheel_gain = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "drag_wheel_gain_h" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.drag_wheel_gain_h = v;
+                        saw_drag_gain_h = true;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "drag_wheel_damping" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.drag_wheel_damping = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "drag_deadzone_px" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.drag_deadzone_px = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "tap_max_duration_ms" => {
+                    if let Some(v) = Self::parse_u64(value) {
+                        snap.tap_max_duration_ms = v;
+                        true
+                    } else if let Some(v) = Self::parse_f64(value) {
+                        snap.tap_max_duration_ms = v.max(1.0) as u64;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "loop_sleep_ms" => {
+                    if let Some(v) = Self::parse_u64(value) {
+                        snap.loop_sleep_ms = v;
+                        true
+                    } else if let Some(v) = Self::parse_f64(value) {
+                        snap.loop_sleep_ms = v.max(1.0) as u64;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "max_velocity_hires" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.max_velocity_hires = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "easing_kind" => {
+                    if let Some(kind) = EasingKind::from_label(value.trim_matches('"')) {
+                        snap.easing_kind = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.easing_kind = EasingKind::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "smooth_enabled" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.smooth_enabled = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "middle_scroll_enabled" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.middle_scroll_enabled = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "output_curve" => {
+                    if let Some(kind) = OutputCurve::from_label(value.trim_matches('"')) {
+                        snap.output_curve = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.output_curve = OutputCurve::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "broadcast_scroll" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.broadcast_scroll = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "clip_grace_ms" => {
+                    if let Some(v) = Self::parse_u64(value) {
+                        snap.clip_grace_ms = v;
+                        true
+                    } else if let Some(v) = Self::parse_f64(value) {
+                        snap.clip_grace_ms = v.max(0.0) as u64;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "lang" => {
+                    if let Some(kind) = Lang::from_label(value.trim_matches('"')) {
+                        snap.lang = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.lang = Lang::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "auto_disable_fullscreen" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.auto_disable_fullscreen = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "decay_model" => {
+                    if let Some(kind) = DecayModel::from_label(value.trim_matches('"')) {
+                        snap.decay_model = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.decay_model = DecayModel::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "spring_stiffness" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.spring_stiffness = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "spring_damping" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.spring_damping = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "stop_threshold" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.stop_threshold = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "emit_threshold" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.emit_threshold = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "anchor_icon" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.anchor_icon = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "raw_input_mode" => {
+                    if let Some(kind) = RawInputMode::from_label(value.trim_matches('"')) {
+                        snap.raw_input_mode = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.raw_input_mode = RawInputMode::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "scroll_under_cursor" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.scroll_under_cursor = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "tray_icon_path" => {
+                    if let Some(v) = Self::parse_optional_string(value) {
+                        snap.tray_icon_path = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "log_level" => {
+                    if let Some(kind) = LogLevel::from_label(value.trim_matches('"')) {
+                        snap.log_level = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.log_level = LogLevel::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "hires_timer" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.hires_timer = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "smooth_horizontal" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.smooth_horizontal = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "output_method" => {
+                    if let Some(kind) = OutputMethod::from_label(value.trim_matches('"')) {
+                        snap.output_method = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.output_method = OutputMethod::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "snap_to_edge" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.snap_to_edge = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "snap_velocity" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.snap_velocity = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "input_tag" => {
+                    // Physical input carries extra info 0, so a zero tag would mistake it for ours.
+                    let parsed = Self::parse_u64(value)
+                        .or_else(|| Self::parse_f64(value).map(|v| v.max(0.0) as u64));
+                    if let Some(v) = parsed.filter(|v| *v != 0) {
+                        snap.input_tag = v.min(u32::MAX as u64);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "turbo_button" => {
+                    if let Some(kind) = TurboButton::from_label(value.trim_matches('"')) {
+                        snap.turbo_button = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.turbo_button = TurboButton::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "turbo_factor" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.turbo_factor = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "min_notch_travel" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.min_notch_travel = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "sleep_when_idle" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.sleep_when_idle = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "disable_for_classes" => {
+                    if let Some(v) = Self::parse_optional_string(value) {
+                        snap.disable_for_classes = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "drag_smoothing" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.drag_smoothing = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "stop_on_focus_change" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.stop_on_focus_change = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "drag_soft_start_px" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.drag_soft_start_px = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "preview_settings" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.preview_settings = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "invert_x" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.invert_x = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "invert_y" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.invert_y = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "invert_x_for_classes" => {
+                    if let Some(v) = Self::parse_optional_string(value) {
+                        snap.invert_x_for_classes = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "invert_y_for_classes" => {
+                    if let Some(v) = Self::parse_optional_string(value) {
+                        snap.invert_y_for_classes = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "tray_status_delay_ms" => {
+                    if let Some(v) = Self::parse_u64(value) {
+                        snap.tray_status_delay_ms = v;
+                        true
+                    } else if let Some(v) = Self::parse_f64(value) {
+                        snap.tray_status_delay_ms = v.max(0.0) as u64;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "single_instance" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.single_instance = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "max_coast_ms" => {
+                    if let Some(v) = Self::parse_u64(value) {
+                        snap.max_coast_ms = v;
+                        true
+                    } else if let Some(v) = Self::parse_f64(value) {
+                        snap.max_coast_ms = v.max(0.0) as u64;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "quantize_to_lines" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.quantize_to_lines = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "batch_ms" => {
+                    if let Some(v) = Self::parse_u64(value) {
+                        snap.batch_ms = v;
+                        true
+                    } else if let Some(v) = Self::parse_f64(value) {
+                        snap.batch_ms = v.max(0.0) as u64;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "axis_order" => {
+                    if let Some(kind) = AxisOrder::from_label(value.trim_matches('"')) {
+                        snap.axis_order = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.axis_order = AxisOrder::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "pulse" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.pulse = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "last_preset" => {
+                    if let Some(v) = Self::parse_optional_string(value) {
+                        snap.last_preset = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "back_button_action" => {
+                    if let Some(kind) = ButtonAction::from_label(value.trim_matches('"')) {
+                        snap.back_button_action = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.back_button_action = ButtonAction::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "forward_button_action" => {
+                    if let Some(kind) = ButtonAction::from_label(value.trim_matches('"')) {
+                        snap.forward_button_action = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.forward_button_action = ButtonAction::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "max_continuous_scroll_ms" => {
+                    if let Some(v) = Self::parse_u64(value) {
+                        snap.max_continuous_scroll_ms = v;
+                        true
+                    } else if let Some(v) = Self::parse_f64(value) {
+                        snap.max_continuous_scroll_ms = v.max(0.0) as u64;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "low_latency_first_emit" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.low_latency_first_emit = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "temp_invert_key" => {
+                    if let Some(v) = Self::parse_u64(value) {
+                        snap.temp_invert_key = v;
+                        true
+                    } else if let Some(v) = Self::parse_f64(value) {
+                        snap.temp_invert_key = v.max(0.0) as u64;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "inertia_carryover" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.inertia_carryover = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "log_format" => {
+                    if let Some(kind) = LogFormat::from_label(value.trim_matches('"')) {
+                        snap.log_format = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.log_format = LogFormat::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "auto_scroll" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.auto_scroll = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "auto_scroll_speed" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.auto_scroll_speed = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "wheel_mode" => {
+                    if let Some(kind) = WheelMode::from_label(value.trim_matches('"')) {
+                        snap.wheel_mode = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.wheel_mode = WheelMode::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "soft_land" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.soft_land = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "soft_land_velocity" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.soft_land_velocity = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "per_axis_momentum" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.per_axis_momentum = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "decay_velocity_scale" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.decay_velocity_scale = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "tray_single" => {
+                    if let Some(kind) = TrayAction::from_label(value.trim_matches('"')) {
+                        snap.tray_single = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.tray_single = TrayAction::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "tray_double" => {
+                    if let Some(kind) = TrayAction::from_label(value.trim_matches('"')) {
+                        snap.tray_double = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.tray_double = TrayAction::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "disable_file" => {
+                    if let Some(v) = Self::parse_optional_string(value) {
+                        snap.disable_file = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "flick_notch_rate" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.flick_notch_rate = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "scroll_sound" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.scroll_sound = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "scroll_sound_file" => {
+                    if let Some(v) = Self::parse_optional_string(value) {
+                        snap.scroll_sound_file = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "max_notches_per_tick" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.max_notches_per_tick = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "fine_key" => {
+                    if let Some(v) = Self::parse_u64(value) {
+                        snap.fine_key = v;
+                        true
+                    } else if let Some(v) = Self::parse_f64(value) {
+                        snap.fine_key = v.max(0.0) as u64;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "fine_factor" => {
+                    if let Some(v) = Self::parse_f64(value) {
+                        snap.fine_factor = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "zoom_for_classes" => {
+                    if let Some(v) = Self::parse_optional_string(value) {
+                        snap.zoom_for_classes = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "wheel_off_for_classes" => {
+                    if let Some(v) = Self::parse_optional_string(value) {
+                        snap.wheel_off_for_classes = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "startup_delay_ms" => {
+                    if let Some(v) = Self::parse_u64(value) {
+                        snap.startup_delay_ms = v;
+                        true
+                    } else if let Some(v) = Self::parse_f64(value) {
+                        snap.startup_delay_ms = v.max(0.0) as u64;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "edge_detect" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.edge_detect = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "input_start_delay_ms" => {
+                    if let Some(v) = Self::parse_u64(value) {
+                        snap.input_start_delay_ms = v;
+                        true
+                    } else if let Some(v) = Self::parse_f64(value) {
+                        snap.input_start_delay_ms = v.max(0.0) as u64;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "stack_mode" => {
+                    if let Some(kind) = StackMode::from_label(value.trim_matches('"')) {
+                        snap.stack_mode = kind;
+                        true
+                    } else if let Ok(raw) = value.trim().parse::<u64>() {
+                        snap.stack_mode = StackMode::from_u64(raw);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "conflict_check" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.conflict_check = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "conflict_apps" => {
+                    if let Some(v) = Self::parse_optional_string(value) {
+                        snap.conflict_apps = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "smooth_vertical" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.smooth_vertical = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "paused" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.paused = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "start_paused" => {
+                    if let Some(v) = Self::parse_bool(value) {
+                        snap.start_paused = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "pause_cooldown_ms" => {
+                    if let Some(v) = Self::parse_u64(value) {
+                        snap.pause_cooldown_ms = v;
+                        true
+                    } else if let Some(v) = Self::parse_f64(value) {
+                        snap.pause_cooldown_ms = v.max(0.0) as u64;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                "mouse_device_path" => {
+                    if let Some(v) = Self::parse_optional_string(value) {
+                        snap.mouse_device_path = v;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                _ => {
+                    log_error!("ignoring unknown config key {}", key);
+                    continue;
+                }
+            };
+            if !accepted {
+                log_error!("ignoring bad value for config key {}: {}", key, value);
+            }
+        }
+
+        if !saw_normal_gain_h {
+            snap.normal_wheel_gain_h = snap.normal_wheel_gain;
+        }
+        if !saw_drag_gain_h {
+            snap.drag_wheel_gain_h = snap.drag_wheel_gain;
+        }
+        snap.invert_x |= reversed_x;
+        snap.invert_y |= reversed_y;
+        snap
+    }
+
+    fn load_from_disk(&self) -> io::Result<()> {
+        let text = match Self::load_path().map(fs::read_to_string) {
+            Some(Ok(text)) => text,
+            Some(Err(err)) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => String::new(),
+        };
+        self.load_text(&text);
+        Ok(())
+    }
+
+    fn load_text(&self, text: &str) {
+        let mut snap = Self::parse_snapshot(text);
+        let presets = Self::parse_presets(text);
+        if let Some(name) = snap.last_preset.clone() {
+            match Self::find_preset(&presets, &name, &snap) {
+                Some(preset) => {
+                    log_info!("restoring preset {}", name);
+                    // The base section still holds the settings from before the preset.
+                    let base = ConfigSnapshot {
+                        last_preset: None,
+                        ..snap.clone()
+                    };
+                    if let Ok(mut guard) = self.preset_backup.lock() {
+                        *guard = Some(self.with_env_overrides(base));
+                    }
+                    snap = ConfigSnapshot {
+                        lang: snap.lang,
+                        mouse_device_path: snap.mouse_device_path,
+                        last_preset: snap.last_preset,
+                        paused: snap.paused,
+                        ..preset
+                    };
+                }
+                None => log_info!("preset {} no longer exists, using the base settings", name),
+            }
+        }
+        self.apply_snapshot(self.with_env_overrides(snap));
+        if let Ok(mut guard) = self.presets.lock() {
+            *guard = presets;
+        }
+    }
+
+    /// Layers `NIMBUS_*` environment variables over the file for this run, parsed and clamped
+    /// like the file itself.
+    fn with_env_overrides(&self, snap: ConfigSnapshot) -> ConfigSnapshot {
+        let base = Self::snapshot_text(&snap);
+        let mut overrides = String::new();
+        for (name, value) in env::vars_os() {
+            let (Some(name), Some(value)) = (name.to_str(), value.to_str()) else {
+                continue;
+            };
+            let Some(key) = name.strip_prefix(ENV_OVERRIDE_PREFIX) else {
+                continue;
+            };
+            let key = key.to_ascii_lowercase();
+            if config_key_doc(&key).is_none() {
+                log_info!("ignoring {}: no config key {}", name, key);
+                continue;
+            }
+
+            log_info!("{} overrides {} for this run", name, key);
+            overrides.push_str(&format!("{}={}\n", key, value));
+        }
+
+        if overrides.is_empty() {
+            return snap;
+        }
+        let overridden = Self::parse_snapshot(&format!("{}{}", base, overrides));
+        // Every line the overrides changed is kept, including ones they only change in passing,
+        // like the invert flag behind a negative gain.
+        let changed = Self::snapshot_text(&overridden);
+        let kept = base
+            .lines()
+            .filter(|line| !changed.lines().any(|other| other == *line))
+            .map(String::from)
+            .collect();
+        if let Ok(mut guard) = self.env_base.lock() {
+            *guard = kept;
+        }
+        overridden
+    }
+
+    /// Puts the file's own values back for keys that came from the environment, so a
+    /// scripted launch does not rewrite the shared config.
+    fn without_env_overrides(&self, snap: ConfigSnapshot) -> ConfigSnapshot {
+        let base = self.env_base.lock().map(|g| g.clone()).unwrap_or_default();
+        if base.is_empty() {
+            return snap;
+        }
+
+        let mut text = Self::snapshot_text(&snap);
+        for line in base {
+            text.push_str(&line);
+            text.push('\n');
+        }
+        Self::parse_snapshot(&text)
+    }
+
+    fn save_to_disk(&self) -> io::Result<()> {
+        if !self.persistent || self.is_previewing() {
+            return Ok(());
+        }
+
+        let Some(path) = Self::config_path() else {
+            return Ok(());
+        };
+
+        let _guard = self.write_lock.lock();
+        let text = self.file_text();
+        let err = match Self::write_file(&path, &text) {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+
+        log_error!("failed to write config {}: {}", path.display(), err);
+
+        let fallback = Self::fallback_config_path().filter(|fallback| *fallback != path);
+        let saved_to = match fallback {
+            Some(fallback) => match Self::write_file(&fallback, &text) {
+                Ok(()) => Some(fallback),
+                Err(fallback_err) => {
+                    log_error!(
+                        "failed to write fallback config {}: {}",
+                        fallback.display(),
+                        fallback_err
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+
+        self.report_save_failure(&path, saved_to.as_deref());
+        match saved_to {
+            Some(_) => Ok(()),
+            None => Err(err),
+        }
+    }
+
+    /// Tells the user once per session that settings are not reaching the primary file.
+    fn report_save_failure(&self, path: &Path, saved_to: Option<&Path>) {
+        if self.save_failure_reported.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        let message = match saved_to {
+            Some(fallback) => format!(
+                "Settings could not be saved to {}.\n\nThey were saved to {} instead. \
+                 Make the original file writable, or run NimbusScroll as administrator, \
+                 to keep a single copy.",
+                path.display(),
+                fallback.display()
+            ),
+            None => format!(
+                "Settings could not be saved to {}.\n\nChanges will be lost on exit. \
+                 Make the file writable, or run NimbusScroll as administrator.",
+                path.display()
+            ),
+        };
+        log_info!("{}", message.replace("\n\n", " "));
+
+        #[cfg(target_os = "windows")]
+        {
+            crate::windows::show_error("NimbusScroll", &message);
+        }
+    }
+
+    /// Saves a user edit. An edit leaves the active preset behind, so it lands in the base
+    /// settings instead of being replaced by the preset again on the next launch.
+    fn save_edit(&self) {
+        self.set_last_preset_raw(None);
+        let _ = self.save_to_disk();
+    }
+
+    fn write_file(path: &Path, text: &str) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, text)
+    }
+
+    /// The settings for the file's base section. While a preset is active that is still what
+    /// was in use before it, so the user's own settings outlive a restart.
+    fn base_snapshot(&self) -> ConfigSnapshot {
+        let live = self.snapshot();
+        let backup = self
+            .preset_backup
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone());
+        match (&live.last_preset, backup) {
+            (Some(_), Some(backup)) => ConfigSnapshot {
+                lang: live.lang,
+                mouse_device_path: live.mouse_device_path,
+                paused: live.paused,
+                last_preset: live.last_preset,
+                ..backup
+            },
+            _ => live,
+        }
+    }
+
+    /// The config file as it would be written now: base settings, then each preset.
+    fn file_text(&self) -> String {
+        let presets = self.presets.lock().map(|g| g.clone()).unwrap_or_default();
+        let snap = self.without_env_overrides(self.base_snapshot());
+
+        let mut text = String::new();
+        text.push_str("# NimbusScroll configuration\n");
+        text.push_str("# This file is rewritten automatically by the application.\n");
+        text.push_str("# Values are loaded on startup.\n\n");
+        text.push_str(&Self::annotate(&Self::snapshot_text(&snap)));
+
+        for (name, preset) in &presets {
+            text.push_str(&format!("\n[{}{}]\n", PRESET_SECTION_PREFIX, name));
+            text.push_str(&Self::snapshot_text(preset));
+        }
+        text
+    }
+
+    fn snapshot_text(snap: &ConfigSnapshot) -> String {
+        let mut text = String::new();
+        text.push_str(&format!(
+            "normal_wheel_gain={}\n",
+            Self::format_f64(snap.normal_wheel_gain)
+        ));
+        text.push_str(&format!(
+            "normal_wheel_gain_h={}\n",
+            Self::format_f64(snap.normal_wheel_gain_h)
+        ));
+        text.push_str(&format!(
+            "normal_wheel_damping={}\n",
+            Self::format_f64(snap.normal_wheel_damping)
+        ));
+        text.push_str(&format!(
+            "drag_wheel_gain={}\n",
+            Self::format_f64(snap.drag_wheel_gain)
+        ));
+        text.push_str(&format!(
+            "drag_wheel_gain_h={}\n",
+            Self::format_f64(snap.drag_wheel_gain_h)
+        ));
+        text.push_str(&format!(
+            "drag_wheel_damping={}\n",
+            Self::format_f64(snap.drag_wheel_damping)
+        ));
+        text.push_str(&format!(
+            "drag_deadzone_px={}\n",
+            Self::format_f64(snap.drag_deadzone_px)
+        ));
+        text.push_str(&format!(
+            "tap_max_duration_ms={}\n",
+            snap.tap_max_duration_ms
+        ));
+        text.push_str(&format!("loop_sleep_ms={}\n", snap.loop_sleep_ms));
+        text.push_str(&format!(
+            "max_velocity_hires={}\n",
+            Self::format_f64(snap.max_velocity_hires)
+        ));
+        text.push_str(&format!("easing_kind={}\n", snap.easing_kind.label()));
+        text.push_str(&format!("smooth_enabled={}\n", snap.smooth_enabled));
+        text.push_str(&format!(
+            "middle_scroll_enabled={}\n",
+            snap.middle_scroll_enabled
+        ));
+        text.push_str(&format!("output_curve={}\n", snap.output_curve.label()));
+        text.push_str(&format!("broadcast_scroll={}\n", snap.broadcast_scroll));
+        text.push_str(&format!("clip_grace_ms={}\n", snap.clip_grace_ms));
+        text.push_str(&format!("lang={}\n", snap.lang.label()));
+        text.push_str(&format!(
+            "auto_disable_fullscreen={}\n",
+            snap.auto_disable_fullscreen
+        ));
+        text.push_str(&format!("decay_model={}\n", snap.decay_model.label()));
+        text.push_str(&format!(
+            "spring_stiffness={}\n",
+            Self::format_f64(snap.spring_stiffness)
+        ));
+        text.push_str(&format!(
+            "spring_damping={}\n",
+            Self::format_f64(snap.spring_damping)
+        ));
+        text.push_str(&format!(
+            "stop_threshold={}\n",
+            Self::format_f64(snap.stop_threshold)
+        ));
+        text.push_str(&format!(
+            "emit_threshold={}\n",
+            Self::format_f64(snap.emit_threshold)
+        ));
+        text.push_str(&format!("anchor_icon={}\n", snap.anchor_icon));
+        text.push_str(&format!("raw_input_mode={}\n", snap.raw_input_mode.label()));
+        text.push_str(&format!(
+            "scroll_under_cursor={}\n",
+            snap.scroll_under_cursor
+        ));
+        text.push_str(&format!(
+            "tray_icon_path=\"{}\"\n",
+            Self::escape_string(snap.tray_icon_path.as_deref().unwrap_or(""))
+        ));
+        text.push_str(&format!("log_level={}\n", snap.log_level.label()));
+        text.push_str(&format!("hires_timer={}\n", snap.hires_timer));
+        text.push_str(&format!("smooth_horizontal={}\n", snap.smooth_horizontal));
+        text.push_str(&format!("output_method={}\n", snap.output_method.label()));
+        text.push_str(&format!("snap_to_edge={}\n", snap.snap_to_edge));
+        text.push_str(&format!(
+            "snap_velocity={}\n",
+            Self::format_f64(snap.snap_velocity)
+        ));
+        text.push_str(&format!("input_tag={}\n", snap.input_tag));
+        text.push_str(&format!("turbo_button={}\n", snap.turbo_button.label()));
+        text.push_str(&format!(
+            "turbo_factor={}\n",
+            Self::format_f64(snap.turbo_factor)
+        ));
+        text.push_str(&format!(
+            "min_notch_travel={}\n",
+            Self::format_f64(snap.min_notch_travel)
+        ));
+        text.push_str(&format!("sleep_when_idle={}\n", snap.sleep_when_idle));
+        text.push_str(&format!(
+            "disable_for_classes=\"{}\"\n",
+            Self::escape_string(snap.disable_for_classes.as_deref().unwrap_or(""))
+        ));
+        text.push_str(&format!(
+            "drag_smoothing={}\n",
+            Self::format_f64(snap.drag_smoothing)
+        ));
+        text.push_str(&format!(
+            "stop_on_focus_change={}\n",
+            snap.stop_on_focus_change
+        ));
+        text.push_str(&format!(
+            "drag_soft_start_px={}\n",
+            Self::format_f64(snap.drag_soft_start_px)
+        ));
+        text.push_str(&format!("preview_settings={}\n", snap.preview_settings));
+        text.push_str(&format!("invert_x={}\n", snap.invert_x));
+        text.push_str(&format!("invert_y={}\n", snap.invert_y));
+        text.push_str(&format!(
+            "invert_x_for_classes=\"{}\"\n",
+            Self::escape_string(snap.invert_x_for_classes.as_deref().unwrap_or(""))
+        ));
+        text.push_str(&format!(
+            "invert_y_for_classes=\"{}\"\n",
+            Self::escape_string(snap.invert_y_for_classes.as_deref().unwrap_or(""))
+        ));
+        text.push_str(&format!(
+            "tray_status_delay_ms={}\n",
+            snap.tray_status_delay_ms
+        ));
+        text.push_str(&format!("single_instance={}\n", snap.single_instance));
+        text.push_str(&format!("max_coast_ms={}\n", snap.max_coast_ms));
+        text.push_str(&format!("quantize_to_lines={}\n", snap.quantize_to_lines));
+        text.push_str(&format!("batch_ms={}\n", snap.batch_ms));
+        text.push_str(&format!("axis_order={}\n", snap.axis_order.label()));
+        text.push_str(&format!("pulse={}\n", snap.pulse));
+        text.push_str(&format!(
+            "last_preset=\"{}\"\n",
+            Self::escape_string(snap.last_preset.as_deref().unwrap_or(""))
+        ));
+        text.push_str(&format!(
+            "back_button_action={}\n",
+            snap.back_button_action.label()
+        ));
+        text.push_str(&format!(
+            "forward_button_action={}\n",
+            snap.forward_button_action.label()
+        ));
+        text.push_str(&format!(
+            "max_continuous_scroll_ms={}\n",
+            snap.max_continuous_scroll_ms
+        ));
+        text.push_str(&format!(
+            "low_latency_first_emit={}\n",
+            snap.low_latency_first_emit
+        ));
+        text.push_str(&format!("temp_invert_key={}\n", snap.temp_invert_key));
+        text.push_str(&format!(
+            "inertia_carryover={}\n",
+            Self::format_f64(snap.inertia_carryover)
+        ));
+        text.push_str(&format!("log_format={}\n", snap.log_format.label()));
+        text.push_str(&format!("auto_scroll={}\n", snap.auto_scroll));
+        text.push_str(&format!(
+            "auto_scroll_speed={}\n",
+            Self::format_f64(snap.auto_scroll_speed)
+        ));
+        text.push_str(&format!("wheel_mode={}\n", snap.wheel_mode.label()));
+        text.push_str(&format!("soft_land={}\n", snap.soft_land));
+        text.push_str(&format!(
+            "soft_land_velocity={}\n",
+            Self::format_f64(snap.soft_land_velocity)
+        ));
+        text.push_str(&format!("per_axis_momentum={}\n", snap.per_axis_momentum));
+        text.push_str(&format!(
+            "decay_velocity_scale={}\n",
+            Self::format_f64(snap.decay_velocity_scale)
+        ));
+        text.push_str(&format!("tray_single={}\n", snap.tray_single.label()));
+        text.push_str(&format!("tray_double={}\n", snap.tray_double.label()));
+        text.push_str(&format!(
+            "disable_file=\"{}\"\n",
+            Self::escape_string(snap.disable_file.as_deref().unwrap_or(""))
+        ));
+        text.push_str(&format!(
+            "flick_notch_rate={}\n",
+            Self::format_f64(snap.flick_notch_rate)
+        ));
+        text.push_str(&format!("scroll_sound={}\n", snap.scroll_sound));
+        text.push_str(&format!(
+            "scroll_sound_file=\"{}\"\n",
+            Self::escape_string(snap.scroll_sound_file.as_deref().unwrap_or(""))
+        ));
+        text.push_str(&format!(
+            "max_notches_per_tick={}\n",
+            Self::format_f64(snap.max_notches_per_tick)
+        ));
+        text.push_str(&format!("fine_key={}\n", snap.fine_key));
+        text.push_str(&format!(
+            "fine_factor={}\n",
+            Self::format_f64(snap.fine_factor)
+        ));
+        text.push_str(&format!(
+            "zoom_for_classes=\"{}\"\n",
+            Self::escape_string(snap.zoom_for_classes.as_deref().unwrap_or(""))
+        ));
+        text.push_str(&format!(
+            "wheel_off_for_classes=\"{}\"\n",
+            Self::escape_string(snap.wheel_off_for_classes.as_deref().unwrap_or(""))
+        ));
+        text.push_str(&format!("startup_delay_ms={}\n", snap.startup_delay_ms));
+        text.push_str(&format!("edge_detect={}\n", snap.edge_detect));
+        text.push_str(&format!(
+            "input_start_delay_ms={}\n",
+            snap.input_start_delay_ms
+        ));
+        text.push_str(&format!("stack_mode={}\n", snap.stack_mode.label()));
+        text.push_str(&format!("conflict_check={}\n", snap.conflict_check));
+        text.push_str(&format!(
+            "conflict_apps=\"{}\"\n",
+            Self::escape_string(snap.conflict_apps.as_deref().unwrap_or(""))
+        ));
+        text.push_str(&format!("smooth_vertical={}\n", snap.smooth_vertical));
+        text.push_str(&format!("paused={}\n", snap.paused));
+        text.push_str(&format!("start_paused={}\n", snap.start_paused));
+        text.push_str(&format!("pause_cooldown_ms={}\n", snap.pause_cooldown_ms));
+        text.push_str(&format!(
+            "mouse_device_path=\"{}\"\n",
+            Self::escape_string(snap.mouse_device_path.as_deref().unwrap_or(""))
+        ));
+        text
+    }
+
+    fn annotate(text: &str) -> String {
+        let mut out = String::with_capacity(text.len() * 2);
+        for line in text.lines() {
+            if let Some((key, _)) = line.split_once('=') {
+                if let Some(doc) = config_key_doc(key) {
+                    out.push_str("# ");
+                    out.push_str(doc);
+                    out.push('\n');
+                }
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn write_default_config(path: Option<PathBuf>) -> io::Result<PathBuf> {
+        let path = match path {
+            Some(path) => path,
+            None => Self::config_path()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?,
+        };
+
+        Self::write_file(&path, &Self::in_memory().file_text())?;
+        Ok(path)
+    }
+
+    fn set_normal_wheel_gain_raw(&self, value: f64) {
+        Self::store_f64(&self.normal_wheel_gain, value.clamp(0.0, 1.0));
+    }
+
+    fn set_normal_wheel_gain_h_raw(&self, value: f64) {
+        Self::store_f64(&self.normal_wheel_gain_h, value.clamp(0.0, 1.0));
+    }
+
+    fn set_normal_wheel_damping_raw(&self, value: f64) {
+        Self::store_f64(&self.normal_wheel_damping, value.clamp(0.0, 1.0));
+    }
+
+    fn set_drag_wheel_gain_raw(&self, value: f64) {
+        Self::store_f64(&self.drag_wheel_gain, value.clamp(0.0, 1.0));
+    }
+
+    fn set_drag_wheel_gain_h_raw(&self, value: f64) {
+        Self::store_f64(&self.drag_wheel_gain_h, value.clamp(0.0, 1.0));
+    }
+
+    fn set_drag_wheel_damping_raw(&self, value: f64) {
+        Self::store_f64(&self.drag_wheel_damping, value.clamp(0.0, 1.0));
+    }
+
+    fn set_drag_deadzone_px_raw(&self, value: f64) {
+        Self::store_f64(&self.drag_deadzone_px, value.max(0.0));
+    }
+
+    fn set_tap_max_duration_ms_raw(&self, value: f64) {
+        Self::store_f64(&self.tap_max_duration_ms, value.max(1.0));
+    }
+
+    fn set_loop_sleep_ms_raw(&self, value: f64) {
+        Self::store_f64(&self.loop_sleep_ms, value.max(1.0));
+    }
+
+    fn set_max_velocity_hires_raw(&self, value: f64) {
+        Self::store_f64(&self.max_velocity_hires, value.max(0.0));
+    }
+
+    fn set_easing_kind_raw(&self, value: EasingKind) {
+        self.easing_kind_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_smooth_enabled_raw(&self, value: bool) {
+        self.smooth_enabled.store(value, Ordering::Relaxed);
+    }
+
+    fn set_middle_scroll_enabled_raw(&self, value: bool) {
+        self.middle_scroll_enabled.store(value, Ordering::Relaxed);
+    }
+
+    fn set_output_curve_raw(&self, value: OutputCurve) {
+        self.output_curve_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_broadcast_scroll_raw(&self, value: bool) {
+        self.broadcast_scroll.store(value, Ordering::Relaxed);
+    }
+
+    fn set_clip_grace_ms_raw(&self, value: f64) {
+        Self::store_f64(&self.clip_grace_ms, value.clamp(0.0, 1000.0));
+    }
+
+    fn set_lang_raw(&self, value: Lang) {
+        self.lang_bits.store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_auto_disable_fullscreen_raw(&self, value: bool) {
+        self.auto_disable_fullscreen.store(value, Ordering::Relaxed);
+    }
+
+    fn set_decay_model_raw(&self, value: DecayModel) {
+        self.decay_model_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_spring_stiffness_raw(&self, value: f64) {
+        Self::store_f64(&self.spring_stiffness, value.clamp(0.001, 0.5));
+    }
+
+    fn set_spring_damping_raw(&self, value: f64) {
+        Self::store_f64(&self.spring_damping, value.clamp(0.0, 2.0));
+    }
+
+    fn set_stop_threshold_raw(&self, value: f64) {
+        Self::store_f64(&self.stop_threshold, value.clamp(0.0, 10.0));
+    }
+
+    fn set_emit_threshold_raw(&self, value: f64) {
+        Self::store_f64(&self.emit_threshold, value.clamp(0.0, 120.0));
+    }
+
+    fn set_anchor_icon_raw(&self, value: bool) {
+        self.anchor_icon.store(value, Ordering::Relaxed);
+    }
+
+    fn set_raw_input_mode_raw(&self, value: RawInputMode) {
+        self.raw_input_mode_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_scroll_under_cursor_raw(&self, value: bool) {
+        self.scroll_under_cursor.store(value, Ordering::Relaxed);
+    }
+
+    fn set_tray_icon_path_raw(&self, value: Option<String>) {
+        if let Ok(mut guard) = self.tray_icon_path.lock() {
+            *guard = value;
+        }
+    }
+
+    fn set_log_level_raw(&self, value: LogLevel) {
+        self.log_level_bits.store(value.to_u64(), Ordering::Relaxed);
+        log::set_level(value);
+    }
+
+    fn set_hires_timer_raw(&self, value: bool) {
+        self.hires_timer.store(value, Ordering::Relaxed);
+    }
+
+    fn set_smooth_horizontal_raw(&self, value: bool) {
+        self.smooth_horizontal.store(value, Ordering::Relaxed);
+    }
+
+    fn set_output_method_raw(&self, value: OutputMethod) {
+        self.output_method_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_snap_to_edge_raw(&self, value: bool) {
+        self.snap_to_edge.store(value, Ordering::Relaxed);
+    }
+
+    fn set_snap_velocity_raw(&self, value: f64) {
+        Self::store_f64(&self.snap_velocity, value.clamp(0.0, 1000.0));
+    }
+
+    fn set_input_tag_raw(&self, value: f64) {
+        Self::store_f64(&self.input_tag, value.clamp(1.0, u32::MAX as f64));
+    }
+
+    fn set_turbo_button_raw(&self, value: TurboButton) {
+        self.turbo_button_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_turbo_factor_raw(&self, value: f64) {
+        Self::store_f64(&self.turbo_factor, value.clamp(1.0, 50.0));
+    }
+
+    fn set_min_notch_travel_raw(&self, value: f64) {
+        Self::store_f64(&self.min_notch_travel, value.clamp(0.0, 10.0));
+    }
+
+    fn set_sleep_when_idle_raw(&self, value: bool) {
+        self.sleep_when_idle.store(value, Ordering::Relaxed);
+    }
+
+    fn set_disable_for_classes_raw(&self, value: Option<String>) {
+        if let Ok(mut guard) = self.disable_for_classes.lock() {
+            *guard = value;
+        }
+    }
+
+    fn set_drag_smoothing_raw(&self, value: f64) {
+        Self::store_f64(&self.drag_smoothing, value.clamp(0.0, 0.95));
+    }
+
+    fn set_stop_on_focus_change_raw(&self, value: bool) {
+        self.stop_on_focus_change.store(value, Ordering::Relaxed);
+    }
+
+    fn set_drag_soft_start_px_raw(&self, value: f64) {
+        Self::store_f64(&self.drag_soft_start_px, value.clamp(0.0, 500.0));
+    }
+
+    fn set_preview_settings_raw(&self, value: bool) {
+        self.preview_settings.store(value, Ordering::Relaxed);
+    }
+
+    fn set_invert_x_raw(&self, value: bool) {
+        self.invert_x.store(value, Ordering::Relaxed);
+    }
+
+    fn set_invert_y_raw(&self, value: bool) {
+        self.invert_y.store(value, Ordering::Relaxed);
+    }
+
+    fn set_invert_x_for_classes_raw(&self, value: Option<String>) {
+        if let Ok(mut guard) = self.invert_x_for_classes.lock() {
+            *guard = value;
+        }
+    }
+
+    fn set_invert_y_for_classes_raw(&self, value: Option<String>) {
+        if let Ok(mut guard) = self.invert_y_for_classes.lock() {
+            *guard = value;
+        }
+    }
+
+    fn set_tray_status_delay_ms_raw(&self, value: f64) {
+        Self::store_f64(&self.tray_status_delay_ms, value.clamp(0.0, 5000.0));
+    }
+
+    fn set_single_instance_raw(&self, value: bool) {
+        self.single_instance.store(value, Ordering::Relaxed);
+    }
+
+    fn set_max_coast_ms_raw(&self, value: f64) {
+        Self::store_f64(&self.max_coast_ms, value.clamp(0.0, 60000.0));
+    }
+
+    fn set_quantize_to_lines_raw(&self, value: bool) {
+        self.quantize_to_lines.store(value, Ordering::Relaxed);
+    }
+
+    fn set_batch_ms_raw(&self, value: f64) {
+        Self::store_f64(&self.batch_ms, value.clamp(0.0, 100.0));
+    }
+
+    fn set_axis_order_raw(&self, value: AxisOrder) {
+        self.axis_order_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_pulse_raw(&self, value: bool) {
+        self.pulse.store(value, Ordering::Relaxed);
+    }
+
+    fn set_last_preset_raw(&self, value: Option<String>) {
+        if let Ok(mut guard) = self.last_preset.lock() {
+            *guard = value;
+        }
+    }
+
+    fn set_back_button_action_raw(&self, value: ButtonAction) {
+        self.back_button_action_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_forward_button_action_raw(&self, value: ButtonAction) {
+        self.forward_button_action_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_max_continuous_scroll_ms_raw(&self, value: f64) {
+        Self::store_f64(&self.max_continuous_scroll_ms, value.clamp(0.0, 60000.0));
+    }
+
+    fn set_low_latency_first_emit_raw(&self, value: bool) {
+        self.low_latency_first_emit.store(value, Ordering::Relaxed);
+    }
+
+    fn set_temp_invert_key_raw(&self, value: f64) {
+        Self::store_f64(&self.temp_invert_key, value.clamp(0.0, 65535.0));
+    }
+
+    fn set_inertia_carryover_raw(&self, value: f64) {
+        Self::store_f64(&self.inertia_carryover, value.clamp(0.0, 2.0));
+    }
+
+    fn set_log_format_raw(&self, value: LogFormat) {
+        self.log_format_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+        log::set_format(value);
+    }
+
+    fn set_auto_scroll_raw(&self, value: bool) {
+        self.auto_scroll.store(value, Ordering::Relaxed);
+    }
+
+    fn set_auto_scroll_speed_raw(&self, value: f64) {
+        Self::store_f64(&self.auto_scroll_speed, value.clamp(0.1, 200.0));
+    }
+
+    fn set_wheel_mode_raw(&self, value: WheelMode) {
+        self.wheel_mode_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_soft_land_raw(&self, value: bool) {
+        self.soft_land.store(value, Ordering::Relaxed);
+    }
+
+    fn set_soft_land_velocity_raw(&self, value: f64) {
+        Self::store_f64(&self.soft_land_velocity, value.clamp(0.0, 120.0));
+    }
+
+    fn set_per_axis_momentum_raw(&self, value: bool) {
+        self.per_axis_momentum.store(value, Ordering::Relaxed);
+    }
+
+    fn set_decay_velocity_scale_raw(&self, value: f64) {
+        Self::store_f64(&self.decay_velocity_scale, value.clamp(0.0, 1.0));
+    }
+
+    fn set_tray_single_raw(&self, value: TrayAction) {
+        self.tray_single_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_tray_double_raw(&self, value: TrayAction) {
+        self.tray_double_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_disable_file_raw(&self, value: Option<String>) {
+        if let Ok(mut guard) = self.disable_file.lock() {
+            *guard = value;
+        }
+    }
+
+    fn set_flick_notch_rate_raw(&self, value: f64) {
+        Self::store_f64(&self.flick_notch_rate, value.clamp(0.0, 100.0));
+    }
+
+    fn set_scroll_sound_raw(&self, value: bool) {
+        self.scroll_sound.store(value, Ordering::Relaxed);
+    }
+
+    fn set_scroll_sound_file_raw(&self, value: Option<String>) {
+        if let Ok(mut guard) = self.scroll_sound_file.lock() {
+            *guard = value;
+        }
+    }
+
+    fn set_max_notches_per_tick_raw(&self, value: f64) {
+        Self::store_f64(&self.max_notches_per_tick, value.clamp(0.0, 1000.0));
+    }
+
+    fn set_fine_key_raw(&self, value: f64) {
+        Self::store_f64(&self.fine_key, value.clamp(0.0, 65535.0));
+    }
+
+    fn set_fine_factor_raw(&self, value: f64) {
+        Self::store_f64(&self.fine_factor, value.clamp(0.01, 1.0));
+    }
+
+    fn set_zoom_for_classes_raw(&self, value: Option<String>) {
+        if let Ok(mut guard) = self.zoom_for_classes.lock() {
+            *guard = value;
+        }
+    }
+
+    fn set_wheel_off_for_classes_raw(&self, value: Option<String>) {
+        if let Ok(mut guard) = self.wheel_off_for_classes.lock() {
+            *guard = value;
+        }
+    }
+
+    fn set_startup_delay_ms_raw(&self, value: f64) {
+        Self::store_f64(&self.startup_delay_ms, value.clamp(0.0, 120000.0));
+    }
+
+    fn set_edge_detect_raw(&self, value: bool) {
+        self.edge_detect.store(value, Ordering::Relaxed);
+    }
+
+    fn set_input_start_delay_ms_raw(&self, value: f64) {
+        Self::store_f64(&self.input_start_delay_ms, value.clamp(0.0, 120000.0));
+    }
+
+    fn set_stack_mode_raw(&self, value: StackMode) {
+        self.stack_mode_bits
+            .store(value.to_u64(), Ordering::Relaxed);
+    }
+
+    fn set_conflict_check_raw(&self, value: bool) {
+        self.conflict_check.store(value, Ordering::Relaxed);
+    }
+
+    fn set_conflict_apps_raw(&self, value: Option<String>) {
+        if let Ok(mut guard) = self.conflict_apps.lock() {
+            *guard = value;
+        }
+    }
+
+    fn set_smooth_vertical_raw(&self, value: bool) {
+        self.smooth_vertical.store(value, Ordering::Relaxed);
+    }
+
+    fn set_paused_raw(&self, value: bool) {
+        self.paused.store(value, Ordering::Relaxed);
+    }
+
+    fn set_start_paused_raw(&self, value: bool) {
+        self.start_paused.store(value, Ordering::Relaxed);
+    }
+
+    fn set_pause_cooldown_ms_raw(&self, value: f64) {
+        Self::store_f64(&self.pause_cooldown_ms, value.clamp(0.0, 5000.0));
+    }
+
+    fn set_mouse_device_path_raw(&self, value: Option<String>) {
+        if let Ok(mut guard) = self.mouse_device_path.lock() {
+            *guard = value;
+        }
+    }
+
+    pub fn normal_wheel_gain(&self) -> f64 {
+        Self::load_f64(&self.normal_wheel_gain)
+    }
+    pub fn set_normal_wheel_gain(&self, value: f64) {
+        self.set_normal_wheel_gain_raw(value);
+        self.save_edit();
+    }
+
+    pub fn normal_wheel_gain_h(&self) -> f64 {
+        Self::load_f64(&self.normal_wheel_gain_h)
+    }
+    pub fn set_normal_wheel_gain_h(&self, value: f64) {
+        self.set_normal_wheel_gain_h_raw(value);
+        self.save_edit();
+    }
+
+    pub fn normal_wheel_damping(&self) -> f64 {
+        Self::load_f64(&self.normal_wheel_damping)
+    }
+    pub fn set_normal_wheel_damping(&self, value: f64) {
+        self.set_normal_wheel_damping_raw(value);
+        self.save_edit();
+    }
+
+    pub fn drag_wheel_gain(&self) -> f64 {
+        Self::load_f64(&self.drag_wheel_gain)
+    }
+    pub fn set_drag_wheel_gain(&self, value: f64) {
+        self.set_drag_wheel_gain_raw(value);
+        self.save_edit();
+    }
+
+    pub fn drag_wheel_gain_h(&self) -> f64 {
+        Self::load_f64(&self.drag_wheel_gain_h)
+    }
+    pub fn set_drag_wheel_gain_h(&self, value: f64) {
+        self.set_drag_wheel_gain_h_raw(value);
+        self.save_edit();
+    }
+
+    pub fn drag_wheel_damping(&self) -> f64 {
+        Self::load_f64(&self.drag_wheel_damping)
+    }
+    pub fn set_drag_wheel_damping(&self, value: f64) {
+        self.set_drag_wheel_damping_raw(value);
+        self.save_edit();
+    }
+
+    pub fn drag_deadzone_px(&self) -> f64 {
+        Self::load_f64(&self.drag_deadzone_px)
+    }
+    pub fn set_drag_deadzone_px(&self, value: f64) {
+        self.set_drag_deadzone_px_raw(value);
+        self.save_edit();
+    }
+
+    pub fn tap_max_duration_ms(&self) -> u64 {
+        Self::load_f64(&self.tap_max_duration_ms).round().max(1.0) as u64
+    }
+    pub fn set_tap_max_duration_ms(&self, value: f64) {
+        self.set_tap_max_duration_ms_raw(value);
+        self.save_edit();
+    }
+
+    pub fn loop_sleep_ms(&self) -> u64 {
+        Self::load_f64(&self.loop_sleep_ms).round().max(1.0) as u64
+    }
+    pub fn set_loop_sleep_ms(&self, value: f64) {
+        self.set_loop_sleep_ms_raw(value);
+        self.save_edit();
+    }
+
+    pub fn max_velocity_hires(&self) -> f64 {
+        Self::load_f64(&self.max_velocity_hires).max(0.0)
+    }
+    pub fn set_max_velocity_hires(&self, value: f64) {
+        self.set_max_velocity_hires_raw(value);
+        self.save_edit();
+    }
+
+    pub fn easing_kind(&self) -> EasingKind {
+        EasingKind::from_u64(self.easing_kind_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_easing_kind(&self, value: EasingKind) {
+        self.set_easing_kind_raw(value);
+        self.save_edit();
+    }
+
+    pub fn smooth_enabled(&self) -> bool {
+        self.smooth_enabled.load(Ordering::Relaxed)
+    }
+    pub fn set_smooth_enabled(&self, value: bool) {
+        self.set_smooth_enabled_raw(value);
+        self.save_edit();
+    }
+
+    pub fn middle_scroll_enabled(&self) -> bool {
+        self.middle_scroll_enabled.load(Ordering::Relaxed)
+    }
+    pub fn set_middle_scroll_enabled(&self, value: bool) {
+        self.set_middle_scroll_enabled_raw(value);
+        self.save_edit();
+    }
+
+    pub fn output_curve(&self) -> OutputCurve {
+        OutputCurve::from_u64(self.output_curve_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_output_curve(&self, value: OutputCurve) {
+        self.set_output_curve_raw(value);
+        self.save_edit();
+    }
+
+    pub fn broadcast_scroll(&self) -> bool {
+        self.broadcast_scroll.load(Ordering::Relaxed)
+    }
+    pub fn set_broadcast_scroll(&self, value: bool) {
+        self.set_broadcast_scroll_raw(value);
+        self.save_edit();
+    }
+
+    pub fn clip_grace_ms(&self) -> u64 {
+        Self::load_f64(&self.clip_grace_ms).round().max(0.0) as u64
+    }
+    pub fn set_clip_grace_ms(&self, value: f64) {
+        self.set_clip_grace_ms_raw(value);
+        self.save_edit();
+    }
+
+    pub fn lang(&self) -> Lang {
+        Lang::from_u64(self.lang_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_lang(&self, value: Lang) {
+        self.set_lang_raw(value);
+        let _ = self.save_to_disk();
+    }
+
+    pub fn auto_disable_fullscreen(&self) -> bool {
+        self.auto_disable_fullscreen.load(Ordering::Relaxed)
+    }
+    pub fn set_auto_disable_fullscreen(&self, value: bool) {
+        self.set_auto_disable_fullscreen_raw(value);
+        self.save_edit();
+    }
+
+    pub fn decay_model(&self) -> DecayModel {
+        DecayModel::from_u64(self.decay_model_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_decay_model(&self, value: DecayModel) {
+        self.set_decay_model_raw(value);
+        self.save_edit();
+    }
+
+    pub fn spring_stiffness(&self) -> f64 {
+        Self::load_f64(&self.spring_stiffness)
+    }
+    pub fn set_spring_stiffness(&self, value: f64) {
+        self.set_spring_stiffness_raw(value);
+        self.save_edit();
+    }
+
+    pub fn spring_damping(&self) -> f64 {
+        Self::load_f64(&self.spring_damping)
+    }
+    pub fn set_spring_damping(&self, value: f64) {
+        self.set_spring_damping_raw(value);
+        self.save_edit();
+    }
+
+    pub fn stop_threshold(&self) -> f64 {
+        Self::load_f64(&self.stop_threshold)
+    }
+    pub fn set_stop_threshold(&self, value: f64) {
+        self.set_stop_threshold_raw(value);
+        self.save_edit();
+    }
+
+    pub fn emit_threshold(&self) -> f64 {
+        Self::load_f64(&self.emit_threshold)
+    }
+    pub fn set_emit_threshold(&self, value: f64) {
+        self.set_emit_threshold_raw(value);
+        self.save_edit();
+    }
+
+    pub fn anchor_icon(&self) -> bool {
+        self.anchor_icon.load(Ordering::Relaxed)
+    }
+    pub fn set_anchor_icon(&self, value: bool) {
+        self.set_anchor_icon_raw(value);
+        self.save_edit();
+    }
+
+    pub fn raw_input_mode(&self) -> RawInputMode {
+        RawInputMode::from_u64(self.raw_input_mode_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_raw_input_mode(&self, value: RawInputMode) {
+        self.set_raw_input_mode_raw(value);
+        self.save_edit();
+    }
+
+    pub fn scroll_under_cursor(&self) -> bool {
+        self.scroll_under_cursor.load(Ordering::Relaxed)
+    }
+    pub fn set_scroll_under_cursor(&self, value: bool) {
+        self.set_scroll_under_cursor_raw(value);
+        self.save_edit();
+    }
+
+    pub fn tray_icon_path(&self) -> Option<String> {
+        self.tray_icon_path
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
+    }
+    pub fn set_tray_icon_path(&self, value: Option<String>) {
+        self.set_tray_icon_path_raw(value);
+        self.save_edit();
+    }
+
+    pub fn log_level(&self) -> LogLevel {
+        LogLevel::from_u64(self.log_level_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_log_level(&self, value: LogLevel) {
+        self.set_log_level_raw(value);
+        self.save_edit();
+    }
+
+    pub fn hires_timer(&self) -> bool {
+        self.hires_timer.load(Ordering::Relaxed)
+    }
+    pub fn set_hires_timer(&self, value: bool) {
+        self.set_hires_timer_raw(value);
+        self.save_edit();
+    }
+
+    pub fn smooth_horizontal(&self) -> bool {
+        self.smooth_horizontal.load(Ordering::Relaxed)
+    }
+    pub fn set_smooth_horizontal(&self, value: bool) {
+        self.set_smooth_horizontal_raw(value);
+        self.save_edit();
+    }
+
+    pub fn output_method(&self) -> OutputMethod {
+        OutputMethod::from_u64(self.output_method_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_output_method(&self, value: OutputMethod) {
+        self.set_output_method_raw(value);
+        self.save_edit();
+    }
+
+    pub fn snap_to_edge(&self) -> bool {
+        self.snap_to_edge.load(Ordering::Relaxed)
+    }
+    pub fn set_snap_to_edge(&self, value: bool) {
+        self.set_snap_to_edge_raw(value);
+        self.save_edit();
+    }
+
+    pub fn snap_velocity(&self) -> f64 {
+        Self::load_f64(&self.snap_velocity)
+    }
+    pub fn set_snap_velocity(&self, value: f64) {
+        self.set_snap_velocity_raw(value);
+        self.save_edit();
+    }
+
+    pub fn input_tag(&self) -> u64 {
+        Self::load_f64(&self.input_tag).round().max(0.0) as u64
+    }
+    pub fn set_input_tag(&self, value: f64) {
+        self.set_input_tag_raw(value);
+        self.save_edit();
+    }
+
+    pub fn turbo_button(&self) -> TurboButton {
+        TurboButton::from_u64(self.turbo_button_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_turbo_button(&self, value: TurboButton) {
+        self.set_turbo_button_raw(value);
+        self.save_edit();
+    }
+
+    pub fn turbo_factor(&self) -> f64 {
+        Self::load_f64(&self.turbo_factor)
+    }
+    pub fn set_turbo_factor(&self, value: f64) {
+        self.set_turbo_factor_raw(value);
+        self.save_edit();
+    }
+
+    pub fn min_notch_travel(&self) -> f64 {
+        Self::load_f64(&self.min_notch_travel)
+    }
+    pub fn set_min_notch_travel(&self, value: f64) {
+        self.set_min_notch_travel_raw(value);
+        self.save_edit();
+    }
+
+    pub fn sleep_when_idle(&self) -> bool {
+        self.sleep_when_idle.load(Ordering::Relaxed)
+    }
+    pub fn set_sleep_when_idle(&self, value: bool) {
+        self.set_sleep_when_idle_raw(value);
+        self.save_edit();
+    }
+
+    pub fn disable_for_classes(&self) -> Option<String> {
+        self.disable_for_classes
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
+    }
+    pub fn set_disable_for_classes(&self, value: Option<String>) {
+        self.set_disable_for_classes_raw(value);
+        self.save_edit();
+    }
+    pub fn is_class_disabled(&self, class: &str) -> bool {
+        Self::class_listed(&self.disable_for_classes, class)
+    }
+    fn class_listed(list: &Mutex<Option<String>>, class: &str) -> bool {
+        let Ok(guard) = list.lock() else {
+            return false;
+        };
+        guard.as_deref().is_some_and(|list| {
+            list.split(',')
+                .map(str::trim)
+                .any(|entry| !entry.is_empty() && entry.eq_ignore_ascii_case(class))
+        })
+    }
+
+    pub fn drag_smoothing(&self) -> f64 {
+        Self::load_f64(&self.drag_smoothing)
+    }
+    pub fn set_drag_smoothing(&self, value: f64) {
+        self.set_drag_smoothing_raw(value);
+        self.save_edit();
+    }
+
+    pub fn stop_on_focus_change(&self) -> bool {
+        self.stop_on_focus_change.load(Ordering::Relaxed)
+    }
+    pub fn set_stop_on_focus_change(&self, value: bool) {
+        self.set_stop_on_focus_change_raw(value);
+        self.save_edit();
+    }
+
+    pub fn drag_soft_start_px(&self) -> f64 {
+        Self::load_f64(&self.drag_soft_start_px)
+    }
+    pub fn set_drag_soft_start_px(&self, value: f64) {
+        self.set_drag_soft_start_px_raw(value);
+        self.save_edit();
+    }
+
+    pub fn preview_settings(&self) -> bool {
+        self.preview_settings.load(Ordering::Relaxed)
+    }
+    pub fn set_preview_settings(&self, value: bool) {
+        self.set_preview_settings_raw(value);
+        self.save_edit();
+    }
+
+    pub fn invert_x(&self) -> bool {
+        self.invert_x.load(Ordering::Relaxed)
+    }
+    pub fn set_invert_x(&self, value: bool) {
+        self.set_invert_x_raw(value);
+        self.save_edit();
+    }
+
+    pub fn invert_y(&self) -> bool {
+        self.invert_y.load(Ordering::Relaxed)
+    }
+    pub fn set_invert_y(&self, value: bool) {
+        self.set_invert_y_raw(value);
+        self.save_edit();
+    }
+
+    pub fn invert_x_for_classes(&self) -> Option<String> {
+        self.invert_x_for_classes
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
+    }
+    pub fn set_invert_x_for_classes(&self, value: Option<String>) {
+        self.set_invert_x_for_classes_raw(value);
+        self.save_edit();
+    }
+
+    pub fn invert_y_for_classes(&self) -> Option<String> {
+        self.invert_y_for_classes
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
+    }
+    pub fn set_invert_y_for_classes(&self, value: Option<String>) {
+        self.set_invert_y_for_classes_raw(value);
+        self.save_edit();
+    }
+    /// Zoom wins when a class is in both lists.
+    pub fn wheel_action(&self, class: Option<&str>) -> WheelAction {
+        let listed = |list| class.is_some_and(|class| Self::class_listed(list, class));
+        if listed(&self.zoom_for_classes) {
+            WheelAction::Zoom
+        } else if listed(&self.wheel_off_for_classes) {
+            WheelAction::None
+        } else {
+            WheelAction::Scroll
+        }
+    }
+
+    /// Direction for (horizontal, vertical) scrolling in the given window class: a listed
+    /// class flips the global setting for that axis only.
+    pub fn scroll_inversion(&self, class: Option<&str>) -> (bool, bool) {
+        let listed = |list| class.is_some_and(|class| Self::class_listed(list, class));
+        (
+            self.invert_x() != listed(&self.invert_x_for_classes),
+            self.invert_y() != listed(&self.invert_y_for_classes),
+        )
+    }
+
+    pub fn tray_status_delay_ms(&self) -> u64 {
+        Self::load_f64(&self.tray_status_delay_ms).round().max(0.0) as u64
+    }
+    pub fn set_tray_status_delay_ms(&self, value: f64) {
+        self.set_tray_status_delay_ms_raw(value);
+        self.save_edit();
+    }
+
+    pub fn single_instance(&self) -> bool {
+        self.single_instance.load(Ordering::Relaxed)
+    }
+    pub fn set_single_instance(&self, value: bool) {
+        self.set_single_instance_raw(value);
+        self.save_edit();
+    }
+
+    pub fn max_coast_ms(&self) -> u64 {
+        Self::load_f64(&self.max_coast_ms).round().max(0.0) as u64
+    }
+    pub fn set_max_coast_ms(&self, value: f64) {
+        self.set_max_coast_ms_raw(value);
+        self.save_edit();
+    }
+
+    pub fn quantize_to_lines(&self) -> bool {
+        self.quantize_to_lines.load(Ordering::Relaxed)
+    }
+    pub fn set_quantize_to_lines(&self, value: bool) {
+        self.set_quantize_to_lines_raw(value);
+        self.save_edit();
+    }
+
+    pub fn batch_ms(&self) -> u64 {
+        Self::load_f64(&self.batch_ms).round().max(0.0) as u64
+    }
+    pub fn set_batch_ms(&self, value: f64) {
+        self.set_batch_ms_raw(value);
+        self.save_edit();
+    }
+
+    pub fn axis_order(&self) -> AxisOrder {
+        AxisOrder::from_u64(self.axis_order_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_axis_order(&self, value: AxisOrder) {
+        self.set_axis_order_raw(value);
+        self.save_edit();
+    }
+
+    pub fn pulse(&self) -> bool {
+        self.pulse.load(Ordering::Relaxed)
+    }
+    pub fn set_pulse(&self, value: bool) {
+        self.set_pulse_raw(value);
+        self.save_edit();
+    }
+
+    pub fn last_preset(&self) -> Option<String> {
+        self.last_preset.lock().ok().and_then(|guard| guard.clone())
+    }
+    pub fn set_last_preset(&self, value: Option<String>) {
+        self.set_last_preset_raw(value);
+        let _ = self.save_to_disk();
+    }
+
+    pub fn back_button_action(&self) -> ButtonAction {
+        ButtonAction::from_u64(self.back_button_action_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_back_button_action(&self, value: ButtonAction) {
+        self.set_back_button_action_raw(value);
+        self.save_edit();
+    }
+
+    pub fn forward_button_action(&self) -> ButtonAction {
+        ButtonAction::from_u64(self.forward_button_action_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_forward_button_action(&self, value: ButtonAction) {
+        self.set_forward_button_action_raw(value);
+        self.save_edit();
+    }
+
+    pub fn max_continuous_scroll_ms(&self) -> u64 {
+        Self::load_f64(&self.max_continuous_scroll_ms)
+            .round()
+            .max(0.0) as u64
+    }
+    pub fn set_max_continuous_scroll_ms(&self, value: f64) {
+        self.set_max_continuous_scroll_ms_raw(value);
+        self.save_edit();
+    }
+
+    pub fn low_latency_first_emit(&self) -> bool {
+        self.low_latency_first_emit.load(Ordering::Relaxed)
+    }
+    pub fn set_low_latency_first_emit(&self, value: bool) {
+        self.set_low_latency_first_emit_raw(value);
+        self.save_edit();
+    }
+
+    pub fn temp_invert_key(&self) -> u64 {
+        Self::load_f64(&self.temp_invert_key).round().max(0.0) as u64
+    }
+    pub fn set_temp_invert_key(&self, value: f64) {
+        self.set_temp_invert_key_raw(value);
+        self.save_edit();
+    }
+
+    pub fn inertia_carryover(&self) -> f64 {
+        Self::load_f64(&self.inertia_carryover)
+    }
+    pub fn set_inertia_carryover(&self, value: f64) {
+        self.set_inertia_carryover_raw(value);
+        self.save_edit();
+    }
 
-        fs::write(path, text)
+    pub fn log_format(&self) -> LogFormat {
+        LogFormat::from_u64(self.log_format_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_log_format(&self, value: LogFormat) {
+        self.set_log_format_raw(value);
+        self.save_edit();
     }
 
-    fn set_normal_wheel_gain_raw(&self, value: f64) {
-        Self::store_f64(&self.normal_wheel_gain, value.clamp(0.0, 1.0));
+    pub fn auto_scroll(&self) -> bool {
+        self.auto_scroll.load(Ordering::Relaxed)
+    }
+    pub fn set_auto_scroll(&self, value: bool) {
+        self.set_auto_scroll_raw(value);
+        self.save_edit();
     }
 
-    fn set_normal_wheel_damping_raw(&self, value: f64) {
-        Self::store_f64(&self.normal_wheel_damping, value.clamp(0.0, 1.0));
+    pub fn auto_scroll_speed(&self) -> f64 {
+        Self::load_f64(&self.auto_scroll_speed)
+    }
+    pub fn set_auto_scroll_speed(&self, value: f64) {
+        self.set_auto_scroll_speed_raw(value);
+        self.save_edit();
     }
 
-    fn set_drag_wheel_gain_raw(&self, value: f64) {
-        Self::store_f64(&self.drag_wheel_gain, value.clamp(0.0, 1.0));
+    pub fn wheel_mode(&self) -> WheelMode {
+        WheelMode::from_u64(self.wheel_mode_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_wheel_mode(&self, value: WheelMode) {
+        self.set_wheel_mode_raw(value);
+        self.save_edit();
     }
 
-    fn set_drag_wheel_damping_raw(&self, value: f64) {
-        Self::store_f64(&self.drag_wheel_damping, value.clamp(0.0, 1.0));
+    pub fn soft_land(&self) -> bool {
+        self.soft_land.load(Ordering::Relaxed)
+    }
+    pub fn set_soft_land(&self, value: bool) {
+        self.set_soft_land_raw(value);
+        self.save_edit();
     }
 
-    fn set_drag_deadzone_px_raw(&self, value: f64) {
-        Self::store_f64(&self.drag_deadzone_px, value.max(0.0));
+    pub fn soft_land_velocity(&self) -> f64 {
+        Self::load_f64(&self.soft_land_velocity)
+    }
+    pub fn set_soft_land_velocity(&self, value: f64) {
+        self.set_soft_land_velocity_raw(value);
+        self.save_edit();
     }
 
-    fn set_tap_max_duration_ms_raw(&self, value: f64) {
-        Self::store_f64(&self.tap_max_duration_ms, value.max(1.0));
+    pub fn per_axis_momentum(&self) -> bool {
+        self.per_axis_momentum.load(Ordering::Relaxed)
+    }
+    pub fn set_per_axis_momentum(&self, value: bool) {
+        self.set_per_axis_momentum_raw(value);
+        self.save_edit();
     }
 
-    fn set_loop_sleep_ms_raw(&self, value: f64) {
-        Self::store_f64(&self.loop_sleep_ms, value.max(1.0));
+    pub fn decay_velocity_scale(&self) -> f64 {
+        Self::load_f64(&self.decay_velocity_scale)
+    }
+    pub fn set_decay_velocity_scale(&self, value: f64) {
+        self.set_decay_velocity_scale_raw(value);
+        self.save_edit();
     }
 
-    fn set_max_velocity_hires_raw(&self, value: f64) {
-        Self::store_f64(&self.max_velocity_hires, value.max(0.0));
+    pub fn tray_single(&self) -> TrayAction {
+        TrayAction::from_u64(self.tray_single_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_tray_single(&self, value: TrayAction) {
+        self.set_tray_single_raw(value);
+        self.save_edit();
     }
 
-    fn set_easing_kind_raw(&self, value: EasingKind) {
-        self.easing_kind_bits
-            .store(value.to_u64(), Ordering::Relaxed);
+    pub fn tray_double(&self) -> TrayAction {
+        TrayAction::from_u64(self.tray_double_bits.load(Ordering::Relaxed))
+    }
+    pub fn set_tray_double(&self, value: TrayAction) {
+        self.set_tray_double_raw(value);
+        self.save_edit();
     }
 
-    fn set_smooth_enabled_raw(&self, value: bool) {
-        self.smooth_enabled.store(value, Ordering::Relaxed);
+    pub fn disable_file(&self) -> Option<String> {
+        self.disable_file
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
+    }
+    pub fn set_disable_file(&self, value: Option<String>) {
+        self.set_disable_file_raw(value);
+        self.save_edit();
     }
 
-    fn set_middle_scroll_enabled_raw(&self, value: bool) {
-        self.middle_scroll_enabled.store(value, Ordering::Relaxed);
+    pub fn flick_notch_rate(&self) -> f64 {
+        Self::load_f64(&self.flick_notch_rate)
+    }
+    pub fn set_flick_notch_rate(&self, value: f64) {
+        self.set_flick_notch_rate_raw(value);
+        self.save_edit();
     }
 
-    fn set_mouse_device_path_raw(&self, value: Option<String>) {
-        if let Ok(mut guard) = self.mouse_device_path.lock() {
-            *guard = value;
-        }
+    pub fn scroll_sound(&self) -> bool {
+        self.scroll_sound.load(Ordering::Relaxed)
+    }
+    pub fn set_scroll_sound(&self, value: bool) {
+        self.set_scroll_sound_raw(value);
+        self.save_edit();
     }
 
-    pub fn normal_wheel_gain(&self) -> f64 {
-        Self::load_f64(&self.normal_wheel_gain)
+    pub fn scroll_sound_file(&self) -> Option<String> {
+        self.scroll_sound_file
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
     }
-    pub fn set_normal_wheel_gain(&self, value: f64) {
-        self.set_normal_wheel_gain_raw(value);
-        let _ = self.save_to_disk();
+    pub fn set_scroll_sound_file(&self, value: Option<String>) {
+        self.set_scroll_sound_file_raw(value);
+        self.save_edit();
     }
 
-    pub fn normal_wheel_damping(&self) -> f64 {
-        Self::load_f64(&self.normal_wheel_damping)
+    pub fn max_notches_per_tick(&self) -> f64 {
+        Self::load_f64(&self.max_notches_per_tick)
     }
-    pub fn set_normal_wheel_damping(&self, value: f64) {
-        self.set_normal_wheel_damping_raw(value);
-        let _ = self.save_to_disk();
+    pub fn set_max_notches_per_tick(&self, value: f64) {
+        self.set_max_notches_per_tick_raw(value);
+        self.save_edit();
     }
 
-    pub fn drag_wheel_gain(&self) -> f64 {
-        Self::load_f64(&self.drag_wheel_gain)
+    pub fn fine_key(&self) -> u64 {
+        Self::load_f64(&self.fine_key).round().max(0.0) as u64
     }
-    pub fn set_drag_wheel_gain(&self, value: f64) {
-        self.set_drag_wheel_gain_raw(value);
-        let _ = self.save_to_disk();
+    pub fn set_fine_key(&self, value: f64) {
+        self.set_fine_key_raw(value);
+        self.save_edit();
     }
 
-    pub fn drag_wheel_damping(&self) -> f64 {
-        Self::load_f64(&self.drag_wheel_damping)
+    pub fn fine_factor(&self) -> f64 {
+        Self::load_f64(&self.fine_factor)
     }
-    pub fn set_drag_wheel_damping(&self, value: f64) {
-        self.set_drag_wheel_damping_raw(value);
-        let _ = self.save_to_disk();
+    pub fn set_fine_factor(&self, value: f64) {
+        self.set_fine_factor_raw(value);
+        self.save_edit();
     }
 
-    pub fn drag_deadzone_px(&self) -> f64 {
-        Self::load_f64(&self.drag_deadzone_px)
+    pub fn zoom_for_classes(&self) -> Option<String> {
+        self.zoom_for_classes
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
     }
-    pub fn set_drag_deadzone_px(&self, value: f64) {
-        self.set_drag_deadzone_px_raw(value);
-        let _ = self.save_to_disk();
+    pub fn set_zoom_for_classes(&self, value: Option<String>) {
+        self.set_zoom_for_classes_raw(value);
+        self.save_edit();
     }
 
-    pub fn tap_max_duration_ms(&self) -> u64 {
-        Self::load_f64(&self.tap_max_duration_ms).round().max(1.0) as u64
+    pub fn wheel_off_for_classes(&self) -> Option<String> {
+        self.wheel_off_for_classes
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
     }
-    pub fn set_tap_max_duration_ms(&self, value: f64) {
-        self.set_tap_max_duration_ms_raw(value);
-        let _ = self.save_to_disk();
+    pub fn set_wheel_off_for_classes(&self, value: Option<String>) {
+        self.set_wheel_off_for_classes_raw(value);
+        self.save_edit();
     }
 
-    pub fn loop_sleep_ms(&self) -> u64 {
-        Self::load_f64(&self.loop_sleep_ms).round().max(1.0) as u64
+    pub fn startup_delay_ms(&self) -> u64 {
+        Self::load_f64(&self.startup_delay_ms).round().max(0.0) as u64
     }
-    pub fn set_loop_sleep_ms(&self, value: f64) {
-        self.set_loop_sleep_ms_raw(value);
-        let _ = self.save_to_disk();
+    pub fn set_startup_delay_ms(&self, value: f64) {
+        self.set_startup_delay_ms_raw(value);
+        self.save_edit();
     }
 
-    pub fn max_velocity_hires(&self) -> f64 {
-        Self::load_f64(&self.max_velocity_hires).max(0.0)
+    pub fn edge_detect(&self) -> bool {
+        self.edge_detect.load(Ordering::Relaxed)
     }
-    pub fn set_max_velocity_hires(&self, value: f64) {
-        self.set_max_velocity_hires_raw(value);
-        let _ = self.save_to_disk();
+    pub fn set_edge_detect(&self, value: bool) {
+        self.set_edge_detect_raw(value);
+        self.save_edit();
     }
 
-    pub fn easing_kind(&self) -> EasingKind {
-        EasingKind::from_u64(self.easing_kind_bits.load(Ordering::Relaxed))
+    pub fn input_start_delay_ms(&self) -> u64 {
+        Self::load_f64(&self.input_start_delay_ms).round().max(0.0) as u64
     }
-    pub fn set_easing_kind(&self, value: EasingKind) {
-        self.set_easing_kind_raw(value);
-        let _ = self.save_to_disk();
+    pub fn set_input_start_delay_ms(&self, value: f64) {
+        self.set_input_start_delay_ms_raw(value);
+        self.save_edit();
     }
 
-    pub fn smooth_enabled(&self) -> bool {
-        self.smooth_enabled.load(Ordering::Relaxed)
+    pub fn stack_mode(&self) -> StackMode {
+        StackMode::from_u64(self.stack_mode_bits.load(Ordering::Relaxed))
     }
-    pub fn set_smooth_enabled(&self, value: bool) {
-        self.set_smooth_enabled_raw(value);
-        let _ = self.save_to_disk();
+    pub fn set_stack_mode(&self, value: StackMode) {
+        self.set_stack_mode_raw(value);
+        self.save_edit();
     }
 
-    pub fn middle_scroll_enabled(&self) -> bool {
-        self.middle_scroll_enabled.load(Ordering::Relaxed)
+    pub fn conflict_check(&self) -> bool {
+        self.conflict_check.load(Ordering::Relaxed)
     }
-    pub fn set_middle_scroll_enabled(&self, value: bool) {
-        self.set_middle_scroll_enabled_raw(value);
+    pub fn set_conflict_check(&self, value: bool) {
+        self.set_conflict_check_raw(value);
+        self.save_edit();
+    }
+
+    pub fn conflict_apps(&self) -> Option<String> {
+        self.conflict_apps
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
+    }
+    pub fn set_conflict_apps(&self, value: Option<String>) {
+        self.set_conflict_apps_raw(value);
+        self.save_edit();
+    }
+
+    pub fn smooth_vertical(&self) -> bool {
+        self.smooth_vertical.load(Ordering::Relaxed)
+    }
+    pub fn set_smooth_vertical(&self, value: bool) {
+        self.set_smooth_vertical_raw(value);
+        self.save_edit();
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+    pub fn set_paused(&self, value: bool) {
+        self.set_paused_raw(value);
         let _ = self.save_to_disk();
     }
 
+    pub fn start_paused(&self) -> bool {
+        self.start_paused.load(Ordering::Relaxed)
+    }
+    pub fn set_start_paused(&self, value: bool) {
+        self.set_start_paused_raw(value);
+        self.save_edit();
+    }
+
+    pub fn pause_cooldown_ms(&self) -> u64 {
+        Self::load_f64(&self.pause_cooldown_ms).round().max(0.0) as u64
+    }
+    pub fn set_pause_cooldown_ms(&self, value: f64) {
+        self.set_pause_cooldown_ms_raw(value);
+        self.save_edit();
+    }
+
     pub fn mouse_device_path(&self) -> Option<String> {
         self.mouse_device_path
             .lock()
@@ -803,10 +4570,142 @@ pub(crate) fn request_exit() {
     EXIT_REQUESTED.store(true, Ordering::Relaxed);
 }
 
+pub(crate) fn set_wheel_scroll_units(lines: u32, chars: u32) {
+    WHEEL_LINES.store(lines, Ordering::Relaxed);
+    WHEEL_CHARS.store(chars, Ordering::Relaxed);
+}
+
 pub(crate) fn should_exit() -> bool {
     EXIT_REQUESTED.load(Ordering::Relaxed)
 }
 
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EngineState {
+    Running = 0,
+    Pausing = 1,
+    Paused = 2,
+    Resuming = 3,
+}
+
+impl EngineState {
+    const fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Pausing,
+            2 => Self::Paused,
+            3 => Self::Resuming,
+            _ => Self::Running,
+        }
+    }
+
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Pausing => "pausing",
+            Self::Paused => "paused",
+            Self::Resuming => "resuming",
+        }
+    }
+}
+
+pub(crate) fn engine_state() -> EngineState {
+    EngineState::from_u8(ENGINE_STATE.load(Ordering::Acquire))
+}
+
+/// Moves the engine from `from` to `to`, failing if another transition got there first.
+pub(crate) fn transition_engine(from: EngineState, to: EngineState) -> bool {
+    let moved = ENGINE_STATE
+        .compare_exchange(from as u8, to as u8, Ordering::AcqRel, Ordering::Acquire)
+        .is_ok();
+    if moved {
+        log_info!("engine {} -> {}", from.label(), to.label());
+    }
+    moved
+}
+
+/// Puts the engine in the paused state before the scroll loop starts, as `paused` or
+/// `start_paused` ask.
+pub(crate) fn start_paused() {
+    PAUSE_WANTED.store(true, Ordering::Release);
+    if transition_engine(EngineState::Running, EngineState::Paused) {
+        log_info!("starting paused");
+    }
+}
+
+/// Flips the wanted pause state. A toggle within `pause_cooldown_ms` of the last switch, or
+/// mid-transition, only changes where the engine ends up, so a burst costs one switch.
+pub(crate) fn toggle_pause() {
+    PAUSE_WANTED.fetch_xor(true, Ordering::AcqRel);
+    resolve_pause();
+}
+
+/// Starts pausing or resuming when the engine is settled away from the wanted state and the
+/// cooldown has passed; the scroll loop calls it every tick to pick up deferred toggles.
+pub(crate) fn resolve_pause() -> bool {
+    let wanted = PAUSE_WANTED.load(Ordering::Acquire);
+    let (from, to) = match engine_state() {
+        EngineState::Running if wanted => (EngineState::Running, EngineState::Pausing),
+        EngineState::Paused if !wanted => (EngineState::Paused, EngineState::Resuming),
+        _ => return false,
+    };
+
+    let cooldown = Duration::from_millis(config().pause_cooldown_ms());
+    let Ok(mut switched_at) = PAUSE_SWITCHED_AT.lock() else {
+        return false;
+    };
+    if switched_at.is_some_and(|at| at.elapsed() < cooldown) {
+        return false;
+    }
+    if !transition_engine(from, to) {
+        return false;
+    }
+    *switched_at = Some(Instant::now());
+    true
+}
+
+/// Records whether the `disable_file` kill switch is present, logging each change.
+pub(crate) fn set_disabled(value: bool) {
+    if DISABLED.swap(value, Ordering::Relaxed) != value {
+        if value {
+            log_info!("disable file present, passing input through");
+        } else {
+            log_info!("disable file removed, smoothing again");
+        }
+    }
+}
+
+pub(crate) fn is_disabled() -> bool {
+    DISABLED.load(Ordering::Relaxed)
+}
+
+pub(crate) fn set_suspended(value: bool) {
+    SUSPENDED.store(value, Ordering::Relaxed);
+}
+
+pub(crate) fn is_suspended() -> bool {
+    SUSPENDED.load(Ordering::Relaxed)
+}
+
+pub(crate) fn set_wheel_action(value: WheelAction) {
+    WHEEL_ACTION.store(value.to_u64(), Ordering::Relaxed);
+}
+
+pub(crate) fn wheel_action() -> WheelAction {
+    WheelAction::from_u64(WHEEL_ACTION.load(Ordering::Relaxed))
+}
+
+pub(crate) fn set_auto_scrolling(value: bool) {
+    AUTO_SCROLLING.store(value, Ordering::Relaxed);
+}
+
+pub(crate) fn is_auto_scrolling() -> bool {
+    AUTO_SCROLLING.load(Ordering::Relaxed)
+}
+
+pub(crate) fn is_pause_requested() -> bool {
+    PAUSE_WANTED.load(Ordering::Acquire)
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct MouseDeviceInfo {
     pub(crate) label: String,
@@ -816,11 +4715,17 @@ pub(crate) struct MouseDeviceInfo {
 #[derive(Debug, Default)]
 pub(crate) struct ModifierState {
     pub(crate) win_down: bool,
+    pub(crate) invert_down: bool,
+    pub(crate) fine_down: bool,
 }
 
 impl ModifierState {
     pub const fn new() -> Self {
-        Self { win_down: false }
+        Self {
+            win_down: false,
+            invert_down: false,
+            fine_down: false,
+        }
     }
 }
 
@@ -879,6 +4784,36 @@ impl MiddleDragState {
     }
 }
 
+/// Tells slow, deliberate notches from a flick for `flick_notch_rate`, by how fast wheel
+/// travel arrives on one axis.
+#[derive(Debug)]
+pub(crate) struct FlickGate {
+    last: Option<Instant>,
+    discrete: bool,
+}
+
+impl FlickGate {
+    pub const fn new() -> Self {
+        Self {
+            last: None,
+            discrete: false,
+        }
+    }
+
+    /// Records `hires` units of travel and returns whether they should go out as a plain step
+    /// instead of feeding momentum. A burst already coasting keeps coasting.
+    pub(crate) fn update(&mut self, hires: f64, coasting: bool, threshold: f64) -> bool {
+        let now = Instant::now();
+        let rate = self.last.map_or(0.0, |last| {
+            let secs = now.duration_since(last).as_secs_f64().max(0.001);
+            hires.abs() / 120.0 / secs
+        });
+        self.last = Some(now);
+        self.discrete = threshold > 0.0 && !coasting && rate < threshold;
+        self.discrete
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum ScrollKey {
     LeftMeta,
@@ -891,6 +4826,13 @@ pub(crate) enum ScrollKey {
     Forward,
     Back,
     Task,
+    LeftCtrl,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Equal,
+    Minus,
     Other(u16),
 }
 
@@ -911,56 +4853,308 @@ pub(crate) enum InputEvent {
     Rel { axis: ScrollAxis, value: i32 },
 }
 
+impl InputEvent {
+    pub(crate) const fn is_wheel(self) -> bool {
+        matches!(
+            self,
+            Self::Rel {
+                axis: ScrollAxis::Wheel
+                    | ScrollAxis::WheelHiRes
+                    | ScrollAxis::HWheel
+                    | ScrollAxis::HWheelHiRes,
+                ..
+            }
+        )
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum OutputEvent {
     Key { key: ScrollKey, value: i32 },
     Rel { axis: ScrollAxis, value: i32 },
 }
 
+impl OutputEvent {
+    /// Reverses wheel output on the flagged axes; pointer motion is left alone.
+    pub(crate) fn inverted(self, x: bool, y: bool) -> Self {
+        match self {
+            Self::Rel {
+                axis: axis @ (ScrollAxis::HWheel | ScrollAxis::HWheelHiRes),
+                value,
+            } if x => Self::Rel {
+                axis,
+                value: -value,
+            },
+            Self::Rel {
+                axis: axis @ (ScrollAxis::Wheel | ScrollAxis::WheelHiRes),
+                value,
+            } if y => Self::Rel {
+                axis,
+                value: -value,
+            },
+            other => other,
+        }
+    }
+}
+
+impl From<InputEvent> for OutputEvent {
+    fn from(event: InputEvent) -> Self {
+        match event {
+            InputEvent::Key { key, value } => Self::Key { key, value },
+            InputEvent::Rel { axis, value } => Self::Rel { axis, value },
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct MomentumAxis {
     pub(crate) velocity_hires: f64,
+    pub(crate) target_hires: f64,
+    // Travel still owed to the per-notch minimum; only the exponential model uses it.
+    pub(crate) floor_hires: f64,
     pub(crate) hires_accum: f64,
     pub(crate) detent_accum: f64,
+    // Time stepped since momentum was last added.
+    pub(crate) coast: Duration,
+    // Time stepped since the axis last started from rest or reversed.
+    pub(crate) run: Duration,
 }
 
 impl MomentumAxis {
     pub const fn new() -> Self {
         Self {
             velocity_hires: 0.0,
+            target_hires: 0.0,
+            floor_hires: 0.0,
             hires_accum: 0.0,
             detent_accum: 0.0,
+            coast: Duration::ZERO,
+            run: Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.halt();
+        self.hires_accum = 0.0;
+        self.detent_accum = 0.0;
+        self.coast = Duration::ZERO;
+        self.run = Duration::ZERO;
+    }
+
+    /// Stops the motion but leaves the accumulators, so travel already owed is still emitted.
+    fn halt(&mut self) {
+        self.velocity_hires = 0.0;
+        self.target_hires = 0.0;
+        self.floor_hires = 0.0;
+    }
+
+    /// Restarts the same-direction run when the axis was at rest or `distance` reverses it.
+    fn track_run(&mut self, distance: f64) {
+        let moving = self.velocity_hires + self.target_hires;
+        if self.is_settled() || distance.signum() != moving.signum() {
+            self.run = Duration::ZERO;
+        }
+    }
+
+    pub(crate) fn push_detents(&mut self, input_detents: f64, gain: f64, max_velocity: f64) {
+        self.track_run(input_detents * gain);
+        self.velocity_hires += input_detents * 120.0 * gain;
+        self.velocity_hires = self.velocity_hires.clamp(-max_velocity, max_velocity);
+        self.coast = Duration::ZERO;
+    }
+
+    /// Like `push_detents`, but momentum already heading the same way is only raised to the
+    /// notch's own speed, never summed with it.
+    pub(crate) fn top_up_detents(&mut self, input_detents: f64, gain: f64, max_velocity: f64) {
+        let push = (input_detents * 120.0 * gain).clamp(-max_velocity, max_velocity);
+        self.track_run(push);
+        self.velocity_hires = top_up(self.velocity_hires, push).clamp(-max_velocity, max_velocity);
+        self.coast = Duration::ZERO;
+    }
+
+    /// Scales momentum still heading the same way as `distance` before a new notch lands on it,
+    /// within the same limits a single push is held to.
+    pub(crate) fn carry_over(
+        &mut self,
+        distance: f64,
+        carryover: f64,
+        max_velocity: f64,
+        max_target: f64,
+    ) {
+        if self.velocity_hires * distance > 0.0 {
+            self.velocity_hires =
+                (self.velocity_hires * carryover).clamp(-max_velocity, max_velocity);
+        }
+        if self.target_hires * distance > 0.0 {
+            self.target_hires = (self.target_hires * carryover).clamp(-max_target, max_target);
+        }
+    }
+
+    pub(crate) fn push_target(&mut self, distance_hires: f64) {
+        self.track_run(distance_hires);
+        self.target_hires += distance_hires;
+        self.coast = Duration::ZERO;
+    }
+
+    pub(crate) fn top_up_target(&mut self, distance_hires: f64) {
+        self.track_run(distance_hires);
+        self.target_hires = top_up(self.target_hires, distance_hires);
+        self.coast = Duration::ZERO;
+    }
+
+    /// Clears the axis if a bad config combination has pushed it to NaN or infinity, so one
+    /// bad tick cannot wedge scrolling until restart.
+    fn sanitize(&mut self) -> bool {
+        let finite = self.velocity_hires.is_finite()
+            && self.target_hires.is_finite()
+            && self.floor_hires.is_finite()
+            && self.hires_accum.is_finite()
+            && self.detent_accum.is_finite();
+        if !finite {
+            log_error!(
+                "momentum went non-finite (velocity {}, target {}), resetting",
+                self.velocity_hires,
+                self.target_hires
+            );
+            self.clear();
+        }
+        finite
+    }
+
+    pub(crate) fn is_settled(&self) -> bool {
+        self.velocity_hires == 0.0 && self.target_hires == 0.0 && self.floor_hires == 0.0
+    }
+
+    pub(crate) fn push_floor(&mut self, distance_hires: f64) {
+        if self.floor_hires.signum() != distance_hires.signum() {
+            self.floor_hires = 0.0;
+        }
+        self.floor_hires += distance_hires;
+    }
+
+    pub(crate) fn top_up_floor(&mut self, distance_hires: f64) {
+        if self.floor_hires.signum() != distance_hires.signum() {
+            self.floor_hires = 0.0;
+        }
+        self.floor_hires = top_up(self.floor_hires, distance_hires);
+    }
+
+    pub(crate) fn tick(
+        &mut self,
+        damping: f64,
+        stop_threshold: f64,
+        soft_land: f64,
+        dt: Duration,
+        easing_kind: EasingKind,
+    ) {
+        let send = self.step(damping, stop_threshold, soft_land, dt, easing_kind);
+        self.accumulate(send);
+    }
+
+    fn tick_scale(dt: Duration, easing_kind: EasingKind) -> f64 {
+        let raw_scale = base_ticks(dt).clamp(0.25, MAX_TICK_SCALE);
+        let normalized = ((raw_scale - 0.25) / (MAX_TICK_SCALE - 0.25)).clamp(0.0, 1.0);
+        let eased = easing_kind.apply(normalized).clamp(0.0, 1.0);
+        0.25 + eased * (MAX_TICK_SCALE - 0.25)
+    }
+
+    /// Advances the exponential model by one tick. A non-zero `soft_land` is the velocity below
+    /// which the decay gives way to a linear ramp to zero, in place of the `stop_threshold` snap.
+    pub(crate) fn step(
+        &mut self,
+        damping: f64,
+        stop_threshold: f64,
+        soft_land: f64,
+        dt: Duration,
+        easing_kind: EasingKind,
+    ) -> f64 {
+        if !self.sanitize() {
+            return 0.0;
+        }
+
+        let scale = Self::tick_scale(dt, easing_kind);
+        let decay = damping.powf(scale);
+
+        // What `scale` base ticks of the decay would have sent, so a coast covers the same
+        // distance however the loop slices time; at the base tick this is just the velocity.
+        let mut send = if damping < 1.0 {
+            self.velocity_hires * (1.0 - decay) / (1.0 - damping)
+        } else {
+            self.velocity_hires * scale
+        };
+
+        if self.floor_hires != 0.0 {
+            let creep = self.floor_hires.abs().min(FLOOR_CREEP_HIRES * scale);
+            if send.abs() < creep {
+                send = creep.copysign(self.floor_hires);
+            }
+            if send.signum() == self.floor_hires.signum() {
+                if send.abs() >= self.floor_hires.abs() {
+                    self.floor_hires = 0.0;
+                } else {
+                    self.floor_hires -= send;
+                }
+            }
         }
-    }
 
-    pub(crate) fn clear(&mut self) {
-        self.velocity_hires = 0.0;
-        self.hires_accum = 0.0;
-        self.detent_accum = 0.0;
-    }
+        let cutoff = if soft_land > 0.0 {
+            VELOCITY_EPSILON
+        } else {
+            stop_threshold.max(VELOCITY_EPSILON)
+        };
+        if self.velocity_hires.abs() < soft_land {
+            // Hold the deceleration the decay had on reaching `soft_land`, so the stop is smooth.
+            let ramp = soft_land * (1.0 - decay);
+            self.velocity_hires =
+                (self.velocity_hires.abs() - ramp).max(0.0) * self.velocity_hires.signum();
+        } else {
+            self.velocity_hires *= decay;
+        }
 
-    pub(crate) fn push_detents(&mut self, input_detents: f64, gain: f64, max_velocity: f64) {
-        self.velocity_hires += input_detents * 120.0 * gain;
-        self.velocity_hires = self.velocity_hires.clamp(-max_velocity, max_velocity);
+        if self.velocity_hires.abs() < cutoff {
+            self.velocity_hires = 0.0;
+        }
+
+        send
     }
 
-    pub(crate) fn tick(&mut self, damping: f64, dt: Duration, easing_kind: EasingKind) {
-        let dt_ms = dt.as_secs_f64() * 1000.0;
+    pub(crate) fn step_spring(
+        &mut self,
+        stiffness: f64,
+        damping: f64,
+        dt: Duration,
+        easing_kind: EasingKind,
+    ) -> f64 {
+        if !self.sanitize() || (self.target_hires == 0.0 && self.velocity_hires == 0.0) {
+            return 0.0;
+        }
 
-        let base_dt = 1000.0 / 144.0;
-        let raw_scale = (dt_ms / base_dt).clamp(0.25, 4.0);
-        let normalized = ((raw_scale - 0.25) / 3.75).clamp(0.0, 1.0);
-        let eased = easing_kind.apply(normalized).clamp(0.0, 1.0);
-        let scale = 0.25 + eased * 3.75;
+        let scale = Self::tick_scale(dt, easing_kind);
 
-        self.hires_accum += self.velocity_hires * scale;
+        let accel = stiffness * self.target_hires - damping * self.velocity_hires;
+        self.velocity_hires += accel * scale;
 
-        let effective_damping = damping.powf(scale);
-        self.velocity_hires *= effective_damping;
+        let mut send = self.velocity_hires * scale;
+        if send.abs() >= self.target_hires.abs() && send.signum() == self.target_hires.signum() {
+            send = self.target_hires;
+            self.target_hires = 0.0;
+            self.velocity_hires = 0.0;
+            return send;
+        }
+        self.target_hires -= send;
 
-        if self.velocity_hires.abs() < VELOCITY_EPSILON {
+        if self.target_hires.abs() < 0.5 && self.velocity_hires.abs() < 0.05 {
+            send += self.target_hires;
+            self.target_hires = 0.0;
             self.velocity_hires = 0.0;
         }
+
+        send
+    }
+
+    pub(crate) fn accumulate(&mut self, send: f64) {
+        self.hires_accum += send;
+
         if self.hires_accum.abs() < ACCUM_EPSILON {
             self.hires_accum = 0.0;
         }
@@ -969,8 +5163,34 @@ impl MomentumAxis {
         }
     }
 
-    pub(crate) fn drain(&mut self) -> (i32, i32) {
-        let hires = trunc_to_i32(self.hires_accum);
+    pub(crate) fn drain(
+        &mut self,
+        emit_threshold: f64,
+        quantum: f64,
+        max_hires: f64,
+    ) -> (i32, i32) {
+        let moving =
+            self.velocity_hires != 0.0 || self.target_hires != 0.0 || self.floor_hires != 0.0;
+        if moving && self.hires_accum.abs() < emit_threshold {
+            return (0, 0);
+        }
+
+        // A long stall can step a huge delta at once; past the cap it stays in the accumulator
+        // and goes out over the following ticks instead of as one jump. The cap is at least one
+        // quantum, or a tiny setting would hold output back for good.
+        let pending = if max_hires > 0.0 {
+            let cap = max_hires.max(quantum);
+            self.hires_accum.clamp(-cap, cap)
+        } else {
+            self.hires_accum
+        };
+        let hires = if quantum > 1.0 {
+            // Whole lines only, rounded away from zero so apps that truncate still see them.
+            let value = (pending / quantum).trunc() * quantum;
+            trunc_to_i32(value.abs().ceil().copysign(value))
+        } else {
+            trunc_to_i32(pending)
+        };
         self.hires_accum -= hires as f64;
 
         self.detent_accum += hires as f64 / 120.0;
@@ -980,8 +5200,14 @@ impl MomentumAxis {
         (hires, detents)
     }
 
-    pub(crate) fn drain_events(&mut self, vertical: bool) -> Vec<OutputEvent> {
-        let (hires, detents) = self.drain();
+    pub(crate) fn drain_events(
+        &mut self,
+        vertical: bool,
+        emit_threshold: f64,
+        quantum: f64,
+        max_hires: f64,
+    ) -> Vec<OutputEvent> {
+        let (hires, detents) = self.drain(emit_threshold, quantum, max_hires);
         let mut out = Vec::with_capacity(2);
 
         if hires != 0 {
@@ -1082,6 +5308,26 @@ pub(crate) struct ScrollController {
     immediate_drag_h: ImmediateAxis,
     middle: MiddleDragState,
     modifiers: ModifierState,
+    hires_wheel_v: bool,
+    hires_wheel_h: bool,
+    flick_v: FlickGate,
+    flick_h: FlickGate,
+    turbo_held: bool,
+    // Wheel travel not yet sent as a zoom keystroke, in notches.
+    zoom_accum: f64,
+    drag_filter_x: f64,
+    drag_filter_y: f64,
+    drag_travel: f64,
+    // Time stepped since momentum was last drained, for batch_ms.
+    batch_elapsed: Duration,
+    // Which axis led the last emitting tick, for AxisOrder::Alternate.
+    vertical_led: bool,
+    // Pointer offset from where autoscroll started, while a middle click has it running.
+    auto_scroll: Option<(f64, f64)>,
+    // The button whose click ended autoscroll; its release is kept from the app too.
+    swallowed_release: Option<ScrollKey>,
+    // A middle press kept from the app until it is known whether the click starts autoscroll.
+    held_middle: bool,
 }
 
 impl ScrollController {
@@ -1095,9 +5341,37 @@ impl ScrollController {
             immediate_drag_h: ImmediateAxis::new(),
             middle: MiddleDragState::new(),
             modifiers: ModifierState::new(),
+            hires_wheel_v: false,
+            hires_wheel_h: false,
+            flick_v: FlickGate::new(),
+            flick_h: FlickGate::new(),
+            turbo_held: false,
+            zoom_accum: 0.0,
+            drag_filter_x: 0.0,
+            drag_filter_y: 0.0,
+            drag_travel: 0.0,
+            batch_elapsed: Duration::ZERO,
+            vertical_led: false,
+            auto_scroll: None,
+            swallowed_release: None,
+            held_middle: false,
         }
     }
 
+    pub fn reset_device_state(&mut self) {
+        self.hires_wheel_v = false;
+        self.hires_wheel_h = false;
+        self.turbo_held = false;
+        self.modifiers.invert_down = false;
+        self.modifiers.fine_down = false;
+    }
+
+    /// Layers a held `temp_invert_key` over the permanent inversion, so the two cancel out.
+    pub fn inversion(&self, (invert_x, invert_y): (bool, bool)) -> (bool, bool) {
+        let flip = self.modifiers.invert_down;
+        (invert_x != flip, invert_y != flip)
+    }
+
     pub fn clear_scroll_state(&mut self) {
         self.normal_wheel_v.clear();
         self.normal_wheel_h.clear();
@@ -1106,17 +5380,102 @@ impl ScrollController {
         self.immediate_drag_v.clear();
         self.immediate_drag_h.clear();
         self.middle.clear();
+        self.auto_scroll = None;
+        self.held_middle = false;
+        self.zoom_accum = 0.0;
+    }
+
+    /// Turns vertical wheel travel into Ctrl+= / Ctrl+- taps, one per whole notch, for windows
+    /// in `zoom_for_classes`. Nothing coasts, no wheel event goes out and tilt is dropped.
+    pub fn zoom_steps(&mut self, input: InputEvent) -> Vec<OutputEvent> {
+        let InputEvent::Rel { axis, value } = input else {
+            return Vec::new();
+        };
+        match axis {
+            ScrollAxis::WheelHiRes => {
+                self.hires_wheel_v = true;
+                self.zoom_accum += value as f64 / 120.0;
+            }
+            ScrollAxis::Wheel if !self.hires_wheel_v => self.zoom_accum += value as f64,
+            _ => return Vec::new(),
+        }
+
+        let steps = trunc_to_i32(self.zoom_accum);
+        self.zoom_accum -= steps as f64;
+        let key = if steps > 0 {
+            ScrollKey::Equal
+        } else {
+            ScrollKey::Minus
+        };
+        (0..steps.unsigned_abs())
+            .flat_map(|_| Self::chord(ScrollKey::LeftCtrl, key))
+            .collect()
+    }
+
+    /// Drops coasting momentum for `stop_on_focus_change`. A held middle-button drag keeps
+    /// going, so its pointer clip is not pulled out from under it.
+    pub fn drop_momentum(&mut self) {
+        self.normal_wheel_v.clear();
+        self.normal_wheel_h.clear();
+        self.auto_scroll = None;
+        if self.middle.pressed_at.is_none() {
+            self.drag_wheel_v.clear();
+            self.drag_wheel_h.clear();
+        }
+    }
+
+    /// Ends vertical coasting for `edge_detect` once the document cannot move any further. A held
+    /// middle-button drag keeps its own momentum.
+    pub fn stop_vertical(&mut self) {
+        self.normal_wheel_v.clear();
+        if self.middle.pressed_at.is_none() {
+            self.drag_wheel_v.clear();
+        }
+    }
+
+    pub fn is_auto_scrolling(&self) -> bool {
+        self.auto_scroll.is_some()
+    }
+
+    pub fn is_drag_scrolling(&self, cfg: &ScrollConfig) -> bool {
+        cfg.middle_scroll_enabled() && self.middle.is_scroll_mode(cfg.tap_max_duration_ms())
+    }
+
+    pub fn cancel_drag(&mut self) {
+        self.immediate_drag_v.clear();
+        self.immediate_drag_h.clear();
+        self.middle.clear();
     }
 
     pub fn handle_input(&mut self, input: InputEvent, cfg: &ScrollConfig) -> Vec<OutputEvent> {
         match input {
-            InputEvent::Key { key, value } => self.handle_key(key, value),
+            InputEvent::Key { key, value } => self.handle_key(key, value, cfg),
             InputEvent::Rel { axis, value } => self.handle_rel(axis, value, cfg),
         }
     }
 
-    fn handle_key(&mut self, key: ScrollKey, value: i32) -> Vec<OutputEvent> {
+    fn handle_key(&mut self, key: ScrollKey, value: i32, cfg: &ScrollConfig) -> Vec<OutputEvent> {
         let mut out = Vec::new();
+        let invert_key = cfg.temp_invert_key();
+        if invert_key != 0 && key == ScrollKey::Other(invert_key as u16) {
+            self.modifiers.invert_down = value != 0;
+        }
+        let fine_key = cfg.fine_key();
+        if fine_key != 0 && key == ScrollKey::Other(fine_key as u16) {
+            self.modifiers.fine_down = value != 0;
+        }
+
+        // Any click ends autoscroll without reaching the app underneath. Windows cannot block
+        // raw input, so there the mouse hook holds the physical click back.
+        if self.auto_scroll.is_some() && value == 1 && Self::is_mouse_button(key) {
+            self.auto_scroll = None;
+            self.swallowed_release = Some(key);
+            return out;
+        }
+        if value == 0 && self.swallowed_release == Some(key) {
+            self.swallowed_release = None;
+            return out;
+        }
 
         match key {
             ScrollKey::LeftMeta | ScrollKey::RightMeta => {
@@ -1136,14 +5495,48 @@ impl ScrollController {
 
             ScrollKey::Middle => {
                 if value == 1 {
-                    self.middle.begin();
+                    // With middle scroll off the button is a plain click; skip drag tracking.
+                    if cfg.middle_scroll_enabled() {
+                        self.middle.begin();
+                        self.drag_filter_x = 0.0;
+                        self.drag_filter_y = 0.0;
+                        self.drag_travel = 0.0;
+                        if cfg.auto_scroll() {
+                            self.held_middle = true;
+                            return out;
+                        }
+                    }
                 } else if value == 0 {
+                    let edge = self.take_flick_edge(cfg);
+                    let tap = self.middle.pressed_at.is_some()
+                        && self.middle.is_tap(cfg.tap_max_duration_ms());
+                    let held = std::mem::take(&mut self.held_middle);
                     self.middle.clear();
+                    if tap && cfg.auto_scroll() {
+                        self.auto_scroll = Some((0.0, 0.0));
+                        // The app never saw the press, so the whole click stays ours. On Windows
+                        // the mouse hook kept the physical click back.
+                        if held {
+                            return out;
+                        }
+                    }
+                    if held {
+                        out.push(OutputEvent::Key { key, value: 1 });
+                    }
+                    out.push(OutputEvent::Key { key, value });
+                    if let Some(edge) = edge {
+                        out.extend(Self::chord(ScrollKey::LeftCtrl, edge));
+                    }
+                    return out;
                 }
 
                 out.push(OutputEvent::Key { key, value });
             }
 
+            _ if cfg.turbo_button().matches(key) => {
+                self.turbo_held = value != 0;
+            }
+
             ScrollKey::Left
             | ScrollKey::Right
             | ScrollKey::Side
@@ -1151,9 +5544,22 @@ impl ScrollController {
             | ScrollKey::Forward
             | ScrollKey::Back
             | ScrollKey::Task
-            | ScrollKey::Other(_) => {
-                out.push(OutputEvent::Key { key, value });
-            }
+            | ScrollKey::LeftCtrl
+            | ScrollKey::Home
+            | ScrollKey::End
+            | ScrollKey::PageUp
+            | ScrollKey::PageDown
+            | ScrollKey::Equal
+            | ScrollKey::Minus
+            | ScrollKey::Other(_) => match Self::button_action(key, cfg).key() {
+                // The side button itself is swallowed; its press taps the mapped key instead.
+                Some(mapped) => {
+                    if value != 0 {
+                        out.extend(Self::tap(mapped));
+                    }
+                }
+                None => out.push(OutputEvent::Key { key, value }),
+            },
         }
 
         out
@@ -1164,25 +5570,47 @@ impl ScrollController {
         let middle_scroll_mode =
             middle_scroll_enabled && self.middle.is_scroll_mode(cfg.tap_max_duration_ms());
         let smooth_enabled = cfg.smooth_enabled() && !self.modifiers.win_down;
+        let smooth_horizontal = smooth_enabled && cfg.smooth_horizontal();
+        let smooth_vertical = smooth_enabled && cfg.smooth_vertical();
+        // Turbo and fine compose when both are held; both factors are kept above zero.
+        let turbo = if self.turbo_held {
+            cfg.turbo_factor()
+        } else {
+            1.0
+        };
+        let fine = if self.modifiers.fine_down {
+            cfg.fine_factor()
+        } else {
+            1.0
+        };
+        let boost = turbo * fine;
 
         let mut out = Vec::new();
 
         match axis {
             ScrollAxis::X => {
+                if let Some((dx, _)) = self.auto_scroll.as_mut() {
+                    *dx += value as f64;
+                }
                 if middle_scroll_enabled && self.middle.pressed_at.is_some() {
                     self.middle.push_motion(value, 0, cfg.drag_deadzone_px());
                 }
 
                 if middle_scroll_mode {
-                    if smooth_enabled {
-                        self.drag_wheel_h.push_detents(
-                            -(value as f64),
-                            cfg.drag_wheel_gain(),
-                            cfg.max_velocity_hires(),
+                    let delta = Self::filter_drag(&mut self.drag_filter_x, value, cfg);
+                    let delta = self.soft_start(delta, cfg);
+                    if smooth_horizontal {
+                        Self::push_wheel(
+                            &mut self.drag_wheel_h,
+                            -delta,
+                            cfg.drag_wheel_gain_h(),
+                            cfg.drag_wheel_damping(),
+                            boost,
+                            cfg,
                         );
                     } else {
                         self.immediate_drag_h
-                            .push_detents(-(value as f64), cfg.drag_wheel_gain());
+                            .push_detents(-delta, cfg.drag_wheel_gain_h() * boost);
                         out.extend(self.immediate_drag_h.drain_events(false));
                     }
                 } else {
@@ -1191,20 +5619,28 @@ impl ScrollController {
             }
 
             ScrollAxis::Y => {
+                if let Some((_, dy)) = self.auto_scroll.as_mut() {
+                    *dy += value as f64;
+                }
                 if middle_scroll_enabled && self.middle.pressed_at.is_some() {
                     self.middle.push_motion(0, value, cfg.drag_deadzone_px());
                 }
 
                 if middle_scroll_mode {
-                    if smooth_enabled {
-                        self.drag_wheel_v.push_detents(
-                            -(value as f64),
+                    let delta = Self::filter_drag(&mut self.drag_filter_y, value, cfg);
+                    let delta = self.soft_start(delta, cfg);
+                    if smooth_vertical {
+                        Self::push_wheel(
+                            &mut self.drag_wheel_v,
+                            -delta,
                             cfg.drag_wheel_gain(),
-                            cfg.max_velocity_hires(),
+                            cfg.drag_wheel_damping(),
+                            boost,
+                            cfg,
                         );
                     } else {
                         self.immediate_drag_v
-                            .push_detents(-(value as f64), cfg.drag_wheel_gain());
+                            .push_detents(-delta, cfg.drag_wheel_gain() * boost);
                         out.extend(self.immediate_drag_v.drain_events(true));
                     }
                 } else {
@@ -1213,18 +5649,41 @@ impl ScrollController {
             }
 
             ScrollAxis::Wheel => {
-                if smooth_enabled {
-                    if middle_scroll_mode {
-                        self.drag_wheel_v.push_detents(
+                if smooth_vertical {
+                    if self.hires_wheel_v {
+                        // Already counted through the matching hi-res event, which also
+                        // decided whether this notch coasts.
+                        if !middle_scroll_mode && self.flick_v.discrete {
+                            out.push(OutputEvent::Rel { axis, value });
+                        }
+                        return out;
+                    }
+
+                    if !middle_scroll_mode
+                        && self.flick_v.update(
+                            value as f64 * 120.0,
+                            !self.normal_wheel_v.is_settled(),
+                            cfg.flick_notch_rate(),
+                        )
+                    {
+                        out.push(OutputEvent::Rel { axis, value });
+                    } else if middle_scroll_mode {
+                        Self::push_wheel(
+                            &mut self.drag_wheel_v,
                             value as f64,
-                            cfg.drag_wheel_gain(),
-                            cfg.max_velocity_hires(),
+                            Self::wheel_gain_during_drag(cfg.normal_wheel_gain(), cfg),
+                            cfg.drag_wheel_damping(),
+                            boost,
+                            cfg,
                         );
                     } else {
-                        self.normal_wheel_v.push_detents(
+                        Self::push_wheel(
+                            &mut self.normal_wheel_v,
                             value as f64,
                             cfg.normal_wheel_gain(),
-                            cfg.max_velocity_hires(),
+                            cfg.normal_wheel_damping(),
+                            boost,
+                            cfg,
                         );
                     }
                 } else {
@@ -1233,19 +5692,35 @@ impl ScrollController {
             }
 
             ScrollAxis::WheelHiRes => {
-                if smooth_enabled {
+                self.hires_wheel_v = true;
+
+                if smooth_vertical {
                     let detents = value as f64 / 120.0;
-                    if middle_scroll_mode {
-                        self.drag_wheel_v.push_detents(
+                    if !middle_scroll_mode
+                        && self.flick_v.update(
+                            value as f64,
+                            !self.normal_wheel_v.is_settled(),
+                            cfg.flick_notch_rate(),
+                        )
+                    {
+                        out.push(OutputEvent::Rel { axis, value });
+                    } else if middle_scroll_mode {
+                        Self::push_wheel(
+                            &mut self.drag_wheel_v,
                             detents,
-                            cfg.drag_wheel_gain(),
-                            cfg.max_velocity_hires(),
+                            Self::wheel_gain_during_drag(cfg.normal_wheel_gain(), cfg),
+                            cfg.drag_wheel_damping(),
+                            boost,
+                            cfg,
                         );
                     } else {
-                        self.normal_wheel_v.push_detents(
+                        Self::push_wheel(
+                            &mut self.normal_wheel_v,
                             detents,
                             cfg.normal_wheel_gain(),
-                            cfg.max_velocity_hires(),
+                            cfg.normal_wheel_damping(),
+                            boost,
+                            cfg,
                         );
                     }
                 } else {
@@ -1254,18 +5729,39 @@ impl ScrollController {
             }
 
             ScrollAxis::HWheel => {
-                if smooth_enabled {
-                    if middle_scroll_mode {
-                        self.drag_wheel_h.push_detents(
+                if smooth_horizontal {
+                    if self.hires_wheel_h {
+                        if !middle_scroll_mode && self.flick_h.discrete {
+                            out.push(OutputEvent::Rel { axis, value });
+                        }
+                        return out;
+                    }
+
+                    if !middle_scroll_mode
+                        && self.flick_h.update(
+                            value as f64 * 120.0,
+                            !self.normal_wheel_h.is_settled(),
+                            cfg.flick_notch_rate(),
+                        )
+                    {
+                        out.push(OutputEvent::Rel { axis, value });
+                    } else if middle_scroll_mode {
+                        Self::push_wheel(
+                            &mut self.drag_wheel_h,
                             value as f64,
-                            cfg.drag_wheel_gain(),
-                            cfg.max_velocity_hires(),
+                            Self::wheel_gain_during_drag(cfg.normal_wheel_gain_h(), cfg),
+                            cfg.drag_wheel_damping(),
+                            boost,
+                            cfg,
                         );
                     } else {
-                        self.normal_wheel_h.push_detents(
+                        Self::push_wheel(
+                            &mut self.normal_wheel_h,
                             value as f64,
-                            cfg.normal_wheel_gain(),
-                            cfg.max_velocity_hires(),
+                            cfg.normal_wheel_gain_h(),
+                            cfg.normal_wheel_damping(),
+                            boost,
+                            cfg,
                         );
                     }
                 } else {
@@ -1274,19 +5770,35 @@ impl ScrollController {
             }
 
             ScrollAxis::HWheelHiRes => {
-                if smooth_enabled {
+                self.hires_wheel_h = true;
+
+                if smooth_horizontal {
                     let detents = value as f64 / 120.0;
-                    if middle_scroll_mode {
-                        self.drag_wheel_h.push_detents(
+                    if !middle_scroll_mode
+                        && self.flick_h.update(
+                            value as f64,
+                            !self.normal_wheel_h.is_settled(),
+                            cfg.flick_notch_rate(),
+                        )
+                    {
+                        out.push(OutputEvent::Rel { axis, value });
+                    } else if middle_scroll_mode {
+                        Self::push_wheel(
+                            &mut self.drag_wheel_h,
                             detents,
-                            cfg.drag_wheel_gain(),
-                            cfg.max_velocity_hires(),
+                            Self::wheel_gain_during_drag(cfg.normal_wheel_gain_h(), cfg),
+                            cfg.drag_wheel_damping(),
+                            boost,
+                            cfg,
                         );
                     } else {
-                        self.normal_wheel_h.push_detents(
+                        Self::push_wheel(
+                            &mut self.normal_wheel_h,
                             detents,
-                            cfg.normal_wheel_gain(),
-                            cfg.max_velocity_hires(),
+                            cfg.normal_wheel_gain_h(),
+                            cfg.normal_wheel_damping(),
+                            boost,
+                            cfg,
                         );
                     }
                 } else {
@@ -1299,10 +5811,50 @@ impl ScrollController {
             }
         }
 
+        if let Some(press) = self.take_held_middle(cfg) {
+            out.insert(0, press);
+        }
         out
     }
 
+    /// Lets a held-back middle press through once the hold has turned into a drag.
+    fn take_held_middle(&mut self, cfg: &ScrollConfig) -> Option<OutputEvent> {
+        if !self.held_middle || !self.middle.is_scroll_mode(cfg.tap_max_duration_ms()) {
+            return None;
+        }
+        self.held_middle = false;
+        Some(OutputEvent::Key {
+            key: ScrollKey::Middle,
+            value: 1,
+        })
+    }
+
+    /// Momentum velocity as (horizontal, vertical), in hi-res units per tick.
+    pub(crate) fn velocity(&self) -> (f64, f64) {
+        (
+            self.normal_wheel_h.velocity_hires + self.drag_wheel_h.velocity_hires,
+            self.normal_wheel_v.velocity_hires + self.drag_wheel_v.velocity_hires,
+        )
+    }
+
+    /// True when no momentum is left to emit and the middle button is not held.
+    pub fn is_idle(&self) -> bool {
+        self.normal_wheel_v.is_settled()
+            && self.normal_wheel_h.is_settled()
+            && self.drag_wheel_v.is_settled()
+            && self.drag_wheel_h.is_settled()
+            && self.middle.pressed_at.is_none()
+            && self.auto_scroll.is_none()
+    }
+
     pub fn advance(&mut self, cfg: &ScrollConfig, dt: Duration) -> Vec<OutputEvent> {
+        // A middle hold that outlasts a tap is a drag even if the pointer never moves.
+        let mut out: Vec<OutputEvent> = self.take_held_middle(cfg).into_iter().collect();
+        out.extend(self.advance_momentum(cfg, dt));
+        out
+    }
+
+    fn advance_momentum(&mut self, cfg: &ScrollConfig, dt: Duration) -> Vec<OutputEvent> {
         if self.modifiers.win_down || !cfg.smooth_enabled() {
             self.normal_wheel_v.clear();
             self.normal_wheel_h.clear();
@@ -1311,23 +5863,409 @@ impl ScrollController {
             return Vec::new();
         }
 
+        if !cfg.smooth_horizontal() {
+            self.normal_wheel_h.clear();
+            self.drag_wheel_h.clear();
+        }
+        if !cfg.smooth_vertical() {
+            self.normal_wheel_v.clear();
+            self.drag_wheel_v.clear();
+        }
+
         let easing_kind = cfg.easing_kind();
 
-        self.normal_wheel_v
-            .tick(cfg.normal_wheel_damping(), dt, easing_kind);
-        self.normal_wheel_h
-            .tick(cfg.normal_wheel_damping(), dt, easing_kind);
-        self.drag_wheel_v
-            .tick(cfg.drag_wheel_damping(), dt, easing_kind);
-        self.drag_wheel_h
-            .tick(cfg.drag_wheel_damping(), dt, easing_kind);
+        let normal_damping = cfg.normal_wheel_damping();
+        let normal = Self::postprocess(
+            (
+                Self::step_wheel(
+                    &mut self.normal_wheel_h,
+                    normal_damping,
+                    dt,
+                    easing_kind,
+                    cfg,
+                ),
+                Self::step_wheel(
+                    &mut self.normal_wheel_v,
+                    normal_damping,
+                    dt,
+                    easing_kind,
+                    cfg,
+                ),
+            ),
+            cfg,
+            dt,
+        );
+        self.normal_wheel_h.accumulate(normal.0);
+        self.normal_wheel_v.accumulate(normal.1);
+
+        let drag_damping = cfg.drag_wheel_damping();
+        let drag = Self::postprocess(
+            (
+                Self::step_wheel(&mut self.drag_wheel_h, drag_damping, dt, easing_kind, cfg),
+                Self::step_wheel(&mut self.drag_wheel_v, drag_damping, dt, easing_kind, cfg),
+            ),
+            cfg,
+            dt,
+        );
+        self.drag_wheel_h.accumulate(drag.0);
+        self.drag_wheel_v.accumulate(drag.1);
+
+        // Autoscroll speed follows how far the pointer sits from where it started, like a
+        // browser's; below the origin scrolls down, right of it scrolls right.
+        if let Some((dx, dy)) = self.auto_scroll {
+            let deadzone = cfg.drag_deadzone_px();
+            let rate = cfg.auto_scroll_speed() * dt.as_secs_f64();
+            let speed = |offset: f64| offset.signum() * (offset.abs() - deadzone).max(0.0) * rate;
+            if cfg.smooth_vertical() {
+                self.drag_wheel_v.accumulate(-speed(dy));
+            }
+            if cfg.smooth_horizontal() {
+                self.drag_wheel_h.accumulate(speed(dx));
+            }
+        }
 
-        let mut out = Vec::new();
-        out.extend(self.normal_wheel_v.drain_events(true));
-        out.extend(self.normal_wheel_h.drain_events(false));
-        out.extend(self.drag_wheel_v.drain_events(true));
-        out.extend(self.drag_wheel_h.drain_events(false));
-        out
+        // Momentum keeps stepping every tick, but output is held back until the batch window
+        // has passed; the accumulators carry it over. Settling flushes whatever is left.
+        self.batch_elapsed += dt;
+        let settled = self.normal_wheel_v.is_settled()
+            && self.normal_wheel_h.is_settled()
+            && self.drag_wheel_v.is_settled()
+            && self.drag_wheel_h.is_settled();
+        if !settled && self.batch_elapsed < Duration::from_millis(cfg.batch_ms()) {
+            return Vec::new();
+        }
+        self.batch_elapsed = Duration::ZERO;
+
+        let emit_threshold = cfg.emit_threshold();
+        let (line, column) = Self::line_quanta(cfg);
+        let max_hires = cfg.max_notches_per_tick() * 120.0;
+        let mut vertical = self
+            .normal_wheel_v
+            .drain_events(true, emit_threshold, line, max_hires);
+        vertical.extend(
+            self.drag_wheel_v
+                .drain_events(true, emit_threshold, line, max_hires),
+        );
+        let mut horizontal =
+            self.normal_wheel_h
+                .drain_events(false, emit_threshold, column, max_hires);
+        horizontal.extend(
+            self.drag_wheel_h
+                .drain_events(false, emit_threshold, column, max_hires),
+        );
+
+        // Some apps only act on the first wheel event of a frame, so the order is configurable
+        // to keep one axis from always losing out during a diagonal scroll.
+        let vertical_first = match cfg.axis_order() {
+            AxisOrder::VerticalFirst => true,
+            AxisOrder::HorizontalFirst => false,
+            AxisOrder::Alternate if vertical.is_empty() || horizontal.is_empty() => true,
+            AxisOrder::Alternate => {
+                self.vertical_led = !self.vertical_led;
+                self.vertical_led
+            }
+        };
+        if vertical_first {
+            vertical.extend(horizontal);
+            vertical
+        } else {
+            horizontal.extend(vertical);
+            horizontal
+        }
+    }
+
+    /// Hi-res units per OS scroll line and character, or 1.0 when output is not quantized.
+    fn line_quanta(cfg: &ScrollConfig) -> (f64, f64) {
+        if !cfg.quantize_to_lines() {
+            return (1.0, 1.0);
+        }
+        // WHEEL_PAGESCROLL and unknown settings fall back to plain hi-res output.
+        let quantum = |units: u32| {
+            if (1..=120).contains(&units) {
+                120.0 / units as f64
+            } else {
+                1.0
+            }
+        };
+        (
+            quantum(WHEEL_LINES.load(Ordering::Relaxed)),
+            quantum(WHEEL_CHARS.load(Ordering::Relaxed)),
+        )
+    }
+
+    /// Wheel notches during a drag feed the drag momentum instead of a separate axis, scaled
+    /// so a notch travels as far under drag damping as it would outside a drag. Spinning the
+    /// wheel mid-drag therefore speeds up or brakes the drag without resetting it.
+    fn wheel_gain_during_drag(gain: f64, cfg: &ScrollConfig) -> f64 {
+        gain * (1.0 - cfg.drag_wheel_damping()).max(0.001)
+            / (1.0 - cfg.normal_wheel_damping()).max(0.001)
+    }
+
+    /// Low-pass for middle-drag deltas. Only the small steps a trackpoint streams are smoothed;
+    /// larger mouse motion passes straight through so regular mice gain no lag.
+    fn filter_drag(state: &mut f64, value: i32, cfg: &ScrollConfig) -> f64 {
+        let value = value as f64;
+        let smoothing = cfg.drag_smoothing();
+        if smoothing <= 0.0 || value.abs() > DRAG_SMOOTHING_MAX_DELTA {
+            *state = value;
+            return value;
+        }
+
+        *state = *state * smoothing + value * (1.0 - smoothing);
+        *state
+    }
+
+    /// Scales drag motion down near the start of a drag, ramping linearly to full sensitivity
+    /// once drag_soft_start_px of travel has accumulated since the middle press.
+    fn soft_start(&mut self, delta: f64, cfg: &ScrollConfig) -> f64 {
+        let ramp = cfg.drag_soft_start_px();
+        if ramp <= 0.0 || self.drag_travel >= ramp {
+            return delta;
+        }
+
+        let midpoint = self.drag_travel + delta.abs() * 0.5;
+        self.drag_travel += delta.abs();
+        delta * (midpoint / ramp).min(1.0)
+    }
+
+    /// A drag released fast enough jumps to the document edge instead of coasting.
+    fn take_flick_edge(&mut self, cfg: &ScrollConfig) -> Option<ScrollKey> {
+        let velocity = self.drag_wheel_v.velocity_hires;
+        if !cfg.snap_to_edge()
+            || !self.is_drag_scrolling(cfg)
+            || velocity.abs() < cfg.snap_velocity()
+        {
+            return None;
+        }
+
+        self.drag_wheel_v.clear();
+        self.drag_wheel_h.clear();
+        Some(if velocity > 0.0 {
+            ScrollKey::Home
+        } else {
+            ScrollKey::End
+        })
+    }
+
+    const fn is_mouse_button(key: ScrollKey) -> bool {
+        matches!(
+            key,
+            ScrollKey::Left
+                | ScrollKey::Right
+                | ScrollKey::Middle
+                | ScrollKey::Side
+                | ScrollKey::Extra
+                | ScrollKey::Forward
+                | ScrollKey::Back
+                | ScrollKey::Task
+        )
+    }
+
+    fn button_action(key: ScrollKey, cfg: &ScrollConfig) -> ButtonAction {
+        match key {
+            ScrollKey::Side | ScrollKey::Back => cfg.back_button_action(),
+            ScrollKey::Extra | ScrollKey::Forward => cfg.forward_button_action(),
+            _ => ButtonAction::None,
+        }
+    }
+
+    fn tap(key: ScrollKey) -> [OutputEvent; 2] {
+        [
+            OutputEvent::Key { key, value: 1 },
+            OutputEvent::Key { key, value: 0 },
+        ]
+    }
+
+    fn chord(modifier: ScrollKey, key: ScrollKey) -> [OutputEvent; 4] {
+        [
+            OutputEvent::Key {
+                key: modifier,
+                value: 1,
+            },
+            OutputEvent::Key { key, value: 1 },
+            OutputEvent::Key { key, value: 0 },
+            OutputEvent::Key {
+                key: modifier,
+                value: 0,
+            },
+        ]
+    }
+
+    fn push_wheel(
+        axis: &mut MomentumAxis,
+        detents: f64,
+        gain: f64,
+        damping: f64,
+        boost: f64,
+        cfg: &ScrollConfig,
+    ) {
+        let floor = detents * 120.0 * cfg.min_notch_travel();
+        let max_velocity = cfg.max_velocity_hires() * boost;
+        axis.carry_over(
+            detents,
+            cfg.inertia_carryover(),
+            max_velocity,
+            max_velocity / (1.0 - damping).max(0.001),
+        );
+        let top_up = cfg.stack_mode() == StackMode::Target;
+        match cfg.decay_model() {
+            DecayModel::Exp => {
+                if top_up {
+                    axis.top_up_detents(detents, gain * boost, max_velocity);
+                } else {
+                    axis.push_detents(detents, gain * boost, max_velocity);
+                }
+                if floor != 0.0 {
+                    if top_up {
+                        axis.top_up_floor(floor);
+                    } else {
+                        axis.push_floor(floor);
+                    }
+                }
+            }
+            DecayModel::Spring => {
+                // Same total travel per notch as the exponential model at its base rate.
+                let travel = detents * 120.0 * gain * boost / (1.0 - damping).max(0.001);
+                let travel = if travel.abs() < floor.abs() {
+                    floor
+                } else {
+                    travel
+                };
+                if top_up {
+                    axis.top_up_target(travel);
+                } else {
+                    axis.push_target(travel);
+                }
+            }
+        }
+    }
+
+    fn step_wheel(
+        axis: &mut MomentumAxis,
+        damping: f64,
+        dt: Duration,
+        easing_kind: EasingKind,
+        cfg: &ScrollConfig,
+    ) -> f64 {
+        // A stall longer than one step may span is stepped in slices, so no time is dropped,
+        // up to `MAX_STEPPED_MS`.
+        let max_slice = Duration::from_secs_f64(BASE_TICK_MS * MAX_TICK_SCALE / 1000.0);
+        let mut left = dt.min(Duration::from_millis(MAX_STEPPED_MS));
+        let mut send = 0.0;
+        loop {
+            let slice = left.min(max_slice);
+            left -= slice;
+            send += match cfg.decay_model() {
+                DecayModel::Exp => {
+                    let soft_land = if cfg.soft_land() {
+                        cfg.soft_land_velocity()
+                    } else {
+                        0.0
+                    };
+                    // Fast coasts lose proportionally more per tick, then ease out at the base
+                    // rate.
+                    let loss = (1.0 - damping)
+                        * (1.0 + cfg.decay_velocity_scale() * axis.velocity_hires.abs());
+                    let damping = (1.0 - loss).clamp(0.0, 1.0);
+                    axis.step(damping, cfg.stop_threshold(), soft_land, slice, easing_kind)
+                }
+                DecayModel::Spring => axis.step_spring(
+                    cfg.spring_stiffness(),
+                    cfg.spring_damping(),
+                    slice,
+                    easing_kind,
+                ),
+            };
+            if left.is_zero() || axis.is_settled() {
+                break;
+            }
+        }
+
+        let max_coast = cfg.max_coast_ms();
+        let max_run = cfg.max_continuous_scroll_ms();
+        if !axis.is_settled() {
+            axis.coast += dt;
+            axis.run += dt;
+            if max_coast > 0 && axis.coast >= Duration::from_millis(max_coast) {
+                axis.halt();
+            }
+            // A long one-way run most likely hit the end of the document; stop feeding it.
+            if max_run > 0 && axis.run >= Duration::from_millis(max_run) {
+                axis.halt();
+                axis.run = Duration::ZERO;
+            }
+        }
+        send
+    }
+
+    /// Per-tick emitted delta of one wheel notch over `span`, after the output curve, as a
+    /// share of `max_velocity_hires` so gain changes show. Without a cap the peak is 1.0.
+    pub(crate) fn decay_preview(cfg: &ScrollConfig, span: Duration) -> Vec<f64> {
+        let dt = Duration::from_millis(cfg.loop_sleep_ms().max(1));
+        let ticks = (span.as_millis() / dt.as_millis()).max(1) as usize;
+        let damping = cfg.normal_wheel_damping();
+        let easing_kind = cfg.easing_kind();
+
+        let mut axis = MomentumAxis::new();
+        Self::push_wheel(&mut axis, 1.0, cfg.normal_wheel_gain(), damping, 1.0, cfg);
+
+        let mut samples: Vec<f64> = (0..ticks)
+            .map(|_| {
+                let send = Self::step_wheel(&mut axis, damping, dt, easing_kind, cfg);
+                Self::postprocess((0.0, send), cfg, dt).1
+            })
+            .collect();
+        let limit = cfg.max_velocity_hires() * base_ticks(dt);
+        let scale = if limit > 0.0 {
+            limit
+        } else {
+            samples.iter().fold(0.0_f64, |peak, v| peak.max(v.abs()))
+        };
+        if scale > 0.0 {
+            samples.iter_mut().for_each(|v| *v /= scale);
+        }
+        samples
+    }
+
+    /// Shapes the distance stepped over `dt`. The limit is per base tick, so a long tick is
+    /// held to the same speed as several short ones.
+    fn postprocess(send: (f64, f64), cfg: &ScrollConfig, dt: Duration) -> (f64, f64) {
+        let curve = cfg.output_curve();
+        if curve == OutputCurve::Identity {
+            return send;
+        }
+
+        let (x, y) = send;
+        let limit = cfg.max_velocity_hires() * base_ticks(dt);
+        if cfg.per_axis_momentum() {
+            // Each axis is held to the limit by itself, so a fast vertical coast no longer
+            // squeezes a slow horizontal one.
+            let shape = |v: f64| curve.apply(v.abs(), limit).copysign(v);
+            return (shape(x), shape(y));
+        }
+
+        let magnitude = x.hypot(y);
+        if magnitude < ACCUM_EPSILON {
+            return send;
+        }
+
+        let shaped = curve.apply(magnitude, limit);
+        let factor = shaped / magnitude;
+        (x * factor, y * factor)
+    }
+}
+
+/// How many base ticks `dt` spans.
+fn base_ticks(dt: Duration) -> f64 {
+    dt.as_secs_f64() * 1000.0 / BASE_TICK_MS
+}
+
+/// `current` raised to `push` when both head the same way; otherwise `push` is added as is.
+fn top_up(current: f64, push: f64) -> f64 {
+    if current * push > 0.0 {
+        current.abs().max(push.abs()).copysign(push)
+    } else {
+        current + push
     }
 }
 
@@ -1338,3 +6276,320 @@ pub(crate) fn trunc_to_i32(v: f64) -> i32 {
         v.ceil() as i32
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK: Duration = Duration::from_micros(6944);
+
+    /// Steps one wheel axis until it settles and returns the distance it sent.
+    fn coast(axis: &mut MomentumAxis, damping: f64, cfg: &ScrollConfig) -> f64 {
+        let mut travel = 0.0;
+        for _ in 0..10_000 {
+            if axis.is_settled() {
+                break;
+            }
+            travel += ScrollController::step_wheel(axis, damping, TICK, EasingKind::Linear, cfg);
+        }
+        travel
+    }
+
+    #[test]
+    fn min_notch_travel_outlasts_aggressive_damping() {
+        let cfg = ScrollConfig::in_memory();
+        cfg.set_normal_wheel_damping(0.5);
+        cfg.set_min_notch_travel(1.0);
+        let gain = cfg.normal_wheel_gain();
+
+        let mut axis = MomentumAxis::new();
+        ScrollController::push_wheel(&mut axis, 1.0, gain, 0.5, 1.0, &cfg);
+        // Left to the decay alone this notch would stop after gain / (1 - damping) of a notch.
+        assert!(axis.velocity_hires / 0.5 < 120.0);
+
+        let travel = coast(&mut axis, 0.5, &cfg);
+        assert!(travel >= 120.0 - ACCUM_EPSILON, "travelled {travel}");
+    }
+
+    fn moving_axis(velocity: f64) -> MomentumAxis {
+        let mut axis = MomentumAxis::new();
+        axis.velocity_hires = velocity;
+        axis
+    }
+
+    #[test]
+    fn velocity_scaled_decay_slows_fast_coasts_harder() {
+        let cfg = ScrollConfig::in_memory();
+        cfg.set_decay_velocity_scale(0.05);
+        let damping = cfg.normal_wheel_damping();
+
+        let mut slow = moving_axis(10.0);
+        let mut fast = moving_axis(100.0);
+        ScrollController::step_wheel(&mut slow, damping, TICK, EasingKind::Linear, &cfg);
+        ScrollController::step_wheel(&mut fast, damping, TICK, EasingKind::Linear, &cfg);
+
+        assert!(fast.velocity_hires / 100.0 < slow.velocity_hires / 10.0);
+        assert!(slow.velocity_hires / 10.0 < damping);
+    }
+
+    #[test]
+    fn zero_velocity_scale_keeps_plain_decay() {
+        let cfg = ScrollConfig::in_memory();
+        assert_eq!(cfg.decay_velocity_scale(), 0.0);
+        let damping = cfg.normal_wheel_damping();
+
+        let mut scaled = moving_axis(60.0);
+        let mut plain = moving_axis(60.0);
+        for _ in 0..50 {
+            let got =
+                ScrollController::step_wheel(&mut scaled, damping, TICK, EasingKind::Linear, &cfg);
+            let want = plain.step(damping, cfg.stop_threshold(), 0.0, TICK, EasingKind::Linear);
+            assert_eq!(got, want);
+            assert_eq!(scaled.velocity_hires, plain.velocity_hires);
+        }
+    }
+
+    /// Distance one three-notch flick covers in the ticks given, in milliseconds.
+    fn travel_over(ticks_ms: &[u64], cfg: &ScrollConfig) -> f64 {
+        let damping = cfg.normal_wheel_damping();
+        let mut axis = MomentumAxis::new();
+        ScrollController::push_wheel(&mut axis, 3.0, cfg.normal_wheel_gain(), damping, 1.0, cfg);
+        ticks_ms
+            .iter()
+            .map(|&ms| {
+                let dt = Duration::from_millis(ms);
+                let send =
+                    ScrollController::step_wheel(&mut axis, damping, dt, EasingKind::Linear, cfg);
+                ScrollController::postprocess((0.0, send), cfg, dt).1
+            })
+            .sum()
+    }
+
+    #[test]
+    fn irregular_ticks_cover_the_same_distance() {
+        let cfg = ScrollConfig::in_memory();
+        cfg.set_output_curve(OutputCurve::Clamp);
+
+        let steady = travel_over(&[5; 40], &cfg);
+        // Same 200 ms, with stalls well past the largest single step the decay takes.
+        let irregular = travel_over(&[2, 9, 40, 3, 60, 5, 31, 50], &cfg);
+        assert!(
+            (steady - irregular).abs() < steady * 0.01,
+            "steady {steady}, irregular {irregular}"
+        );
+    }
+
+    #[test]
+    fn strip_comment_needs_a_blank_before_the_marker() {
+        assert_eq!(
+            ScrollConfig::strip_comment("normal_wheel_gain = 0.1 ; comment"),
+            "normal_wheel_gain = 0.1"
+        );
+        assert_eq!(ScrollConfig::strip_comment("key=value#x"), "key=value#x");
+        assert_eq!(
+            ScrollConfig::strip_comment(r#"disable_file = "a ; b" # note"#),
+            r#"disable_file = "a ; b""#
+        );
+        assert_eq!(ScrollConfig::strip_comment("# whole line"), "");
+    }
+
+    #[test]
+    fn parse_snapshot_skips_bad_lines_and_keeps_the_rest() {
+        let snap = ScrollConfig::parse_snapshot(
+            "normal_wheel_gain = 0.12 ; faster\n\
+             this line is garbage\n\
+             normal_wheel_damping = fast\n\
+             = 3\n\
+             no_such_key = 1\n\
+             disable_file=C:\\stop#1\n\
+             max_velocity_hires = 30 # cap\n\
+             smooth_horizontal = false\n\
+             smooth_horizontal = maybe\n\
+             output_curve = wobbly\n\
+             input_tag = 0\n",
+        );
+
+        assert_eq!(snap.normal_wheel_gain, 0.12);
+        assert_eq!(snap.normal_wheel_damping, DEFAULT_NORMAL_WHEEL_DAMPING);
+        assert_eq!(snap.disable_file.as_deref(), Some("C:\\stop#1"));
+        assert_eq!(snap.max_velocity_hires, 30.0);
+        // A rejected value leaves the key as it was, not reset to its default.
+        assert!(!snap.smooth_horizontal);
+        let defaults = ConfigSnapshot::defaults();
+        assert_eq!(snap.output_curve, defaults.output_curve);
+        assert_eq!(snap.input_tag, defaults.input_tag);
+    }
+
+    #[test]
+    fn per_axis_momentum_leaves_an_idle_axis_alone() {
+        let cfg = ScrollConfig::in_memory();
+        cfg.set_output_curve(OutputCurve::EaseOut);
+        cfg.set_per_axis_momentum(true);
+        let limit = cfg.max_velocity_hires();
+
+        let (x, y) = ScrollController::postprocess((0.0, 3.0 * limit), &cfg, TICK);
+        assert_eq!(x, 0.0);
+        assert!(y > 0.0 && y <= limit);
+        // A slow axis is shaped by itself, not squeezed by the fast one.
+        let (slow, _) = ScrollController::postprocess((1.0, 3.0 * limit), &cfg, TICK);
+        assert!((slow - 1.0).abs() < 0.01, "slow axis became {slow}");
+
+        let mut controller = ScrollController::new();
+        controller.handle_input(
+            InputEvent::Rel {
+                axis: ScrollAxis::Wheel,
+                value: 3,
+            },
+            &cfg,
+        );
+        let out: Vec<OutputEvent> = (0..30)
+            .flat_map(|_| controller.advance(&cfg, TICK))
+            .collect();
+        assert!(out.iter().any(|event| matches!(
+            event,
+            OutputEvent::Rel {
+                axis: ScrollAxis::WheelHiRes,
+                ..
+            }
+        )));
+        assert!(!out.iter().any(|event| matches!(
+            event,
+            OutputEvent::Rel {
+                axis: ScrollAxis::HWheel | ScrollAxis::HWheelHiRes,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn edits_made_under_a_preset_survive_a_reload() {
+        let file = "normal_wheel_gain=0.08\n\
+                    last_preset=\"Fast\"\n\
+                    \n\
+                    [preset:Fast]\n\
+                    normal_wheel_gain=0.2\n";
+        let cfg = ScrollConfig::in_memory();
+        cfg.load_text(file);
+        assert_eq!(cfg.normal_wheel_gain(), 0.2);
+
+        cfg.set_max_velocity_hires(30.0);
+        let reloaded = ScrollConfig::in_memory();
+        reloaded.load_text(&cfg.file_text());
+        assert_eq!(reloaded.max_velocity_hires(), 30.0);
+        assert_eq!(reloaded.normal_wheel_gain(), 0.2);
+        assert_eq!(reloaded.last_preset(), None);
+    }
+
+    #[test]
+    fn base_settings_outlive_a_restart_under_a_preset() {
+        let cfg = ScrollConfig::in_memory();
+        cfg.load_text("normal_wheel_gain=0.1\n\n[preset:Fast]\nnormal_wheel_gain=0.2\n");
+        assert!(cfg.apply_preset("Fast"));
+
+        let reloaded = ScrollConfig::in_memory();
+        reloaded.load_text(&cfg.file_text());
+        assert_eq!(reloaded.normal_wheel_gain(), 0.2);
+        assert!(reloaded.can_restore_pre_preset());
+        assert!(reloaded.restore_pre_preset());
+        assert_eq!(reloaded.normal_wheel_gain(), 0.1);
+    }
+
+    #[test]
+    fn presentation_preset_only_changes_the_glide() {
+        let cfg = ScrollConfig::in_memory();
+        cfg.set_wheel_mode(WheelMode::Additive);
+        cfg.set_input_tag(7.0);
+        assert!(cfg.apply_preset(PRESENTATION_PRESET));
+
+        assert_eq!(cfg.normal_wheel_gain(), 0.05);
+        assert_eq!(cfg.wheel_mode(), WheelMode::Additive);
+        assert_eq!(cfg.input_tag(), 7);
+    }
+
+    #[test]
+    fn negative_gain_reverses_the_axis_once() {
+        let cfg = ScrollConfig::in_memory();
+        assert!(cfg.set_value("normal_wheel_gain", "-0.1"));
+        assert!(cfg.set_value("normal_wheel_gain", "-0.1"));
+        assert_eq!(cfg.normal_wheel_gain(), 0.1);
+        assert_eq!(cfg.scroll_inversion(None), (false, true));
+
+        let mut controller = ScrollController::new();
+        controller.handle_input(
+            InputEvent::Rel {
+                axis: ScrollAxis::Wheel,
+                value: 1,
+            },
+            &cfg,
+        );
+        let (invert_x, invert_y) = cfg.scroll_inversion(None);
+        let sent: i32 = (0..30)
+            .flat_map(|_| controller.advance(&cfg, TICK))
+            .map(|event| match event.inverted(invert_x, invert_y) {
+                OutputEvent::Rel {
+                    axis: ScrollAxis::WheelHiRes,
+                    value,
+                } => value,
+                _ => 0,
+            })
+            .sum();
+        assert!(sent < 0, "sent {sent}");
+
+        // A legacy file with one shared gain reverses only the vertical axis.
+        let legacy = ScrollConfig::parse_snapshot("normal_wheel_gain=-0.1\n");
+        assert_eq!(legacy.normal_wheel_gain_h, 0.1);
+        assert!(legacy.invert_y && !legacy.invert_x);
+    }
+
+    #[test]
+    fn damping_at_either_end_stays_finite() {
+        let cfg = ScrollConfig::in_memory();
+        cfg.set_stop_threshold(0.0);
+
+        // Damping 1.0 keeps every bit of velocity: a steady glide that never settles.
+        let mut frictionless = moving_axis(20.0);
+        for _ in 0..1_000 {
+            let send = ScrollController::step_wheel(
+                &mut frictionless,
+                1.0,
+                TICK,
+                EasingKind::Linear,
+                &cfg,
+            );
+            assert!(send.is_finite() && send > 0.0, "sent {send}");
+        }
+        assert_eq!(frictionless.velocity_hires, 20.0);
+
+        // Damping 0.0 keeps none: the first tick sends the velocity and stops.
+        let mut dead = moving_axis(20.0);
+        let send = ScrollController::step_wheel(&mut dead, 0.0, TICK, EasingKind::Linear, &cfg);
+        assert!(send.is_finite(), "sent {send}");
+        assert!(dead.is_settled());
+    }
+
+    #[test]
+    fn a_long_stall_sends_a_bounded_burst() {
+        let cfg = ScrollConfig::in_memory();
+        let week = Duration::from_secs(7 * 24 * 60 * 60);
+
+        let damping = cfg.normal_wheel_damping();
+        let mut axis = moving_axis(20.0);
+        let send = ScrollController::step_wheel(&mut axis, damping, week, EasingKind::Linear, &cfg);
+        assert!(
+            send.is_finite() && send <= 20.0 / (1.0 - damping),
+            "sent {send}"
+        );
+        let (_, y) = ScrollController::postprocess((0.0, send), &cfg, week);
+        assert!(y.is_finite(), "postprocessed to {y}");
+
+        // Without friction only the capped stretch of the stall is scrolled.
+        let mut frictionless = moving_axis(20.0);
+        let send =
+            ScrollController::step_wheel(&mut frictionless, 1.0, week, EasingKind::Linear, &cfg);
+        let cap = 20.0 * MAX_STEPPED_MS as f64 / BASE_TICK_MS;
+        assert!(
+            send.is_finite() && send <= cap + ACCUM_EPSILON,
+            "sent {send}"
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! C interface for driving the scroll engine without the tray application.
+//!
+//! The host feeds wheel events as they arrive and calls `nimbus_tick` from its own loop; the
+//! events it gets back are what NimbusScroll would have injected. See `include/nimbus.h`.
+
+use crate::engine::{InputEvent, OutputEvent, ScrollAxis, ScrollConfig, ScrollController};
+use std::ffi::{c_char, CStr};
+use std::ptr;
+use std::time::Duration;
+
+pub const NIMBUS_AXIS_WHEEL: i32 = 0;
+pub const NIMBUS_AXIS_HWHEEL: i32 = 1;
+pub const NIMBUS_AXIS_WHEEL_HIRES: i32 = 2;
+pub const NIMBUS_AXIS_HWHEEL_HIRES: i32 = 3;
+
+pub struct NimbusEngine {
+    config: ScrollConfig,
+    controller: ScrollController,
+    pending: Vec<NimbusEvent>,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct NimbusEvent {
+    pub axis: i32,
+    pub value: i32,
+}
+
+impl NimbusEngine {
+    fn queue(&mut self, events: Vec<OutputEvent>) {
+        self.pending
+            .extend(events.into_iter().filter_map(event_from_output));
+    }
+}
+
+fn axis_from_code(code: i32) -> Option<ScrollAxis> {
+    match code {
+        NIMBUS_AXIS_WHEEL => Some(ScrollAxis::Wheel),
+        NIMBUS_AXIS_HWHEEL => Some(ScrollAxis::HWheel),
+        NIMBUS_AXIS_WHEEL_HIRES => Some(ScrollAxis::WheelHiRes),
+        NIMBUS_AXIS_HWHEEL_HIRES => Some(ScrollAxis::HWheelHiRes),
+        _ => None,
+    }
+}
+
+/// Keeps only wheel output; pointer motion and keys belong to the host.
+fn event_from_output(event: OutputEvent) -> Option<NimbusEvent> {
+    let OutputEvent::Rel { axis, value } = event else {
+        return None;
+    };
+    let axis = match axis {
+        ScrollAxis::Wheel => NIMBUS_AXIS_WHEEL,
+        ScrollAxis::HWheel => NIMBUS_AXIS_HWHEEL,
+        ScrollAxis::WheelHiRes => NIMBUS_AXIS_WHEEL_HIRES,
+        ScrollAxis::HWheelHiRes => NIMBUS_AXIS_HWHEEL_HIRES,
+        _ => return None,
+    };
+    Some(NimbusEvent { axis, value })
+}
+
+/// Creates an engine with default settings that never reads or writes the config file.
+#[no_mangle]
+pub extern "C" fn nimbus_init() -> *mut NimbusEngine {
+    Box::into_raw(Box::new(NimbusEngine {
+        config: ScrollConfig::in_memory(),
+        controller: ScrollController::new(),
+        pending: Vec::new(),
+    }))
+}
+
+/// # Safety
+///
+/// `engine` must come from `nimbus_init` and is invalid afterwards; null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn nimbus_free(engine: *mut NimbusEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
+
+/// Sets one config file key, e.g. `normal_wheel_gain` to `0.1`; false for an unknown key.
+///
+/// # Safety
+///
+/// `engine` must be live, and `key` and `value` must be null-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn nimbus_set_config(
+    engine: *mut NimbusEngine,
+    key: *const c_char,
+    value: *const c_char,
+) -> bool {
+    let Some(engine) = engine.as_mut() else {
+        return false;
+    };
+    if key.is_null() || value.is_null() {
+        return false;
+    }
+    let (Ok(key), Ok(value)) = (CStr::from_ptr(key).to_str(), CStr::from_ptr(value).to_str())
+    else {
+        return false;
+    };
+    engine.config.set_value(key, value)
+}
+
+/// Feeds one wheel event read from the device; false for an unknown axis.
+///
+/// # Safety
+///
+/// `engine` must be live.
+#[no_mangle]
+pub unsafe extern "C" fn nimbus_feed_wheel(
+    engine: *mut NimbusEngine,
+    axis: i32,
+    value: i32,
+) -> bool {
+    let Some(engine) = engine.as_mut() else {
+        return false;
+    };
+    let Some(axis) = axis_from_code(axis) else {
+        return false;
+    };
+    let events = engine
+        .controller
+        .handle_input(InputEvent::Rel { axis, value }, &engine.config);
+    engine.queue(events);
+    true
+}
+
+/// Advances momentum by `dt_ms` and writes up to `capacity` events to `out`, returning how
+/// many were written. Events that do not fit are returned by the next call.
+///
+/// # Safety
+///
+/// `engine` must be live and `out` must have room for `capacity` events.
+#[no_mangle]
+pub unsafe extern "C" fn nimbus_tick(
+    engine: *mut NimbusEngine,
+    dt_ms: f64,
+    out: *mut NimbusEvent,
+    capacity: usize,
+) -> usize {
+    let Some(engine) = engine.as_mut() else {
+        return 0;
+    };
+    let dt = Duration::try_from_secs_f64(dt_ms / 1000.0).unwrap_or_default();
+    let events = engine.controller.advance(&engine.config, dt);
+    engine.queue(events);
+
+    if out.is_null() {
+        return 0;
+    }
+    let count = engine.pending.len().min(capacity);
+    ptr::copy_nonoverlapping(engine.pending.as_ptr(), out, count);
+    engine.pending.drain(..count);
+    count
+}
@@ -0,0 +1,127 @@
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Lang {
+    English = 0,
+    Russian = 1,
+}
+
+impl Lang {
+    pub(crate) const ALL: [Self; 2] = [Self::English, Self::Russian];
+
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::Russian => "ru",
+        }
+    }
+
+    pub(crate) fn from_label(value: &str) -> Option<Self> {
+        match value {
+            "en" => Some(Self::English),
+            "ru" => Some(Self::Russian),
+            _ => None,
+        }
+    }
+
+    pub(crate) const fn name(self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::Russian => "Русский",
+        }
+    }
+
+    pub(crate) fn from_name(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|lang| lang.name() == value)
+    }
+
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            1 => Self::Russian,
+            _ => Self::English,
+        }
+    }
+
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
+    }
+
+    const fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::English => ENGLISH,
+            Self::Russian => RUSSIAN,
+        }
+    }
+}
+
+// Tray labels mark their access key with `&`, as Windows menus do.
+const ENGLISH: &[(&str, &str)] = &[
+    ("tray.settings", "&Settings"),
+    ("tray.calibrate", "&Calibrate"),
+    ("tray.presets", "Pr&esets"),
+    ("tray.restore_preset", "&Back to previous settings"),
+    ("tray.save_preset", "Save current as &preset..."),
+    ("tray.device", "Scroll &Device"),
+    ("tray.pause", "&Pause"),
+    ("tray.resume", "&Resume"),
+    ("tray.status_paused", "paused"),
+    ("tray.status_disabled", "disabled by administrator"),
+    ("tray.admin", "Running as administrator"),
+    ("tray.limited", "Limited: elevated apps won't scroll"),
+    ("tray.restart_admin", "Restart as a&dministrator"),
+    ("tray.about", "&About"),
+    ("tray.exit", "E&xit"),
+    ("instance.running", "NimbusScroll is already running."),
+    (
+        "safe.reset_prompt",
+        "NimbusScroll started in safe mode with default settings. Overwrite the config file \
+         with the defaults as well? Saved presets will be removed.",
+    ),
+];
+
+const RUSSIAN: &[(&str, &str)] = &[
+    ("tray.settings", "&Настройки"),
+    ("tray.calibrate", "&Калибровка"),
+    ("tray.presets", "Пр&есеты"),
+    ("tray.restore_preset", "&Вернуть прежние настройки"),
+    ("tray.save_preset", "&Сохранить текущие как пресет..."),
+    ("tray.device", "&Устройство прокрутки"),
+    ("tray.pause", "&Пауза"),
+    ("tray.resume", "П&родолжить"),
+    ("tray.status_paused", "приостановлено"),
+    ("tray.status_disabled", "отключено администратором"),
+    ("tray.admin", "Запущено от имени администратора"),
+    (
+        "tray.limited",
+        "Ограничено: приложения администратора не прокручиваются",
+    ),
+    (
+        "tray.restart_admin",
+        "Пере&запустить от имени администратора",
+    ),
+    ("tray.about", "&О программе"),
+    ("tray.exit", "&Выход"),
+    ("instance.running", "NimbusScroll уже запущен."),
+    (
+        "safe.reset_prompt",
+        "NimbusScroll запущен в безопасном режиме с настройками по умолчанию. Перезаписать \
+         ими и файл настроек? Сохранённые пресеты будут удалены.",
+    ),
+];
+
+fn lookup(table: &'static [(&'static str, &'static str)], id: &str) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(key, _)| *key == id)
+        .map(|(_, text)| *text)
+}
+
+pub(crate) fn tr(id: &'static str) -> &'static str {
+    let lang = crate::engine::config().lang();
+    lookup(lang.table(), id)
+        .or_else(|| lookup(ENGLISH, id))
+        .unwrap_or(id)
+}
+
+pub(crate) fn apply_ui_language(lang: Lang) {
+    let _ = slint::select_bundled_translation(lang.label());
+}
@@ -0,0 +1,53 @@
+#![allow(non_snake_case, non_camel_case_types, dead_code)]
+
+#[macro_use]
+mod log;
+
+mod app;
+mod cli;
+mod easing;
+mod engine;
+mod ffi;
+mod i18n;
+mod trace;
+mod tray;
+mod watchdog;
+
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "linux")]
+mod wayland;
+
+#[cfg(target_os = "windows")]
+#[path = "tray-windows.rs"]
+mod tray_windows;
+
+#[cfg(target_os = "linux")]
+#[path = "tray-wayland.rs"]
+mod tray_wayland;
+
+/// Entry point of the tray application.
+pub fn main() {
+    match cli::parse() {
+        cli::Command::Run => {
+            let _ = app::run(false);
+        }
+        cli::Command::Trace(path) => {
+            trace::request(path);
+            let _ = app::run(false);
+        }
+        cli::Command::Safe => {
+            cli::attach_console();
+            let _ = app::run(true);
+        }
+        cli::Command::Version => {
+            cli::attach_console();
+            std::process::exit(cli::print_version());
+        }
+        cli::Command::WriteDefaultConfig(path) => {
+            cli::attach_console();
+            std::process::exit(cli::write_default_config(path));
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+static LEVEL: AtomicU64 = AtomicU64::new(LogLevel::Info as u64);
+static FORMAT: AtomicU64 = AtomicU64::new(LogFormat::Text as u64);
+static START: OnceLock<Instant> = OnceLock::new();
+
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum LogLevel {
+    Error = 0,
+    Info = 1,
+    Trace = 2,
+}
+
+impl LogLevel {
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Info => "info",
+            Self::Trace => "trace",
+        }
+    }
+
+    pub(crate) fn from_label(value: &str) -> Option<Self> {
+        match value {
+            "error" => Some(Self::Error),
+            "info" => Some(Self::Info),
+            "trace" => Some(Self::Trace),
+            _ => None,
+        }
+    }
+
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            0 => Self::Error,
+            2 => Self::Trace,
+            _ => Self::Info,
+        }
+    }
+
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
+    }
+}
+
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LogFormat {
+    Text = 0,
+    Json = 1,
+}
+
+impl LogFormat {
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Json => "json",
+        }
+    }
+
+    pub(crate) fn from_label(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(Self::Text),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub(crate) const fn from_u64(value: u64) -> Self {
+        match value {
+            1 => Self::Json,
+            _ => Self::Text,
+        }
+    }
+
+    pub(crate) const fn to_u64(self) -> u64 {
+        self as u64
+    }
+}
+
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)*) => {
+        if $crate::log::enabled($level) {
+            $crate::log::write($level, &format!($($arg)*))
+        }
+    };
+}
+
+macro_rules! log_trace {
+    ($($arg:tt)*) => {
+        log_at!($crate::log::LogLevel::Trace, $($arg)*)
+    };
+}
+
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        log_at!($crate::log::LogLevel::Info, $($arg)*)
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        log_at!($crate::log::LogLevel::Error, $($arg)*)
+    };
+}
+
+pub(crate) fn set_level(level: LogLevel) {
+    LEVEL.store(level.to_u64(), Ordering::Relaxed);
+}
+
+pub(crate) fn set_format(format: LogFormat) {
+    START.get_or_init(Instant::now);
+    FORMAT.store(format.to_u64(), Ordering::Relaxed);
+}
+
+pub(crate) fn enabled(level: LogLevel) -> bool {
+    level.to_u64() <= LEVEL.load(Ordering::Relaxed)
+}
+
+pub(crate) fn write(level: LogLevel, message: &str) {
+    let line = match LogFormat::from_u64(FORMAT.load(Ordering::Relaxed)) {
+        LogFormat::Text => format!("[nimbusscroll] {}: {}", level.label(), message),
+        LogFormat::Json => json_line(level, message),
+    };
+
+    #[cfg(target_os = "windows")]
+    {
+        crate::windows::debug_output(&line);
+    }
+
+    eprintln!("{}", line);
+}
+
+/// One JSON object per line; `t` counts seconds from config load on a monotonic clock.
+fn json_line(level: LogLevel, message: &str) -> String {
+    let start = *START.get_or_init(Instant::now);
+    let mut msg = String::with_capacity(message.len());
+    for c in message.chars() {
+        match c {
+            '"' => msg.push_str("\\\""),
+            '\\' => msg.push_str("\\\\"),
+            '\n' => msg.push_str("\\n"),
+            '\r' => msg.push_str("\\r"),
+            '\t' => msg.push_str("\\t"),
+            c if c.is_control() => msg.push_str(&format!("\\u{:04x}", c as u32)),
+            c => msg.push(c),
+        }
+    }
+    format!(
+        "{{\"t\":{:.6},\"level\":\"{}\",\"msg\":\"{}\"}}",
+        start.elapsed().as_secs_f64(),
+        level.label(),
+        msg
+    )
+}
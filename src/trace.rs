@@ -0,0 +1,151 @@
+//! CSV recording of the scroll loop for `--trace <file>`.
+//!
+//! Each tick that moved anything becomes one row of hi-res wheel units, so a session can be
+//! replayed in a spreadsheet when tuning the physics.
+
+use crate::engine::{InputEvent, OutputEvent, ScrollAxis, ScrollConfig};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const COLUMNS: &str = "t_ms,raw_h,raw_v,velocity_h,velocity_v,emitted_h,emitted_v";
+
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Asks the scroll loop to record into `path` once it starts.
+pub(crate) fn request(path: PathBuf) {
+    let _ = PATH.set(path);
+}
+
+#[derive(Default)]
+struct WheelSum {
+    hires: (f64, f64),
+    detents: (f64, f64),
+}
+
+impl WheelSum {
+    fn add(&mut self, axis: ScrollAxis, value: i32) {
+        let value = value as f64;
+        match axis {
+            ScrollAxis::HWheelHiRes => self.hires.0 += value,
+            ScrollAxis::WheelHiRes => self.hires.1 += value,
+            ScrollAxis::HWheel => self.detents.0 += value * 120.0,
+            ScrollAxis::Wheel => self.detents.1 += value * 120.0,
+            _ => {}
+        }
+    }
+
+    /// (horizontal, vertical) travel; detent events only count where no hi-res event came, as
+    /// devices and the controller send both for the same movement.
+    fn take(&mut self) -> (f64, f64) {
+        let pick = |hires: f64, detents: f64| if hires != 0.0 { hires } else { detents };
+        let sum = (
+            pick(self.hires.0, self.detents.0),
+            pick(self.hires.1, self.detents.1),
+        );
+        *self = Self::default();
+        sum
+    }
+}
+
+pub(crate) struct Trace {
+    out: BufWriter<File>,
+    start: Instant,
+    last_flush: Instant,
+    raw: WheelSum,
+    emitted: WheelSum,
+}
+
+impl Trace {
+    /// Opens the requested trace file, if any, and writes the header with the config in effect.
+    pub(crate) fn open(cfg: &ScrollConfig) -> Option<Self> {
+        let path = PATH.get()?;
+        match Self::create(path, cfg) {
+            Ok(trace) => {
+                log_info!("tracing scroll events to {}", path.display());
+                Some(trace)
+            }
+            Err(err) => {
+                log_error!("failed to open trace file {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    fn create(path: &Path, cfg: &ScrollConfig) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        writeln!(
+            out,
+            "# NimbusScroll {} trace, started at unix time {}",
+            env!("CARGO_PKG_VERSION"),
+            started
+        )?;
+        writeln!(out, "# units are hi-res wheel steps (120 per notch)")?;
+        for line in cfg.config_text().lines() {
+            writeln!(out, "# {}", line)?;
+        }
+        writeln!(out, "{}", COLUMNS)?;
+        out.flush()?;
+
+        let now = Instant::now();
+        Ok(Self {
+            out,
+            start: now,
+            last_flush: now,
+            raw: WheelSum::default(),
+            emitted: WheelSum::default(),
+        })
+    }
+
+    pub(crate) fn input(&mut self, event: InputEvent) {
+        if let InputEvent::Rel { axis, value } = event {
+            self.raw.add(axis, value);
+        }
+    }
+
+    pub(crate) fn output(&mut self, events: &[OutputEvent]) {
+        for event in events {
+            if let OutputEvent::Rel { axis, value } = *event {
+                self.emitted.add(axis, value);
+            }
+        }
+    }
+
+    /// Writes the tick's row, skipping ticks where nothing moved.
+    pub(crate) fn end_tick(&mut self, velocity: (f64, f64)) -> io::Result<()> {
+        let raw = self.raw.take();
+        let emitted = self.emitted.take();
+        let now = Instant::now();
+
+        let moved = raw != (0.0, 0.0) || velocity != (0.0, 0.0) || emitted != (0.0, 0.0);
+        if moved {
+            writeln!(
+                self.out,
+                "{:.3},{},{},{:.3},{:.3},{},{}",
+                now.duration_since(self.start).as_secs_f64() * 1000.0,
+                raw.0,
+                raw.1,
+                velocity.0,
+                velocity.1,
+                emitted.0,
+                emitted.1
+            )?;
+        }
+
+        // Flush at least once a second, and as soon as a burst settles so an idle loop never
+        // sits on unwritten rows.
+        let settled = !moved && !self.out.buffer().is_empty();
+        if settled || now.duration_since(self.last_flush) >= FLUSH_INTERVAL {
+            self.last_flush = now;
+            self.out.flush()?;
+        }
+        Ok(())
+    }
+}
@@ -1,11 +1,22 @@
+use crate::engine::MouseDeviceInfo;
+use crate::i18n::tr;
 use crate::tray::UiHandles;
-use ksni::menu::StandardItem;
+use ksni::menu::{CheckmarkItem, StandardItem, SubMenu};
 use ksni::TrayMethods;
 use std::error::Error;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+static TRAY: OnceLock<(tokio::runtime::Handle, ksni::Handle<NimbusTray>)> = OnceLock::new();
 
 #[derive(Clone)]
 struct NimbusTray {
     ui: UiHandles,
+    paused: bool,
+    pulsing: bool,
+    // Enumerated on refresh rather than per menu build, which runs on every pulse.
+    devices: Vec<(MouseDeviceInfo, bool)>,
 }
 
 impl ksni::Tray for NimbusTray {
@@ -14,20 +25,130 @@ impl ksni::Tray for NimbusTray {
     }
 
     fn icon_name(&self) -> String {
-        "input-mouse".into()
+        // Hosts prefer the themed name, so clear it while the pulse pixmap is up.
+        if self.pulsing {
+            return String::new();
+        }
+        custom_icon()
+            .map(|(_, name)| name)
+            .unwrap_or_else(|| "input-mouse".into())
+    }
+
+    fn icon_pixmap(&self) -> Vec<ksni::Icon> {
+        if !self.pulsing {
+            return Vec::new();
+        }
+
+        // SNI wants ARGB in network byte order.
+        let data = crate::tray::pulse_icon_rgba()
+            .chunks_exact(4)
+            .flat_map(|px| [px[3], px[0], px[1], px[2]])
+            .collect();
+        vec![ksni::Icon {
+            width: crate::tray::PULSE_ICON_SIZE as i32,
+            height: crate::tray::PULSE_ICON_SIZE as i32,
+            data,
+        }]
+    }
+
+    // SNI has no double click, so only tray_single applies here.
+    fn activate(&mut self, _x: i32, _y: i32) {
+        self.ui.run_tray_action(self.ui.config.tray_single());
+    }
+
+    fn icon_theme_path(&self) -> String {
+        custom_icon().map(|(dir, _)| dir).unwrap_or_default()
     }
 
     fn title(&self) -> String {
         "NimbusScroll".into()
     }
 
+    fn tool_tip(&self) -> ksni::ToolTip {
+        ksni::ToolTip {
+            title: crate::tray::tooltip(self.paused),
+            ..Default::default()
+        }
+    }
+
     fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
         let settings = self.ui.clone();
+        let calibrate = self.ui.clone();
         let about = self.ui.clone();
+        let save_preset = self.ui.clone();
+        let restore_preset = self.ui.clone();
+
+        let mut presets: Vec<ksni::MenuItem<Self>> = self
+            .ui
+            .config
+            .preset_names()
+            .into_iter()
+            .map(|name| {
+                let ui = self.ui.clone();
+                StandardItem {
+                    label: name.clone(),
+                    activate: Box::new(move |_| {
+                        ui.apply_preset(&name);
+                    }),
+                    ..Default::default()
+                }
+                .into()
+            })
+            .collect();
+        if !presets.is_empty() {
+            presets.push(ksni::MenuItem::Separator);
+        }
+        presets.push(
+            StandardItem {
+                label: menu_label(tr("tray.restore_preset")),
+                icon_name: "edit-undo".into(),
+                enabled: self.ui.config.can_restore_pre_preset(),
+                activate: Box::new(move |_| {
+                    restore_preset.restore_pre_preset();
+                }),
+                ..Default::default()
+            }
+            .into(),
+        );
+        presets.push(
+            StandardItem {
+                label: menu_label(tr("tray.save_preset")),
+                icon_name: "document-save".into(),
+                activate: Box::new(move |_| {
+                    save_preset.show_save_preset();
+                }),
+                ..Default::default()
+            }
+            .into(),
+        );
+
+        let devices: Vec<ksni::MenuItem<Self>> = self
+            .devices
+            .iter()
+            .map(|(device, checked)| {
+                let ui = self.ui.clone();
+                let device = device.clone();
+                CheckmarkItem {
+                    label: device.label.replace('_', "__"),
+                    checked: *checked,
+                    activate: Box::new(move |_| {
+                        ui.select_mouse_device(&device);
+                    }),
+                    ..Default::default()
+                }
+                .into()
+            })
+            .collect();
+        let devices_menu = SubMenu {
+            label: menu_label(tr("tray.device")),
+            visible: !devices.is_empty(),
+            submenu: devices,
+            ..Default::default()
+        };
 
         vec![
             StandardItem {
-                label: "Settings".into(),
+                label: menu_label(tr("tray.settings")),
                 icon_name: "preferences-system".into(),
                 activate: Box::new(move |_| {
                     settings.show_settings();
@@ -36,7 +157,32 @@ impl ksni::Tray for NimbusTray {
             }
             .into(),
             StandardItem {
-                label: "About".into(),
+                label: menu_label(tr("tray.calibrate")),
+                icon_name: "input-mouse".into(),
+                activate: Box::new(move |_| {
+                    calibrate.show_calibrate();
+                }),
+                ..Default::default()
+            }
+            .into(),
+            SubMenu {
+                label: menu_label(tr("tray.presets")),
+                submenu: presets,
+                ..Default::default()
+            }
+            .into(),
+            devices_menu.into(),
+            StandardItem {
+                label: menu_label(crate::tray::pause_label()),
+                icon_name: "media-playback-pause".into(),
+                activate: Box::new(|_| {
+                    crate::engine::toggle_pause();
+                }),
+                ..Default::default()
+            }
+            .into(),
+            StandardItem {
+                label: menu_label(tr("tray.about")),
                 icon_name: "help-about".into(),
                 activate: Box::new(move |_| {
                     about.show_about();
@@ -45,7 +191,7 @@ impl ksni::Tray for NimbusTray {
             }
             .into(),
             StandardItem {
-                label: "Exit".into(),
+                label: menu_label(tr("tray.exit")),
                 icon_name: "application-exit".into(),
                 activate: Box::new(move |_| {
                     crate::engine::request_exit();
@@ -58,7 +204,42 @@ impl ksni::Tray for NimbusTray {
     }
 }
 
+/// DBusMenu marks access keys with `_` where the shared labels use `&`.
+fn menu_label(text: &str) -> String {
+    text.replace('_', "__").replace('&', "_")
+}
+
+/// Splits the configured icon into the theme directory and icon name SNI hosts expect.
+fn custom_icon() -> Option<(String, String)> {
+    let path = crate::engine::config().tray_icon_path()?;
+    let path = Path::new(&path);
+    if !path.is_file() {
+        return None;
+    }
+
+    let dir = path.parent()?.to_str()?.to_string();
+    let name = path.file_stem()?.to_str()?.to_string();
+    Some((dir, name))
+}
+
+pub(crate) fn refresh() {
+    if let Some(path) = crate::engine::config().tray_icon_path() {
+        if custom_icon().is_none() {
+            log_error!("tray icon {} not found, using the default", path);
+        }
+    }
+
+    if let Some((rt, handle)) = TRAY.get() {
+        let handle = handle.clone();
+        let devices = crate::tray::mouse_device_choices();
+        rt.spawn(async move {
+            let _ = handle.update(move |tray| tray.devices = devices).await;
+        });
+    }
+}
+
 pub(crate) fn start(ui: UiHandles) -> Result<(), Box<dyn Error>> {
+    let status_ui = ui.clone();
     std::thread::Builder::new()
         .name("tray-wayland".into())
         .spawn(move || {
@@ -71,16 +252,49 @@ pub(crate) fn start(ui: UiHandles) -> Result<(), Box<dyn Error>> {
             };
 
             rt.block_on(async move {
-                let tray = NimbusTray { ui };
+                let tray = NimbusTray {
+                    ui,
+                    paused: crate::engine::is_pause_requested(),
+                    pulsing: false,
+                    devices: crate::tray::mouse_device_choices(),
+                };
                 let handle = match tray.assume_sni_available(true).spawn().await {
                     Ok(handle) => handle,
                     Err(_) => return,
                 };
 
-                let _keep_alive = handle;
+                let _ = TRAY.set((tokio::runtime::Handle::current(), handle));
                 std::future::pending::<()>().await;
             });
         })?;
 
+    std::thread::Builder::new()
+        .name("tray-status".into())
+        .spawn(move || {
+            let mut status = crate::tray::StatusDebounce::new();
+            let mut pulse = crate::tray::PulseFlash::new();
+            loop {
+                std::thread::sleep(crate::tray::poll_interval());
+                if crate::tray::take_settings_request() {
+                    status_ui.show_settings();
+                }
+                let Some((rt, handle)) = TRAY.get() else {
+                    continue;
+                };
+                if let Some(paused) = status.poll(Instant::now()) {
+                    let handle = handle.clone();
+                    rt.spawn(async move {
+                        let _ = handle.update(move |tray| tray.paused = paused).await;
+                    });
+                }
+                if let Some(pulsing) = pulse.poll(Instant::now()) {
+                    let handle = handle.clone();
+                    rt.spawn(async move {
+                        let _ = handle.update(move |tray| tray.pulsing = pulsing).await;
+                    });
+                }
+            }
+        })?;
+
     Ok(())
 }
@@ -1,33 +1,278 @@
+use crate::engine::MouseDeviceInfo;
+use crate::i18n::tr;
 use crate::tray::UiHandles;
 use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tray_icon::menu::{
+    CheckMenuItem, Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem, Submenu,
+};
+use tray_icon::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+
+static TRAY_DIRTY: AtomicBool = AtomicBool::new(false);
+static REMOVE_REQUESTED: AtomicBool = AtomicBool::new(false);
+static REMOVED: AtomicBool = AtomicBool::new(false);
+
+// Longest remove() waits for the tray thread, so a stuck thread cannot hold up logoff.
+const REMOVE_TIMEOUT: Duration = Duration::from_millis(500);
+
+// At logon the shell may not take icons yet; NIM_ADD is retried with a doubling delay.
+const ADD_ATTEMPTS: u32 = 10;
+const ADD_RETRY_FIRST: Duration = Duration::from_millis(250);
+const ADD_RETRY_MAX: Duration = Duration::from_secs(8);
+const TASKBAR_POLL: Duration = Duration::from_millis(100);
+
+pub(crate) fn refresh() {
+    TRAY_DIRTY.store(true, Ordering::Relaxed);
+}
+
+/// Asks the tray thread to delete the icon and waits briefly for it, for when the process is
+/// about to be terminated.
+pub(crate) fn remove() {
+    REMOVE_REQUESTED.store(true, Ordering::Relaxed);
+    let deadline = Instant::now() + REMOVE_TIMEOUT;
+    while !REMOVED.load(Ordering::Relaxed) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn rebuild_presets(
+    submenu: &Submenu,
+    restore_item: &MenuItem,
+    save_item: &MenuItem,
+) -> Vec<(MenuId, String)> {
+    while submenu.remove_at(0).is_some() {}
+
+    let cfg = crate::engine::config();
+    let names = cfg.preset_names();
+    let mut ids = Vec::with_capacity(names.len());
+    for name in names {
+        let item = MenuItem::new(&name, true, None);
+        ids.push((item.id().clone(), name));
+        let _ = submenu.append(&item);
+    }
+    if !ids.is_empty() {
+        let _ = submenu.append(&PredefinedMenuItem::separator());
+    }
+    restore_item.set_enabled(cfg.can_restore_pre_preset());
+    let _ = submenu.append_items(&[restore_item, save_item]);
+    ids
+}
+
+fn rebuild_devices(submenu: &Submenu) -> Vec<(MenuId, MouseDeviceInfo)> {
+    while submenu.remove_at(0).is_some() {}
+
+    let choices = crate::tray::mouse_device_choices();
+    let mut ids = Vec::with_capacity(choices.len());
+    for (device, checked) in choices {
+        // `&` would otherwise mark an access key in the device name.
+        let item = CheckMenuItem::new(device.label.replace('&', "&&"), true, checked, None);
+        ids.push((item.id().clone(), device));
+        let _ = submenu.append(&item);
+    }
+    ids
+}
+
+fn load_icon() -> Option<tray_icon::Icon> {
+    let path = crate::engine::config().tray_icon_path()?;
+    match tray_icon::Icon::from_path(&path, None) {
+        Ok(icon) => Some(icon),
+        Err(err) => {
+            log_error!("failed to load tray icon {}: {}", path, err);
+            None
+        }
+    }
+}
+
+fn stop_requested() -> bool {
+    REMOVE_REQUESTED.load(Ordering::Relaxed) || crate::engine::should_exit()
+}
+
+/// Waits up to `startup_delay_ms` for the taskbar to appear, for launches at logon that can
+/// beat the shell.
+fn wait_for_taskbar() {
+    let delay = Duration::from_millis(crate::engine::config().startup_delay_ms());
+    let deadline = Instant::now() + delay;
+    while !crate::windows::taskbar_ready() && Instant::now() < deadline && !stop_requested() {
+        std::thread::sleep(TASKBAR_POLL);
+    }
+}
+
+/// tray-icon re-adds the icon itself when Explorer restarts and broadcasts TaskbarCreated.
+fn add_tray(menu: &Menu, tooltip: String, icon: Option<tray_icon::Icon>) -> Option<TrayIcon> {
+    crate::windows::allow_taskbar_created();
+    wait_for_taskbar();
+
+    let mut retry = ADD_RETRY_FIRST;
+    for attempt in 1..=ADD_ATTEMPTS {
+        let mut builder = TrayIconBuilder::new()
+            .with_tooltip(&tooltip)
+            .with_menu(Box::new(menu.clone()));
+        if let Some(icon) = icon.clone() {
+            builder = builder.with_icon(icon);
+        }
+        match builder.build() {
+            Ok(tray) => return Some(tray),
+            Err(err) => {
+                log_error!(
+                    "failed to add the tray icon (attempt {}/{}): {}",
+                    attempt,
+                    ADD_ATTEMPTS,
+                    err
+                );
+            }
+        }
+        if attempt == ADD_ATTEMPTS || stop_requested() {
+            break;
+        }
+        std::thread::sleep(retry);
+        retry = (retry * 2).min(ADD_RETRY_MAX);
+    }
+    None
+}
 
 pub(crate) fn start(ui: UiHandles) -> Result<(), Box<dyn Error>> {
     std::thread::Builder::new()
         .name("tray-windows".into())
         .spawn(move || {
-            use tray_icon::menu::{Menu, MenuEvent, MenuItem};
-            use tray_icon::TrayIconBuilder;
-
             let menu = Menu::new();
-            let settings_item = MenuItem::new("Settings", true, None);
-            let about_item = MenuItem::new("About", true, None);
-            let exit_item = MenuItem::new("Exit", true, None);
-
-            let _ = menu.append_items(&[&settings_item, &about_item, &exit_item]);
+            let settings_item = MenuItem::new(tr("tray.settings"), true, None);
+            let calibrate_item = MenuItem::new(tr("tray.calibrate"), true, None);
+            let presets_menu = Submenu::new(tr("tray.presets"), true);
+            let restore_preset_item = MenuItem::new(tr("tray.restore_preset"), false, None);
+            let save_preset_item = MenuItem::new(tr("tray.save_preset"), true, None);
+            let mut preset_ids =
+                rebuild_presets(&presets_menu, &restore_preset_item, &save_preset_item);
+            let devices_menu = Submenu::new(tr("tray.device"), true);
+            let mut device_ids = rebuild_devices(&devices_menu);
+            let pause_item = MenuItem::new(crate::tray::pause_label(), true, None);
+            let elevated = crate::windows::is_elevated();
+            let status_label = if elevated {
+                tr("tray.admin")
+            } else {
+                tr("tray.limited")
+            };
+            let status_item = MenuItem::new(status_label, false, None);
+            let restart_admin_item = MenuItem::new(tr("tray.restart_admin"), true, None);
+            let about_item = MenuItem::new(tr("tray.about"), true, None);
+            let exit_item = MenuItem::new(tr("tray.exit"), true, None);
 
-            let tray = TrayIconBuilder::new()
-                .with_tooltip("NimbusScroll")
-                .with_menu(Box::new(menu))
-                .build();
+            let _ = menu.append_items(&[&settings_item, &calibrate_item, &presets_menu]);
+            // The backend may not enumerate mice at all; then there is nothing to pick from.
+            if !device_ids.is_empty() {
+                let _ = menu.append(&devices_menu);
+            }
+            let _ = menu.append(&pause_item);
+            let _ = menu.append(&PredefinedMenuItem::separator());
+            let _ = menu.append(&status_item);
+            if !elevated {
+                let _ = menu.append(&restart_admin_item);
+            }
+            let _ = menu.append(&PredefinedMenuItem::separator());
+            let _ = menu.append_items(&[&about_item, &exit_item]);
 
-            let Ok(_tray) = tray else {
+            let mut status = crate::tray::StatusDebounce::new();
+            let mut pulse = crate::tray::PulseFlash::new();
+            let pulse_icon = tray_icon::Icon::from_rgba(
+                crate::tray::pulse_icon_rgba(),
+                crate::tray::PULSE_ICON_SIZE,
+                crate::tray::PULSE_ICON_SIZE,
+            )
+            .ok();
+            let mut icon = load_icon();
+            let Some(tray) = add_tray(&menu, crate::tray::tooltip(status.shown()), icon.clone())
+            else {
+                // Nothing left to remove; do not keep remove() waiting on this thread.
+                REMOVED.store(true, Ordering::Relaxed);
                 return;
             };
 
             let receiver = MenuEvent::receiver();
-            while let Ok(event) = receiver.recv() {
+            let icon_events = TrayIconEvent::receiver();
+            let double_time = crate::windows::double_click_time();
+            let mut clicks = crate::tray::TrayClicks::new();
+            loop {
+                if REMOVE_REQUESTED.load(Ordering::Relaxed) {
+                    // Dropping the tray icon sends NIM_DELETE.
+                    drop(tray);
+                    REMOVED.store(true, Ordering::Relaxed);
+                    break;
+                }
+                if TRAY_DIRTY.swap(false, Ordering::Relaxed) {
+                    icon = load_icon();
+                    let _ = tray.set_icon(icon.clone());
+                    preset_ids =
+                        rebuild_presets(&presets_menu, &restore_preset_item, &save_preset_item);
+                    device_ids = rebuild_devices(&devices_menu);
+                }
+                if crate::tray::take_settings_request() {
+                    ui.show_settings();
+                }
+                if let Some(paused) = status.poll(Instant::now()) {
+                    let _ = tray.set_tooltip(Some(crate::tray::tooltip(paused)));
+                    pause_item.set_text(crate::tray::pause_label());
+                }
+                // Each swap is a single NIM_MODIFY; the revert rides on the next poll.
+                match pulse.poll(Instant::now()) {
+                    Some(true) if pulse_icon.is_some() => {
+                        let _ = tray.set_icon(pulse_icon.clone());
+                    }
+                    Some(false) => {
+                        let _ = tray.set_icon(icon.clone());
+                    }
+                    _ => {}
+                }
+
+                // tray-icon opens the menu on right click itself; left clicks are ours.
+                while let Ok(event) = icon_events.try_recv() {
+                    if let TrayIconEvent::Click {
+                        button: MouseButton::Left,
+                        button_state: MouseButtonState::Up,
+                        ..
+                    } = event
+                    {
+                        if let Some(action) = clicks.click(Instant::now(), double_time) {
+                            ui.run_tray_action(action);
+                        }
+                    }
+                }
+                if let Some(action) = clicks.poll(Instant::now(), double_time) {
+                    ui.run_tray_action(action);
+                }
+
+                let timeout = if clicks.is_pending() {
+                    crate::tray::PULSE_DURATION / 4
+                } else {
+                    crate::tray::poll_interval()
+                };
+                let event = match receiver.recv_timeout(timeout) {
+                    Ok(event) => event,
+                    Err(err) if err.is_timeout() => continue,
+                    Err(_) => break,
+                };
+
                 if event.id == settings_item.id() {
                     ui.show_settings();
+                } else if event.id == calibrate_item.id() {
+                    ui.show_calibrate();
+                } else if event.id == restore_preset_item.id() {
+                    ui.restore_pre_preset();
+                } else if event.id == save_preset_item.id() {
+                    ui.show_save_preset();
+                } else if let Some((_, name)) = preset_ids.iter().find(|(id, _)| *id == event.id) {
+                    ui.apply_preset(name);
+                } else if let Some((_, device)) = device_ids.iter().find(|(id, _)| *id == event.id)
+                {
+                    ui.select_mouse_device(device);
+                } else if event.id == pause_item.id() {
+                    crate::engine::toggle_pause();
+                    pause_item.set_text(crate::tray::pause_label());
+                } else if event.id == restart_admin_item.id() {
+                    if crate::windows::restart_elevated() {
+                        crate::engine::request_exit();
+                        let _ = slint::quit_event_loop();
+                        break;
+                    }
                 } else if event.id == about_item.id() {
                     ui.show_about();
                 } else if event.id == exit_item.id() {
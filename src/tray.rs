@@ -1,14 +1,22 @@
-use crate::engine::ScrollConfig;
-use slint::ComponentHandle;
+use crate::engine::{MouseDeviceInfo, ScrollConfig, ScrollController, TrayAction};
+use crate::i18n::tr;
+use slint::{ComponentHandle, SharedString};
 use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 slint::include_modules!();
 
+static SETTINGS_REQUESTED: AtomicBool = AtomicBool::new(false);
+static PULSE_REQUESTED: AtomicBool = AtomicBool::new(false);
+
 #[derive(Clone)]
 pub(crate) struct UiHandles {
     pub(crate) settings: slint::Weak<SettingsWindow>,
     pub(crate) about: slint::Weak<AboutWindow>,
+    pub(crate) calibrate: slint::Weak<CalibrateWindow>,
+    pub(crate) preset: slint::Weak<PresetWindow>,
     pub(crate) config: Arc<ScrollConfig>,
 }
 
@@ -16,11 +24,87 @@ impl UiHandles {
     pub fn show_settings(&self) {
         let cfg = self.config.clone();
         let _ = self.settings.upgrade_in_event_loop(move |win| {
+            if cfg.preview_settings() {
+                cfg.begin_preview();
+            }
             sync_settings(&win, &cfg);
             let _ = win.show();
         });
     }
 
+    pub fn show_calibrate(&self) {
+        let cfg = self.config.clone();
+        let _ = self.calibrate.upgrade_in_event_loop(move |win| {
+            cfg.begin_preview();
+            win.set_normal_gain(cfg.normal_wheel_gain() as f32);
+            win.set_normal_damping(cfg.normal_wheel_damping() as f32);
+            let _ = win.show();
+        });
+    }
+
+    pub fn show_save_preset(&self) {
+        let _ = self.preset.upgrade_in_event_loop(move |win| {
+            win.set_preset_name("".into());
+            let _ = win.show();
+        });
+    }
+
+    pub fn apply_preset(&self, name: &str) {
+        if !self.config.apply_preset(name) {
+            return;
+        }
+
+        log_info!("applied preset {}", name);
+        let cfg = self.config.clone();
+        let _ = self.settings.upgrade_in_event_loop(move |win| {
+            sync_settings(&win, &cfg);
+        });
+        refresh();
+    }
+
+    pub fn restore_pre_preset(&self) {
+        if !self.config.restore_pre_preset() {
+            return;
+        }
+
+        log_info!("restored the settings from before the preset");
+        let cfg = self.config.clone();
+        let _ = self.settings.upgrade_in_event_loop(move |win| {
+            sync_settings(&win, &cfg);
+        });
+        refresh();
+    }
+
+    /// Switches scrolling to `device`; the backend loop reopens it on its next tick.
+    pub fn select_mouse_device(&self, device: &MouseDeviceInfo) {
+        self.config.set_mouse_device_path(Some(device.path.clone()));
+        log_info!("selected mouse device {}", device.label);
+
+        #[cfg(target_os = "linux")]
+        {
+            let label = SharedString::from(device.label.as_str());
+            let _ = self.settings.upgrade_in_event_loop(move |win| {
+                win.set_selected_mouse(label);
+            });
+        }
+        refresh();
+    }
+
+    /// Runs a configured tray click action.
+    pub fn run_tray_action(&self, action: TrayAction) {
+        match action {
+            TrayAction::None => {}
+            TrayAction::Show => self.show_settings(),
+            TrayAction::Toggle => {
+                crate::engine::toggle_pause();
+            }
+            TrayAction::Quit => {
+                crate::engine::request_exit();
+                let _ = slint::quit_event_loop();
+            }
+        }
+    }
+
     pub fn show_about(&self) {
         let _ = self.about.upgrade_in_event_loop(move |win| {
             let _ = win.show();
@@ -28,17 +112,246 @@ impl UiHandles {
     }
 }
 
+pub(crate) fn pause_label() -> &'static str {
+    if crate::engine::is_pause_requested() {
+        tr("tray.resume")
+    } else {
+        tr("tray.pause")
+    }
+}
+
+/// Asks the tray thread to open the settings window, e.g. for a second launch.
+pub(crate) fn request_settings() {
+    SETTINGS_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+pub(crate) fn take_settings_request() -> bool {
+    SETTINGS_REQUESTED.swap(false, Ordering::Relaxed)
+}
+
+/// How often the tray threads check whether the pause state has settled.
+pub(crate) const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long the tray icon shows the pulse frame after a notch.
+pub(crate) const PULSE_DURATION: Duration = Duration::from_millis(80);
+
+/// Side length of the generated pulse frame.
+pub(crate) const PULSE_ICON_SIZE: u32 = 16;
+
+/// Asks the tray thread to flash the icon. Called from the engine loop, so it only sets a flag.
+pub(crate) fn pulse() {
+    PULSE_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+/// Tray thread poll period; shortened while `pulse` is on so flashes keep up with the wheel.
+pub(crate) fn poll_interval() -> Duration {
+    if crate::engine::config().pulse() {
+        PULSE_DURATION / 4
+    } else {
+        STATUS_POLL_INTERVAL
+    }
+}
+
+/// The pulse frame: a filled white dot as RGBA rows.
+pub(crate) fn pulse_icon_rgba() -> Vec<u8> {
+    let size = PULSE_ICON_SIZE as f64;
+    let center = size / 2.0;
+    let radius = size / 2.0 - 1.0;
+    let mut rgba = Vec::with_capacity((PULSE_ICON_SIZE * PULSE_ICON_SIZE * 4) as usize);
+    for y in 0..PULSE_ICON_SIZE {
+        for x in 0..PULSE_ICON_SIZE {
+            let dx = x as f64 + 0.5 - center;
+            let dy = y as f64 + 0.5 - center;
+            let alpha = (radius + 0.5 - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0);
+            rgba.extend_from_slice(&[255, 255, 255, (alpha * 255.0) as u8]);
+        }
+    }
+    rgba
+}
+
+/// Tracks the pulse frame so each notch swaps the icon once and a timer swaps it back.
+pub(crate) struct PulseFlash {
+    until: Option<Instant>,
+}
+
+impl PulseFlash {
+    pub(crate) fn new() -> Self {
+        Self { until: None }
+    }
+
+    /// Returns `Some(true)` to show the pulse frame and `Some(false)` to restore the icon.
+    pub(crate) fn poll(&mut self, now: Instant) -> Option<bool> {
+        if PULSE_REQUESTED.swap(false, Ordering::Relaxed) {
+            let showing = self.until.is_some();
+            self.until = Some(now + PULSE_DURATION);
+            return (!showing).then_some(true);
+        }
+
+        match self.until {
+            Some(until) if now >= until => {
+                self.until = None;
+                Some(false)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Tells a single tray click from a double one. The single action waits out the double-click
+/// time, but only while a double-click action is configured.
+pub(crate) struct TrayClicks {
+    pending: Option<Instant>,
+}
+
+impl TrayClicks {
+    pub(crate) fn new() -> Self {
+        Self { pending: None }
+    }
+
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Records a click and returns the action that is due now, if any.
+    pub(crate) fn click(&mut self, now: Instant, double_time: Duration) -> Option<TrayAction> {
+        let cfg = crate::engine::config();
+        if cfg.tray_double() == TrayAction::None {
+            return Some(cfg.tray_single());
+        }
+        match self.pending.take() {
+            Some(first) if now.duration_since(first) <= double_time => Some(cfg.tray_double()),
+            _ => {
+                self.pending = Some(now);
+                None
+            }
+        }
+    }
+
+    /// Returns the single-click action once no second click came in time.
+    pub(crate) fn poll(&mut self, now: Instant, double_time: Duration) -> Option<TrayAction> {
+        match self.pending {
+            Some(first) if now.duration_since(first) > double_time => {
+                self.pending = None;
+                Some(crate::engine::config().tray_single())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Tray tooltip for the given pause state, built from scratch each time.
+pub(crate) fn tooltip(paused: bool) -> String {
+    if paused && crate::engine::is_disabled() {
+        format!("NimbusScroll - {}", tr("tray.status_disabled"))
+    } else if paused {
+        format!("NimbusScroll - {}", tr("tray.status_paused"))
+    } else {
+        "NimbusScroll".into()
+    }
+}
+
+/// Paused by the user or switched off by the `disable_file` sentinel.
+fn is_inactive() -> bool {
+    crate::engine::is_pause_requested() || crate::engine::is_disabled()
+}
+
+/// Holds back pause state changes until they have lasted `tray_status_delay_ms`, so rapid
+/// toggles don't make the tray flicker.
+pub(crate) struct StatusDebounce {
+    shown: bool,
+    changed_at: Option<Instant>,
+}
+
+impl StatusDebounce {
+    pub(crate) fn new() -> Self {
+        Self {
+            shown: is_inactive(),
+            changed_at: None,
+        }
+    }
+
+    pub(crate) fn shown(&self) -> bool {
+        self.shown
+    }
+
+    /// Returns the pause state to display once it has settled.
+    pub(crate) fn poll(&mut self, now: Instant) -> Option<bool> {
+        let paused = is_inactive();
+        if paused == self.shown {
+            self.changed_at = None;
+            return None;
+        }
+
+        let since = *self.changed_at.get_or_insert(now);
+        let delay = Duration::from_millis(crate::engine::config().tray_status_delay_ms());
+        if now.saturating_duration_since(since) < delay {
+            return None;
+        }
+
+        self.shown = paused;
+        self.changed_at = None;
+        Some(paused)
+    }
+}
+
 pub(crate) fn sync_settings(win: &SettingsWindow, cfg: &ScrollConfig) {
     win.set_normal_gain(cfg.normal_wheel_gain() as f32);
+    win.set_normal_gain_h(cfg.normal_wheel_gain_h() as f32);
     win.set_normal_damping(cfg.normal_wheel_damping() as f32);
     win.set_drag_gain(cfg.drag_wheel_gain() as f32);
+    win.set_drag_gain_h(cfg.drag_wheel_gain_h() as f32);
     win.set_drag_damping(cfg.drag_wheel_damping() as f32);
     win.set_drag_deadzone_px(cfg.drag_deadzone_px() as f32);
     win.set_tap_max_duration_ms(cfg.tap_max_duration_ms() as f32);
     win.set_loop_sleep_ms(cfg.loop_sleep_ms() as f32);
     win.set_max_velocity_hires(cfg.max_velocity_hires() as f32);
+    win.set_selected_easing(cfg.easing_kind().label().into());
+    win.set_selected_output_curve(cfg.output_curve().label().into());
+    win.set_selected_decay_model(cfg.decay_model().label().into());
     win.set_smooth_enabled(cfg.smooth_enabled());
+    win.set_smooth_horizontal(cfg.smooth_horizontal());
+    win.set_smooth_vertical(cfg.smooth_vertical());
     win.set_middle_scroll_enabled(cfg.middle_scroll_enabled());
+    win.set_auto_disable_fullscreen(cfg.auto_disable_fullscreen());
+    win.set_preview_changes(cfg.preview_settings());
+    win.set_tray_icon_path(cfg.tray_icon_path().unwrap_or_default().into());
+    win.set_decay_curve(decay_curve(cfg));
+}
+
+/// Plots the first second of a single notch's momentum as path commands for the settings preview.
+pub(crate) fn decay_curve(cfg: &ScrollConfig) -> SharedString {
+    let samples = ScrollController::decay_preview(cfg, Duration::from_secs(1));
+    let last = samples.len().saturating_sub(1).max(1) as f64;
+
+    let mut commands = String::new();
+    for (i, v) in samples.iter().enumerate() {
+        let x = i as f64 * 100.0 / last;
+        let y = 100.0 - v.clamp(0.0, 1.0) * 100.0;
+        let op = if i == 0 { 'M' } else { 'L' };
+        commands.push_str(&format!("{} {:.2} {:.2} ", op, x, y));
+    }
+    commands.trim_end().into()
+}
+
+/// Mice for the tray's device submenu, paired with whether each is the one being read.
+pub(crate) fn mouse_device_choices() -> Vec<(MouseDeviceInfo, bool)> {
+    let current = crate::engine::config().mouse_device_path();
+    crate::app::list_mouse_devices()
+        .into_iter()
+        .map(|device| {
+            let checked = current.as_deref() == Some(device.path.as_str());
+            (device, checked)
+        })
+        .collect()
+}
+
+/// Rebuilds the tray icon and menu after the icon path, preset list or device changes.
+pub(crate) fn refresh() {
+    #[cfg(target_os = "linux")]
+    crate::tray_wayland::refresh();
+
+    #[cfg(target_os = "windows")]
+    crate::tray_windows::refresh();
 }
 
 pub(crate) fn start(ui: UiHandles) -> Result<(), Box<dyn Error>> {
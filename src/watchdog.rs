@@ -0,0 +1,76 @@
+//! Stall detection for the long-running loops.
+//!
+//! The UI event loop and the scroll loop each record a heartbeat as they run. A background
+//! thread checks them once a second and logs when one has gone quiet for several seconds,
+//! e.g. the UI thread stuck behind a modal dialog, and again once it recovers.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const STALL_AFTER: Duration = Duration::from_secs(5);
+
+static START: OnceLock<Instant> = OnceLock::new();
+// Milliseconds since START of the last beat, offset by one so 0 means "not started yet".
+static BEATS: [AtomicU64; 2] = [AtomicU64::new(0), AtomicU64::new(0)];
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Loop {
+    Ui = 0,
+    Engine = 1,
+}
+
+impl Loop {
+    const ALL: [Self; 2] = [Self::Ui, Self::Engine];
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Ui => "UI event loop",
+            Self::Engine => "scroll loop",
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    START.get_or_init(Instant::now).elapsed().as_millis() as u64 + 1
+}
+
+pub(crate) fn beat(which: Loop) {
+    BEATS[which as usize].store(now_ms(), Ordering::Relaxed);
+}
+
+/// Starts the checking thread; it ends with the app.
+pub(crate) fn start() {
+    let _ = START.get_or_init(Instant::now);
+    let spawned = std::thread::Builder::new()
+        .name("nimbusscroll-watchdog".into())
+        .spawn(|| {
+            let mut stalled = [false; 2];
+            while !crate::engine::should_exit() {
+                std::thread::sleep(CHECK_INTERVAL);
+                let now = now_ms();
+                for which in Loop::ALL {
+                    let last = BEATS[which as usize].load(Ordering::Relaxed);
+                    if last == 0 {
+                        continue;
+                    }
+                    let quiet = Duration::from_millis(now.saturating_sub(last));
+                    let was_stalled = stalled[which as usize];
+                    if quiet >= STALL_AFTER && !was_stalled {
+                        log_error!(
+                            "{} has not run for {:.1}s",
+                            which.name(),
+                            quiet.as_secs_f64()
+                        );
+                    } else if quiet < STALL_AFTER && was_stalled {
+                        log_info!("{} is running again", which.name());
+                    }
+                    stalled[which as usize] = quiet >= STALL_AFTER;
+                }
+            }
+        });
+    if let Err(err) = spawned {
+        log_error!("failed to start the watchdog: {}", err);
+    }
+}
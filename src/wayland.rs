@@ -1,4 +1,4 @@
-use crate::app::Backend;
+use crate::app::{Backend, PointerHold};
 use crate::engine::{InputEvent, MouseDeviceInfo, OutputEvent, ScrollAxis, ScrollKey};
 use evdev::uinput::VirtualDevice;
 use evdev::{
@@ -7,13 +7,29 @@ use evdev::{
 };
 use std::error::Error;
 use std::fs;
-use std::io::ErrorKind;
-use std::process::Command;
+use std::io::{ErrorKind, Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
 use std::time::Duration;
 
 const PROC_INPUT_DEVICES: &str = "/proc/bus/input/devices";
 const EV_KEY: u16 = 1;
 const EV_REL: u16 = 2;
+const POLLIN: i16 = 0x0001;
+const INSTANCE_SHOW_SETTINGS: &[u8] = b"settings";
+
+#[repr(C)]
+struct PollFd {
+    fd: i32,
+    events: i16,
+    revents: i16,
+}
+
+unsafe extern "C" {
+    fn poll(fds: *mut PollFd, nfds: std::ffi::c_ulong, timeout: i32) -> i32;
+}
 
 #[derive(Debug)]
 struct NimbusScrollError(String);
@@ -76,6 +92,13 @@ fn map_output_key(key: ScrollKey) -> Option<KeyCode> {
         ScrollKey::Forward => KeyCode::BTN_FORWARD,
         ScrollKey::Back => KeyCode::BTN_BACK,
         ScrollKey::Task => KeyCode::BTN_TASK,
+        ScrollKey::LeftCtrl => KeyCode::KEY_LEFTCTRL,
+        ScrollKey::Home => KeyCode::KEY_HOME,
+        ScrollKey::End => KeyCode::KEY_END,
+        ScrollKey::PageUp => KeyCode::KEY_PAGEUP,
+        ScrollKey::PageDown => KeyCode::KEY_PAGEDOWN,
+        ScrollKey::Equal => KeyCode::KEY_EQUAL,
+        ScrollKey::Minus => KeyCode::KEY_MINUS,
         ScrollKey::Other(_) => return None,
     })
 }
@@ -264,7 +287,7 @@ fn guess_keyboard_event_from_proc() -> Result<Vec<String>, Box<dyn Error>> {
 }
 
 fn is_keyboard_like(dev: &Device) -> bool {
-    dev.supported_keys().map_or(false, |keys| {
+    dev.supported_keys().is_some_and(|keys| {
         keys.contains(KeyCode::KEY_LEFTMETA) || keys.contains(KeyCode::KEY_RIGHTMETA)
     })
 }
@@ -279,6 +302,13 @@ fn build_virtual_mouse_impl() -> Result<VirtualDevice, Box<dyn Error>> {
     keys.insert(KeyCode::BTN_FORWARD);
     keys.insert(KeyCode::BTN_BACK);
     keys.insert(KeyCode::BTN_TASK);
+    keys.insert(KeyCode::KEY_LEFTCTRL);
+    keys.insert(KeyCode::KEY_HOME);
+    keys.insert(KeyCode::KEY_END);
+    keys.insert(KeyCode::KEY_PAGEUP);
+    keys.insert(KeyCode::KEY_PAGEDOWN);
+    keys.insert(KeyCode::KEY_EQUAL);
+    keys.insert(KeyCode::KEY_MINUS);
 
     let mut rel_axes = AttributeSet::<RelativeAxisCode>::new();
     rel_axes.insert(RelativeAxisCode::REL_X);
@@ -399,6 +429,89 @@ fn active_window_signature_impl() -> Option<String> {
         .or_else(|| run_command_capture("xprop", &["-root", "_NET_ACTIVE_WINDOW"]))
 }
 
+fn foreground_is_fullscreen_impl() -> bool {
+    run_command_capture("hyprctl", &["activewindow"])
+        .map(|info| {
+            info.lines()
+                .filter_map(|line| line.trim().strip_prefix("fullscreen:"))
+                .any(|value| value.trim() != "0" && value.trim() != "false")
+        })
+        .unwrap_or(false)
+}
+
+fn foreground_class_impl() -> Option<String> {
+    run_command_capture("hyprctl", &["activewindow"]).and_then(|info| {
+        info.lines()
+            .filter_map(|line| line.trim().strip_prefix("class:"))
+            .map(|value| value.trim().to_string())
+            .next()
+    })
+}
+
+fn instance_socket_path() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")?;
+    Some(PathBuf::from(dir).join("nimbusscroll.sock"))
+}
+
+/// Asks a running instance to open its settings; true when one answered and this launch
+/// should exit.
+pub(crate) fn signal_running_instance() -> bool {
+    let Some(path) = instance_socket_path() else {
+        return false;
+    };
+    let Ok(mut stream) = UnixStream::connect(&path) else {
+        return false;
+    };
+    if stream.write_all(INSTANCE_SHOW_SETTINGS).is_err() {
+        return false;
+    }
+    log_info!("NimbusScroll is already running, asked it to open settings");
+    true
+}
+
+/// Accepts requests from later launches on a socket in the runtime directory.
+pub(crate) fn listen_for_instances() {
+    let Some(path) = instance_socket_path() else {
+        return;
+    };
+    // Nothing answered on the socket at startup, so a file left there is stale.
+    let _ = fs::remove_file(&path);
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(err) => {
+            log_error!("failed to listen on {}: {}", path.display(), err);
+            return;
+        }
+    };
+
+    let _ = std::thread::Builder::new()
+        .name("instance-listener".into())
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut request = Vec::new();
+                let _ = stream.take(64).read_to_end(&mut request);
+                if request.trim_ascii() == INSTANCE_SHOW_SETTINGS {
+                    crate::tray::request_settings();
+                }
+            }
+        });
+}
+
+// Part of the freedesktop sound theme, installed on most desktops.
+const DEFAULT_SCROLL_SOUND: &str = "/usr/share/sounds/freedesktop/stereo/audio-volume-change.oga";
+
+fn play_scroll_sound_impl(sound_file: Option<&str>) {
+    let path = sound_file.unwrap_or(DEFAULT_SCROLL_SOUND).to_string();
+    // paplay runs for the length of the clip; wait on it off the scroll loop.
+    std::thread::spawn(move || {
+        let _ = Command::new("paplay")
+            .arg(path)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    });
+}
+
 fn run_shell_command(cmd: &str) -> Option<String> {
     let output = Command::new("sh").arg("-lc").arg(cmd).output().ok()?;
     if !output.status.success() {
@@ -483,6 +596,36 @@ impl Backend for WaylandBackend {
         emit_output_impl(out, event)
     }
 
+    fn emit_passthrough(out: &mut Self::Output, event: InputEvent) -> Result<(), Box<dyn Error>> {
+        emit_output_impl(out, event.into())
+    }
+
+    fn foreground_is_fullscreen() -> bool {
+        foreground_is_fullscreen_impl()
+    }
+
+    fn foreground_class() -> Option<String> {
+        foreground_class_impl()
+    }
+
+    fn hold_pointer(_out: &mut Self::Output, _grace: Duration) -> PointerHold {
+        PointerHold::Held
+    }
+
+    fn release_pointer(_out: &mut Self::Output) {}
+
+    fn show_anchor(_out: &mut Self::Output) {}
+
+    fn hide_anchor(_out: &mut Self::Output) {}
+
+    fn play_scroll_sound(sound_file: Option<&str>) {
+        play_scroll_sound_impl(sound_file);
+    }
+
+    fn scroll_edges() -> Option<(bool, bool)> {
+        None
+    }
+
     fn active_window_signature() -> Option<String> {
         active_window_signature_impl()
     }
@@ -490,4 +633,25 @@ impl Backend for WaylandBackend {
     fn sleep(duration: Duration) {
         std::thread::sleep(duration);
     }
+
+    fn wait_for_input(mouse: &Self::Mouse, keyboards: &[Self::Keyboard], timeout: Duration) {
+        wait_for_input_impl(mouse, keyboards, timeout);
+    }
+}
+
+fn wait_for_input_impl(mouse: &Device, keyboards: &[Device], timeout: Duration) {
+    let mut fds: Vec<PollFd> = std::iter::once(mouse)
+        .chain(keyboards)
+        .map(|device| PollFd {
+            fd: device.as_raw_fd(),
+            events: POLLIN,
+            revents: 0,
+        })
+        .collect();
+    let timeout_ms = timeout.as_millis().min(i32::MAX as u128) as i32;
+
+    // Interrupted or failed polls just return early; the caller loops either way.
+    unsafe {
+        let _ = poll(fds.as_mut_ptr(), fds.len() as std::ffi::c_ulong, timeout_ms);
+    }
 }
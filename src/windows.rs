@@ -1,13 +1,20 @@
-use crate::app::Backend;
-use crate::engine::{InputEvent, MouseDeviceInfo, OutputEvent, ScrollAxis, ScrollKey};
-
+use crate::app::{Backend, PointerHold};
+use crate::engine::{
+    InputEvent, MouseDeviceInfo, OutputEvent, OutputMethod, ScrollAxis, ScrollKey, TurboButton,
+    WheelAction, WheelMode,
+};
+
+use std::any::Any;
+use std::cell::Cell;
 use std::collections::VecDeque;
 use std::error::Error;
 use std::ffi::c_void;
 use std::mem::{self, ManuallyDrop};
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
-use std::sync::{Arc, Mutex, OnceLock};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
 
 type BOOL = i32;
 type UINT = u32;
@@ -23,6 +30,7 @@ type HINSTANCE = *mut c_void;
 type HMODULE = *mut c_void;
 type HWND = *mut c_void;
 type HRAWINPUT = isize;
+type HMONITOR = *mut c_void;
 type HHOOK = *mut c_void;
 
 pub(crate) struct WindowsBackend;
@@ -31,12 +39,41 @@ const XBUTTON1_DATA: u32 = 1;
 const XBUTTON2_DATA: u32 = 2;
 
 const WM_QUIT: UINT = 0x0012;
+const WM_PAINT: UINT = 0x000F;
 const WM_DESTROY: UINT = 0x0002;
+const WM_QUERYENDSESSION: UINT = 0x0011;
+const WM_ENDSESSION: UINT = 0x0016;
+const WM_SETTINGCHANGE: UINT = 0x001A;
+const WM_DISPLAYCHANGE: UINT = 0x007E;
 const WM_INPUT: UINT = 0x00FF;
 const WM_KEYDOWN: UINT = 0x0100;
 const WM_KEYUP: UINT = 0x0101;
 const WM_SYSKEYDOWN: UINT = 0x0104;
 const WM_SYSKEYUP: UINT = 0x0105;
+const WM_MOUSEMOVE: UINT = 0x0200;
+const WM_LBUTTONDOWN: UINT = 0x0201;
+const WM_LBUTTONUP: UINT = 0x0202;
+const WM_RBUTTONDOWN: UINT = 0x0204;
+const WM_RBUTTONUP: UINT = 0x0205;
+const WM_MBUTTONDOWN: UINT = 0x0207;
+const WM_MBUTTONUP: UINT = 0x0208;
+const WM_MOUSEWHEEL: UINT = 0x020A;
+const WM_XBUTTONDOWN: UINT = 0x020B;
+const WM_XBUTTONUP: UINT = 0x020C;
+const WM_MOUSEHWHEEL: UINT = 0x020E;
+const WM_APP: UINT = 0x8000;
+const WM_ANCHOR_SHOW: UINT = WM_APP + 1;
+const WM_ANCHOR_HIDE: UINT = WM_APP + 2;
+const WM_SHOW_SETTINGS: UINT = WM_APP + 3;
+const WM_WHEEL_HOOK_SYNC: UINT = WM_APP + 4;
+
+const SPI_GETWHEELSCROLLLINES: UINT = 0x0068;
+const SPI_SETWHEELSCROLLLINES: UINT = 0x0069;
+const SPI_GETWHEELSCROLLCHARS: UINT = 0x006C;
+const SPI_SETWHEELSCROLLCHARS: UINT = 0x006D;
+
+const WH_MOUSE_LL: i32 = 14;
+const HC_ACTION: i32 = 0;
 
 const RIDEV_INPUTSINK: DWORD = 0x00000100;
 const RID_INPUT: UINT = 0x10000003;
@@ -57,8 +94,60 @@ const RI_MOUSE_BUTTON_5_UP: WORD = 0x0200;
 const RI_MOUSE_WHEEL: WORD = 0x0400;
 const RI_MOUSE_HWHEEL: WORD = 0x0800;
 
+// (down flag, up flag, key) for each button a raw mouse report carries.
+const RAW_MOUSE_BUTTONS: [(WORD, WORD, ScrollKey); 5] = [
+    (
+        RI_MOUSE_LEFT_BUTTON_DOWN,
+        RI_MOUSE_LEFT_BUTTON_UP,
+        ScrollKey::Left,
+    ),
+    (
+        RI_MOUSE_RIGHT_BUTTON_DOWN,
+        RI_MOUSE_RIGHT_BUTTON_UP,
+        ScrollKey::Right,
+    ),
+    (
+        RI_MOUSE_MIDDLE_BUTTON_DOWN,
+        RI_MOUSE_MIDDLE_BUTTON_UP,
+        ScrollKey::Middle,
+    ),
+    (
+        RI_MOUSE_BUTTON_4_DOWN,
+        RI_MOUSE_BUTTON_4_UP,
+        ScrollKey::Back,
+    ),
+    (
+        RI_MOUSE_BUTTON_5_DOWN,
+        RI_MOUSE_BUTTON_5_UP,
+        ScrollKey::Forward,
+    ),
+];
+
 const RI_KEY_BREAK: WORD = 0x0001;
 
+const ATTACH_PARENT_PROCESS: DWORD = u32::MAX;
+const IDYES: i32 = 6;
+const MB_YESNO: UINT = 0x00000004;
+const MB_ICONQUESTION: UINT = 0x00000020;
+const MB_ICONWARNING: UINT = 0x00000030;
+const MB_SETFOREGROUND: UINT = 0x00010000;
+const SW_SHOWNORMAL: i32 = 1;
+
+const SND_ASYNC: DWORD = 0x0001;
+const SND_NODEFAULT: DWORD = 0x0002;
+const SND_FILENAME: DWORD = 0x0002_0000;
+
+const INPUT_KEYBOARD: DWORD = 1;
+const KEYEVENTF_EXTENDEDKEY: DWORD = 0x0001;
+const KEYEVENTF_KEYUP: DWORD = 0x0002;
+const VK_CONTROL: WORD = 0x11;
+const VK_PRIOR: WORD = 0x21;
+const VK_NEXT: WORD = 0x22;
+const VK_END: WORD = 0x23;
+const VK_HOME: WORD = 0x24;
+const VK_OEM_PLUS: WORD = 0xBB;
+const VK_OEM_MINUS: WORD = 0xBD;
+
 const MOUSEEVENTF_MOVE: DWORD = 0x0001;
 const MOUSEEVENTF_LEFTDOWN: DWORD = 0x0002;
 const MOUSEEVENTF_LEFTUP: DWORD = 0x0004;
@@ -73,8 +162,85 @@ const MOUSEEVENTF_HWHEEL: DWORD = 0x1000;
 const MOUSEEVENTF_MOVE_NOCOALESCE: DWORD = 0x2000;
 
 const HWND_MESSAGE: HWND = (-3isize) as HWND;
-
-static MAGIC_WORD: [u8; 8] = *b"PASS\0\0\0\0";
+const ASFW_ANY: DWORD = u32::MAX;
+const RAW_INPUT_CLASS: &[u8] = b"NimbusScrollRawInput\0";
+const TASKBAR_CLASS: &[u8] = b"Shell_TrayWnd\0";
+const TASKBAR_CREATED: &[u8] = b"TaskbarCreated\0";
+const MSGFLT_ADD: DWORD = 1;
+
+const TH32CS_SNAPPROCESS: DWORD = 0x00000002;
+const INVALID_HANDLE_VALUE: HANDLE = (-1isize) as HANDLE;
+const SYNCHRONIZE: DWORD = 0x00100000;
+const MAX_PATH: usize = 260;
+// Smooth-scroll tools whose output stacks on ours, checked when `conflict_apps` is empty.
+const KNOWN_CONFLICTS: &str = "SmoothScroll.exe,WizMouse.exe";
+// Let startup settle before probing, so the timing is not just a busy logon.
+const CONFLICT_CHECK_DELAY: Duration = Duration::from_secs(2);
+const HOOK_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+// A hook chain that is merely busy answers in a few milliseconds.
+const HOOK_PROBE_SLOW: Duration = Duration::from_millis(100);
+
+const GWL_EXSTYLE: i32 = -20;
+const GWL_STYLE: i32 = -16;
+const WS_VSCROLL: LONG = 0x00200000;
+const WS_HSCROLL: LONG = 0x00100000;
+const SB_VERT: i32 = 1;
+const SIF_RANGE: UINT = 0x0001;
+const SIF_PAGE: UINT = 0x0002;
+const SIF_POS: UINT = 0x0004;
+const WS_EX_TOOLWINDOW: LONG = 0x00000080;
+const WS_EX_NOACTIVATE: LONG = 0x08000000;
+const WS_EX_TOPMOST: LONG = 0x00000008;
+const WS_EX_TRANSPARENT: LONG = 0x00000020;
+const WS_EX_LAYERED: LONG = 0x00080000;
+const WS_POPUP: DWORD = 0x80000000;
+const MONITOR_DEFAULTTONULL: DWORD = 0x00000000;
+const MONITOR_DEFAULTTONEAREST: DWORD = 0x00000002;
+
+const CLIP_CONTENTION_LIMIT: u32 = 3;
+
+const CREATE_WAITABLE_TIMER_HIGH_RESOLUTION: DWORD = 0x00000002;
+const TIMER_ALL_ACCESS: DWORD = 0x001F0003;
+const INFINITE: DWORD = 0xFFFFFFFF;
+const WAIT_OBJECT_0: DWORD = 0;
+
+const GA_PARENT: UINT = 1;
+const GA_ROOT: UINT = 2;
+const SMTO_ABORTIFHUNG: UINT = 0x0002;
+const WHEEL_SEND_TIMEOUT_MS: UINT = 50;
+
+const HWND_TOPMOST: HWND = (-1isize) as HWND;
+const SWP_NOACTIVATE: UINT = 0x0010;
+const SWP_SHOWWINDOW: UINT = 0x0040;
+const SW_HIDE: i32 = 0;
+const LWA_COLORKEY: DWORD = 0x00000001;
+const BLACK_BRUSH: i32 = 4;
+
+const ANCHOR_SIZE: i32 = 24;
+const ANCHOR_KEY_COLOR: DWORD = 0x00FF00FF;
+const ANCHOR_FILL_COLOR: DWORD = 0x00F0F0F0;
+
+const SHELL_WINDOW_CLASSES: [&str; 4] = [
+    "Progman",
+    "WorkerW",
+    "Shell_TrayWnd",
+    "Shell_SecondaryTrayWnd",
+];
+
+// Top-level classes that scroll without standard scroll bars: browsers, Electron, UWP,
+// terminals and Office.
+const SCROLLABLE_WINDOW_CLASSES: [&str; 8] = [
+    "Chrome_WidgetWin_1",
+    "MozillaWindowClass",
+    "ApplicationFrameWindow",
+    "CASCADIA_HOSTING_WINDOW_CLASS",
+    "ConsoleWindowClass",
+    "OpusApp",
+    "XLMAIN",
+    "PPTFrameClass",
+];
+// How long a broadcast target list is reused while the foreground window stays put.
+const BROADCAST_REFRESH: Duration = Duration::from_millis(500);
 
 #[derive(Default)]
 pub struct WindowsMouseHandle;
@@ -83,11 +249,19 @@ pub struct WindowsMouseHandle;
 pub struct WindowsKeyboardHandle;
 
 #[derive(Default)]
-pub struct WindowsOutputHandle;
+pub struct WindowsOutputHandle {
+    clip: Option<RECT>,
+    clipped_at: Option<Instant>,
+    clip_contention: u32,
+    broadcast: Option<BroadcastTargets>,
+}
 
 pub struct WindowsInputState {
     mouse_events: Mutex<VecDeque<InputEvent>>,
     keyboard_events: Mutex<VecDeque<InputEvent>>,
+    // Set by the raw input thread when it queues an event, so an idle engine can block.
+    input_pending: Mutex<bool>,
+    input_ready: Condvar,
 }
 
 impl WindowsInputState {
@@ -95,45 +269,85 @@ impl WindowsInputState {
         Self {
             mouse_events: Mutex::new(VecDeque::new()),
             keyboard_events: Mutex::new(VecDeque::new()),
+            input_pending: Mutex::new(false),
+            input_ready: Condvar::new(),
         }
     }
 
     fn push_mouse(&self, ev: InputEvent) {
-        if let Ok(mut q) = self.mouse_events.lock() {
-            q.push_back(ev);
-        }
+        lock(&self.mouse_events).push_back(ev);
+        self.notify_input();
     }
 
     fn push_keyboard(&self, ev: InputEvent) {
-        if let Ok(mut q) = self.keyboard_events.lock() {
-            q.push_back(ev);
-        }
+        lock(&self.keyboard_events).push_back(ev);
+        self.notify_input();
+    }
+
+    fn notify_input(&self) {
+        *lock(&self.input_pending) = true;
+        self.input_ready.notify_one();
+    }
+
+    fn wait_input(&self, timeout: Duration) {
+        let pending = lock(&self.input_pending);
+        let (mut pending, _) = self
+            .input_ready
+            .wait_timeout_while(pending, timeout, |pending| !*pending)
+            .unwrap_or_else(PoisonError::into_inner);
+        *pending = false;
     }
 
     fn drain_mouse(&self) -> Vec<InputEvent> {
-        let mut out = Vec::new();
-        if let Ok(mut q) = self.mouse_events.lock() {
-            while let Some(ev) = q.pop_front() {
-                out.push(ev);
-            }
-        }
-        out
+        lock(&self.mouse_events).drain(..).collect()
     }
 
     fn drain_keyboard(&self) -> Vec<InputEvent> {
-        let mut out = Vec::new();
-        if let Ok(mut q) = self.keyboard_events.lock() {
-            while let Some(ev) = q.pop_front() {
-                out.push(ev);
-            }
-        }
-        out
+        lock(&self.keyboard_events).drain(..).collect()
     }
 }
 
+// A panic while a queue is held must not silently drop every later event.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Runs a body called from user32, logging a panic and returning `fallback` instead of
+/// unwinding into the message loop.
+fn guard_callback<T>(name: &str, fallback: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        log_error!("{} panicked: {}", name, panic_message(payload.as_ref()));
+        fallback
+    })
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
 static STATE: OnceLock<Arc<WindowsInputState>> = OnceLock::new();
 static RAW_THREAD_ID: OnceLock<u32> = OnceLock::new();
 static RAW_THREAD_STARTED: OnceLock<()> = OnceLock::new();
+static TIMER_PERIOD_RAISED: AtomicBool = AtomicBool::new(false);
+// Mirror the raw input thread's wheel hook, so the scroll loop can ask for it to follow config.
+static WHEEL_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+static WHEEL_HOOK_FAILED: AtomicBool = AtomicBool::new(false);
+// Buttons whose physical press the hook kept from apps, one bit per `hook_button_bit`; their
+// releases are kept back too.
+static HOOK_HELD_BUTTONS: AtomicU32 = AtomicU32::new(0);
+// Set by WM_DISPLAYCHANGE; the scroll loop drops its drag clip and takes a fresh one.
+static DISPLAY_CHANGED: AtomicBool = AtomicBool::new(false);
+// When the startup hook probe was sent, and how long our hook took to see it.
+static HOOK_PROBE_SENT: OnceLock<Instant> = OnceLock::new();
+static HOOK_PROBE_SEEN: OnceLock<Duration> = OnceLock::new();
+
+thread_local! {
+    static HIRES_TIMER: Cell<HANDLE> = const { Cell::new(ptr::null_mut()) };
+}
 
 fn state() -> Arc<WindowsInputState> {
     STATE
@@ -149,12 +363,51 @@ pub(crate) fn request_exit() {
     }
 }
 
+/// Windows may kill the process as soon as WM_ENDSESSION returns, without unwinding, so undo
+/// anything that would outlive it here. Settings are written as they change; only an
+/// uncommitted settings preview is dropped.
+fn end_session() {
+    log_info!("session ending, releasing the cursor clip and the tray icon");
+    unsafe {
+        ClipCursor(ptr::null());
+    }
+    crate::engine::request_exit();
+    crate::tray_windows::remove();
+}
+
+/// A monitor was added or removed, or the resolution changed: any clip taken for a drag is in
+/// the old layout, so lift it now and let the scroll loop take a fresh one.
+fn display_changed(wparam: WPARAM, lparam: LPARAM) {
+    let width = lparam & 0xFFFF;
+    let height = (lparam >> 16) & 0xFFFF;
+    log_info!(
+        "display changed to {}x{} at {} bpp, resetting the cursor clip",
+        width,
+        height,
+        wparam
+    );
+    unsafe {
+        ClipCursor(ptr::null());
+    }
+    DISPLAY_CHANGED.store(true, Ordering::Relaxed);
+}
+
 fn ensure_thread_started() {
     let _ = RAW_THREAD_STARTED.get_or_init(|| {
         let _ = std::thread::Builder::new()
             .name("nimbusscroll-win-rawinput".into())
-            .spawn(move || unsafe {
-                raw_input_thread_main();
+            .spawn(move || {
+                let exited = panic::catch_unwind(|| unsafe { raw_input_thread_main() });
+                if let Err(payload) = exited {
+                    log_error!(
+                        "raw input thread panicked: {}",
+                        panic_message(payload.as_ref())
+                    );
+                    // Nothing will deliver the button release now, so drop any drag clip.
+                    unsafe {
+                        ClipCursor(ptr::null());
+                    }
+                }
             });
     });
 }
@@ -179,7 +432,7 @@ fn open_keyboard_devices_impl() -> Result<Vec<WindowsKeyboardHandle>, Box<dyn Er
 
 fn build_virtual_mouse_impl() -> Result<WindowsOutputHandle, Box<dyn Error>> {
     ensure_thread_started();
-    Ok(WindowsOutputHandle)
+    Ok(WindowsOutputHandle::default())
 }
 
 fn reset_virtual_mouse_buttons_impl(_out: &mut WindowsOutputHandle) -> Result<(), Box<dyn Error>> {
@@ -187,9 +440,123 @@ fn reset_virtual_mouse_buttons_impl(_out: &mut WindowsOutputHandle) -> Result<()
 }
 
 fn poll_mouse_events_impl() -> Vec<InputEvent> {
+    sync_wheel_hook();
     state().drain_mouse()
 }
 
+/// The low-level hook is only there to swallow physical input the engine replaces: the wheel in
+/// replace mode or where a class list turns it into zoom keys or nothing, side buttons mapped to
+/// a key or used for turbo, and the clicks that start and end autoscroll. Otherwise raw input
+/// alone is enough.
+fn wheel_hook_wanted() -> bool {
+    let cfg = crate::engine::config();
+    cfg.wheel_mode() == WheelMode::Replace
+        || cfg.zoom_for_classes().is_some()
+        || cfg.wheel_off_for_classes().is_some()
+        || cfg.back_button_action().key().is_some()
+        || cfg.forward_button_action().key().is_some()
+        || cfg.turbo_button() != TurboButton::Off
+        || cfg.auto_scroll()
+}
+
+/// Whether the engine turns a press of `key` into something else, so the physical click must
+/// not reach the app as well. Raw input cannot block it.
+fn engine_takes_button(key: ScrollKey) -> bool {
+    let cfg = crate::engine::config();
+    // Any click ends autoscroll, and the engine keeps that click to itself.
+    if cfg.turbo_button().matches(key) || crate::engine::is_auto_scrolling() {
+        return true;
+    }
+    match key {
+        // The engine holds a middle press back until it knows whether it starts autoscroll,
+        // and sends it on itself if not.
+        ScrollKey::Middle => cfg.auto_scroll() && cfg.middle_scroll_enabled(),
+        ScrollKey::Back => cfg.back_button_action().key().is_some(),
+        ScrollKey::Forward => cfg.forward_button_action().key().is_some(),
+        _ => false,
+    }
+}
+
+/// The button a low-level hook message is about, and whether it was pressed.
+fn hook_button(msg: UINT, info: &MSLLHOOKSTRUCT) -> Option<(ScrollKey, bool)> {
+    match msg {
+        WM_LBUTTONDOWN => Some((ScrollKey::Left, true)),
+        WM_LBUTTONUP => Some((ScrollKey::Left, false)),
+        WM_RBUTTONDOWN => Some((ScrollKey::Right, true)),
+        WM_RBUTTONUP => Some((ScrollKey::Right, false)),
+        WM_MBUTTONDOWN => Some((ScrollKey::Middle, true)),
+        WM_MBUTTONUP => Some((ScrollKey::Middle, false)),
+        WM_XBUTTONDOWN | WM_XBUTTONUP => {
+            // The high word of mouseData says which X button: 1 is back, 2 is forward.
+            let key = match info.mouse_data >> 16 {
+                1 => ScrollKey::Back,
+                2 => ScrollKey::Forward,
+                _ => return None,
+            };
+            Some((key, msg == WM_XBUTTONDOWN))
+        }
+        _ => None,
+    }
+}
+
+fn hook_button_bit(key: ScrollKey) -> u32 {
+    match key {
+        ScrollKey::Left => 1,
+        ScrollKey::Right => 2,
+        ScrollKey::Middle => 4,
+        ScrollKey::Back => 8,
+        ScrollKey::Forward => 16,
+        _ => 0,
+    }
+}
+
+/// Decides whether the hook swallows a physical button event. A release is swallowed exactly
+/// when its press was, whatever changed in between, so apps never see half a click.
+fn swallow_button(key: ScrollKey, pressed: bool) -> bool {
+    let bit = hook_button_bit(key);
+    if !pressed {
+        return HOOK_HELD_BUTTONS.fetch_and(!bit, Ordering::Relaxed) & bit != 0;
+    }
+    if crate::engine::is_suspended() || !engine_takes_button(key) {
+        return false;
+    }
+    HOOK_HELD_BUTTONS.fetch_or(bit, Ordering::Relaxed);
+    true
+}
+
+/// Asks the raw input thread to install or remove the wheel hook after a config change.
+fn sync_wheel_hook() {
+    if !WHEEL_HOOK_FAILED.load(Ordering::Relaxed)
+        && wheel_hook_wanted() != WHEEL_HOOK_INSTALLED.load(Ordering::Relaxed)
+    {
+        post_to_raw_thread(WM_WHEEL_HOOK_SYNC, 0, 0);
+    }
+}
+
+/// Installs or removes the wheel hook to match the config; runs on the raw input thread,
+/// which then services the hook from its message loop.
+unsafe fn update_wheel_hook(hook: HHOOK, h_instance: HINSTANCE) -> HHOOK {
+    let wanted = wheel_hook_wanted();
+    if wanted && hook.is_null() {
+        let hook = SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook_proc), h_instance, 0);
+        if hook.is_null() {
+            log_error!("SetWindowsHookExW failed, the physical wheel will not be suppressed");
+            WHEEL_HOOK_FAILED.store(true, Ordering::Relaxed);
+        } else {
+            log_info!("wheel hook installed");
+        }
+        WHEEL_HOOK_INSTALLED.store(!hook.is_null(), Ordering::Relaxed);
+        hook
+    } else if !wanted && !hook.is_null() {
+        UnhookWindowsHookEx(hook);
+        WHEEL_HOOK_INSTALLED.store(false, Ordering::Relaxed);
+        log_info!("wheel hook removed");
+        ptr::null_mut()
+    } else {
+        hook
+    }
+}
+
 fn poll_keyboard_events_impl() -> Vec<InputEvent> {
     state().drain_keyboard()
 }
@@ -205,6 +572,478 @@ fn active_window_signature_impl() -> Option<String> {
     }
 }
 
+fn foreground_class_impl() -> Option<String> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() {
+            None
+        } else {
+            window_class_name(hwnd)
+        }
+    }
+}
+
+fn foreground_is_fullscreen_impl() -> bool {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() || is_shell_window(hwnd) {
+            return false;
+        }
+
+        let mut rect = RECT {
+            left: 0,
+            top: 0,
+            right: 0,
+            bottom: 0,
+        };
+        if GetWindowRect(hwnd, &mut rect) == 0 {
+            return false;
+        }
+
+        let monitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST);
+        let mut info = MONITORINFO {
+            cb_size: mem::size_of::<MONITORINFO>() as DWORD,
+            rc_monitor: rect,
+            rc_work: rect,
+            dw_flags: 0,
+        };
+        if monitor.is_null() || GetMonitorInfoA(monitor, &mut info) == 0 {
+            return false;
+        }
+
+        let screen = info.rc_monitor;
+        rect.left <= screen.left
+            && rect.top <= screen.top
+            && rect.right >= screen.right
+            && rect.bottom >= screen.bottom
+    }
+}
+
+fn hold_pointer_impl(out: &mut WindowsOutputHandle, grace: Duration) -> PointerHold {
+    if DISPLAY_CHANGED.swap(false, Ordering::Relaxed) {
+        // The old rect may now lie off every monitor; the cursor was already moved back onto
+        // the desktop, so clip again wherever it is. Not another app's clip, so no contention.
+        out.clip = None;
+        out.clip_contention = 0;
+    }
+
+    unsafe {
+        let Some(clip) = out.clip else {
+            let mut cursor = POINT { x: 0, y: 0 };
+            if GetCursorPos(&mut cursor) == 0 {
+                return PointerHold::Held;
+            }
+
+            let rect = RECT {
+                left: cursor.x,
+                top: cursor.y,
+                right: cursor.x + 1,
+                bottom: cursor.y + 1,
+            };
+            if ClipCursor(&rect) != 0 {
+                out.clip = Some(rect);
+                out.clipped_at = Some(Instant::now());
+            }
+            return PointerHold::Held;
+        };
+
+        let mut current = RECT {
+            left: 0,
+            top: 0,
+            right: 0,
+            bottom: 0,
+        };
+        if GetClipCursor(&mut current) == 0 || current == clip {
+            return PointerHold::Held;
+        }
+
+        let recently_clipped = out.clipped_at.is_some_and(|at| at.elapsed() < grace);
+        if recently_clipped {
+            return PointerHold::Held;
+        }
+
+        out.clip_contention += 1;
+        log_info!(
+            "cursor clip changed by another application ({}/{})",
+            out.clip_contention,
+            CLIP_CONTENTION_LIMIT
+        );
+
+        if out.clip_contention >= CLIP_CONTENTION_LIMIT {
+            // The clip belongs to someone else now; forget ours without resetting theirs.
+            out.clip = None;
+            out.clipped_at = None;
+            return PointerHold::Contended;
+        }
+
+        ClipCursor(&clip);
+        out.clipped_at = Some(Instant::now());
+        PointerHold::Held
+    }
+}
+
+fn release_pointer_impl(out: &mut WindowsOutputHandle) {
+    out.clip_contention = 0;
+    out.clipped_at = None;
+
+    let Some(clip) = out.clip.take() else {
+        return;
+    };
+
+    unsafe {
+        let mut current = RECT {
+            left: 0,
+            top: 0,
+            right: 0,
+            bottom: 0,
+        };
+        if GetClipCursor(&mut current) != 0 && current == clip {
+            ClipCursor(ptr::null());
+        }
+    }
+}
+
+fn post_to_raw_thread(msg: UINT, wparam: WPARAM, lparam: LPARAM) {
+    if let Some(thread_id) = RAW_THREAD_ID.get() {
+        unsafe {
+            let _ = PostThreadMessageA(*thread_id, msg, wparam, lparam);
+        }
+    }
+}
+
+fn show_anchor_impl() {
+    let mut cursor = POINT { x: 0, y: 0 };
+    if unsafe { GetCursorPos(&mut cursor) } == 0 {
+        return;
+    }
+    post_to_raw_thread(WM_ANCHOR_SHOW, cursor.x as WPARAM, cursor.y as LPARAM);
+}
+
+fn hide_anchor_impl() {
+    post_to_raw_thread(WM_ANCHOR_HIDE, 0, 0);
+}
+
+fn play_scroll_sound_impl(sound_file: Option<&str>) {
+    // Explorer's navigation click, present since Vista.
+    let path = match sound_file {
+        Some(path) => path.to_string(),
+        None => {
+            let root = std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".to_string());
+            format!("{}\\Media\\Windows Navigation Start.wav", root)
+        }
+    };
+    let path: Vec<u16> = path.encode_utf16().chain(Some(0)).collect();
+    // SND_ASYNC returns as soon as playback is queued, so the scroll loop never waits on it.
+    unsafe {
+        PlaySoundW(
+            path.as_ptr(),
+            ptr::null_mut(),
+            SND_ASYNC | SND_FILENAME | SND_NODEFAULT,
+        );
+    }
+}
+
+pub(crate) fn double_click_time() -> Duration {
+    Duration::from_millis(unsafe { GetDoubleClickTime() } as u64)
+}
+
+/// Without elevation, raw input and injected scrolling do not reach elevated windows.
+pub(crate) fn is_elevated() -> bool {
+    unsafe { IsUserAnAdmin() != 0 }
+}
+
+/// Starts a second, elevated copy through the UAC prompt. Returns false if it was refused.
+pub(crate) fn restart_elevated() -> bool {
+    let Ok(exe) = std::env::current_exe() else {
+        return false;
+    };
+    let verb: Vec<u16> = "runas".encode_utf16().chain(Some(0)).collect();
+    let file: Vec<u16> = exe
+        .as_os_str()
+        .to_string_lossy()
+        .encode_utf16()
+        .chain(Some(0))
+        .collect();
+
+    // ShellExecute reports success with a value above 32.
+    let result = unsafe {
+        ShellExecuteW(
+            ptr::null_mut(),
+            verb.as_ptr(),
+            file.as_ptr(),
+            ptr::null(),
+            ptr::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    if (result as isize) <= 32 {
+        log_error!(
+            "elevated restart failed: ShellExecuteW returned {}",
+            result as isize
+        );
+        return false;
+    }
+    true
+}
+
+pub(crate) fn attach_parent_console() {
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+/// Shows a warning box on its own thread so the caller (often the UI thread) is not blocked.
+pub(crate) fn show_error(title: &str, text: &str) {
+    let title = title.to_string();
+    let text = text.to_string();
+    let _ = std::thread::Builder::new()
+        .name("nimbusscroll-message".into())
+        .spawn(move || warning_box(&title, &text));
+}
+
+fn warning_box(title: &str, text: &str) {
+    let title: Vec<u16> = title.encode_utf16().chain(Some(0)).collect();
+    let text: Vec<u16> = text.encode_utf16().chain(Some(0)).collect();
+    unsafe {
+        let _ = MessageBoxW(
+            ptr::null_mut(),
+            text.as_ptr(),
+            title.as_ptr(),
+            MB_ICONWARNING | MB_SETFOREGROUND,
+        );
+    }
+}
+
+/// Asks a yes/no question and blocks until it is answered.
+pub(crate) fn confirm(title: &str, text: &str) -> bool {
+    let title: Vec<u16> = title.encode_utf16().chain(Some(0)).collect();
+    let text: Vec<u16> = text.encode_utf16().chain(Some(0)).collect();
+    let answer = unsafe {
+        MessageBoxW(
+            ptr::null_mut(),
+            text.as_ptr(),
+            title.as_ptr(),
+            MB_YESNO | MB_ICONQUESTION | MB_SETFOREGROUND,
+        )
+    };
+    answer == IDYES
+}
+
+/// Asks a running instance to open its settings; true when one was found and this launch
+/// should exit.
+pub(crate) fn signal_running_instance() -> bool {
+    unsafe {
+        let hwnd = FindWindowExA(
+            HWND_MESSAGE,
+            ptr::null_mut(),
+            RAW_INPUT_CLASS.as_ptr(),
+            ptr::null(),
+        );
+        if hwnd.is_null() {
+            return false;
+        }
+
+        // This launch owns the foreground, so pass that on for the settings window.
+        let _ = AllowSetForegroundWindow(ASFW_ANY);
+        if PostMessageA(hwnd, WM_SHOW_SETTINGS, 0, 0) == 0 {
+            // An elevated instance does not accept messages from a normal one.
+            warning_box("NimbusScroll", crate::i18n::tr("instance.running"));
+        }
+    }
+    true
+}
+
+/// True once Explorer's taskbar window exists, so a tray icon can be added.
+pub(crate) fn taskbar_ready() -> bool {
+    unsafe {
+        !FindWindowExA(
+            ptr::null_mut(),
+            ptr::null_mut(),
+            TASKBAR_CLASS.as_ptr(),
+            ptr::null(),
+        )
+        .is_null()
+    }
+}
+
+/// Lets Explorer's "TaskbarCreated" broadcast through to an elevated process, so the tray
+/// icon is re-added after Explorer restarts. Without elevation the message already arrives.
+pub(crate) fn allow_taskbar_created() {
+    unsafe {
+        let msg = RegisterWindowMessageA(TASKBAR_CREATED.as_ptr());
+        if msg != 0 && ChangeWindowMessageFilter(msg, MSGFLT_ADD) == 0 {
+            log_error!("failed to allow TaskbarCreated through the message filter");
+        }
+    }
+}
+
+/// Looks for other smooth-scroll software and a hook chain that holds up input, then warns
+/// once. Runs on its own thread; the probe needs the raw input thread free to pump the hook.
+fn start_conflict_check(probe_hook: bool) {
+    let _ = std::thread::Builder::new()
+        .name("nimbusscroll-conflicts".into())
+        .spawn(move || {
+            std::thread::sleep(CONFLICT_CHECK_DELAY);
+            let mut problems = Vec::new();
+
+            let found = running_conflicts();
+            if !found.is_empty() {
+                log_info!(
+                    "other smooth-scroll software is running: {}",
+                    found.join(", ")
+                );
+                problems.push(format!(
+                    "Other smooth-scroll software is running: {}. Scrolling may lag or move \
+                     twice as far; turn its smoothing off or close it.",
+                    found.join(", ")
+                ));
+            }
+
+            if probe_hook {
+                match probe_hook_latency() {
+                    Some(latency) if latency < HOOK_PROBE_SLOW => {}
+                    Some(latency) => {
+                        log_info!("mouse hook probe took {} ms", latency.as_millis());
+                        problems.push(format!(
+                            "Another program's mouse hook is delaying input by about {} ms.",
+                            latency.as_millis()
+                        ));
+                    }
+                    None => {
+                        log_info!("mouse hook probe never arrived");
+                        problems.push(
+                            "Another program's mouse hook did not pass a test event on, so \
+                             the physical wheel may not be suppressed."
+                                .to_string(),
+                        );
+                    }
+                }
+            }
+
+            if !problems.is_empty() {
+                problems.push("Set conflict_check=false to stop this check.".to_string());
+                show_error("NimbusScroll", &problems.join("\n\n"));
+            }
+        });
+}
+
+/// Entries of `conflict_apps` (or the built-in list) that are present: an `.exe` entry is a
+/// running process, anything else a top-level window class or a named mutex.
+fn running_conflicts() -> Vec<String> {
+    let list = crate::engine::config()
+        .conflict_apps()
+        .unwrap_or_else(|| KNOWN_CONFLICTS.to_string());
+    let entries: Vec<&str> = list
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .collect();
+    let processes = running_process_names();
+
+    entries
+        .into_iter()
+        .filter(|entry| {
+            if entry.to_ascii_lowercase().ends_with(".exe") {
+                processes
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(entry))
+            } else {
+                window_class_exists(entry) || mutex_exists(entry)
+            }
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+fn running_process_names() -> Vec<String> {
+    let mut names = Vec::new();
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return names;
+        }
+        let mut entry: PROCESSENTRY32W = mem::zeroed();
+        entry.dw_size = mem::size_of::<PROCESSENTRY32W>() as DWORD;
+        let mut more = Process32FirstW(snapshot, &mut entry) != 0;
+        while more {
+            let len = entry
+                .sz_exe_file
+                .iter()
+                .position(|&c| c == 0)
+                .unwrap_or(MAX_PATH);
+            names.push(String::from_utf16_lossy(&entry.sz_exe_file[..len]));
+            more = Process32NextW(snapshot, &mut entry) != 0;
+        }
+        CloseHandle(snapshot);
+    }
+    names
+}
+
+fn window_class_exists(class: &str) -> bool {
+    let class: Vec<u8> = class.bytes().chain(Some(0)).collect();
+    unsafe {
+        !FindWindowExA(
+            ptr::null_mut(),
+            ptr::null_mut(),
+            class.as_ptr(),
+            ptr::null(),
+        )
+        .is_null()
+    }
+}
+
+fn mutex_exists(name: &str) -> bool {
+    let name: Vec<u16> = name.encode_utf16().chain(Some(0)).collect();
+    unsafe {
+        let handle = OpenMutexW(SYNCHRONIZE, 0, name.as_ptr());
+        if handle.is_null() {
+            return false;
+        }
+        CloseHandle(handle);
+        true
+    }
+}
+
+/// Injects a tagged zero-length move and times how long our hook takes to see it. Hooks
+/// installed after ours run first, so a slow or swallowing one shows up here.
+fn probe_hook_latency() -> Option<Duration> {
+    if HOOK_PROBE_SENT.set(Instant::now()).is_err() {
+        return HOOK_PROBE_SEEN.get().copied();
+    }
+    send_mouse_input(MOUSEEVENTF_MOVE, 0, 0, 0);
+
+    let deadline = Instant::now() + HOOK_PROBE_TIMEOUT;
+    while Instant::now() < deadline {
+        if let Some(latency) = HOOK_PROBE_SEEN.get() {
+            return Some(*latency);
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    None
+}
+
+fn note_hook_probe(info: &MSLLHOOKSTRUCT) {
+    if HOOK_PROBE_SEEN.get().is_some() || info.dw_extra_info as DWORD != input_tag() {
+        return;
+    }
+    if let Some(sent) = HOOK_PROBE_SENT.get() {
+        let _ = HOOK_PROBE_SEEN.set(sent.elapsed());
+    }
+}
+
+pub(crate) fn debug_output(line: &str) {
+    let mut bytes = line.as_bytes().to_vec();
+    bytes.extend_from_slice(b"\r\n\0");
+    unsafe {
+        OutputDebugStringA(bytes.as_ptr());
+    }
+}
+
+/// Raw input only carries the low 32 bits of the extra info, so the tag stays within a DWORD.
+fn input_tag() -> DWORD {
+    crate::engine::config().input_tag() as DWORD
+}
+
 fn send_mouse_input(flags: DWORD, data: DWORD, dx: LONG, dy: LONG) {
     unsafe {
         let input = INPUT {
@@ -216,7 +1055,34 @@ fn send_mouse_input(flags: DWORD, data: DWORD, dx: LONG, dy: LONG) {
                     mouse_data: data as i32,
                     dw_flags: flags,
                     time: 0,
-                    dw_extra_info: MAGIC_WORD.as_ptr() as usize,
+                    dw_extra_info: input_tag() as ULONG_PTR,
+                }),
+            },
+        };
+
+        let _ = SendInput(1, &input, mem::size_of::<INPUT>() as i32);
+    }
+}
+
+fn send_key_input(vk: WORD, key_up: bool) {
+    let mut flags = 0;
+    if matches!(vk, VK_HOME | VK_END | VK_PRIOR | VK_NEXT) {
+        flags |= KEYEVENTF_EXTENDEDKEY;
+    }
+    if key_up {
+        flags |= KEYEVENTF_KEYUP;
+    }
+
+    unsafe {
+        let input = INPUT {
+            type_: INPUT_KEYBOARD,
+            input: INPUT_UNION {
+                ki: ManuallyDrop::new(KEYBDINPUT {
+                    w_vk: vk,
+                    w_scan: 0,
+                    dw_flags: flags,
+                    time: 0,
+                    dw_extra_info: input_tag() as ULONG_PTR,
                 }),
             },
         };
@@ -232,7 +1098,7 @@ fn send_relative_mouse_move(dx: i32, dy: i32) {
     send_mouse_input(MOUSEEVENTF_MOVE | MOUSEEVENTF_MOVE_NOCOALESCE, 0, dx, dy);
 }
 
-fn emit_output_impl(event: OutputEvent) {
+fn emit_output_impl(out: &mut WindowsOutputHandle, event: OutputEvent) {
     match event {
         OutputEvent::Key { key, value } => match key {
             ScrollKey::Left => {
@@ -284,17 +1150,278 @@ fn emit_output_impl(event: OutputEvent) {
                     send_mouse_input(MOUSEEVENTF_XUP, XBUTTON2_DATA, 0, 0);
                 }
             }
-            ScrollKey::LeftMeta | ScrollKey::RightMeta | ScrollKey::Task | ScrollKey::Other(_) => {}
-        },
-        OutputEvent::Rel { axis, value } => match axis {
-            ScrollAxis::X => send_relative_mouse_move(value, 0),
-            ScrollAxis::Y => send_relative_mouse_move(0, value),
-            ScrollAxis::Wheel => send_mouse_input(MOUSEEVENTF_WHEEL, value as u32, 0, 0),
-            ScrollAxis::WheelHiRes => send_mouse_input(MOUSEEVENTF_WHEEL, value as u32, 0, 0),
-            ScrollAxis::HWheel => send_mouse_input(MOUSEEVENTF_HWHEEL, value as u32, 0, 0),
-            ScrollAxis::HWheelHiRes => send_mouse_input(MOUSEEVENTF_HWHEEL, value as u32, 0, 0),
-            ScrollAxis::Other(_) => {}
-        },
+            ScrollKey::LeftCtrl => send_key_input(VK_CONTROL, value == 0),
+            ScrollKey::Home => send_key_input(VK_HOME, value == 0),
+            ScrollKey::End => send_key_input(VK_END, value == 0),
+            ScrollKey::PageUp => send_key_input(VK_PRIOR, value == 0),
+            ScrollKey::PageDown => send_key_input(VK_NEXT, value == 0),
+            ScrollKey::Equal => send_key_input(VK_OEM_PLUS, value == 0),
+            ScrollKey::Minus => send_key_input(VK_OEM_MINUS, value == 0),
+            ScrollKey::LeftMeta | ScrollKey::RightMeta | ScrollKey::Task | ScrollKey::Other(_) => {}
+        },
+        OutputEvent::Rel { axis, value } => match axis {
+            ScrollAxis::X => send_relative_mouse_move(value, 0),
+            ScrollAxis::Y => send_relative_mouse_move(0, value),
+            ScrollAxis::Wheel => send_wheel(MOUSEEVENTF_WHEEL, WM_MOUSEWHEEL, value),
+            ScrollAxis::WheelHiRes => {
+                send_wheel(MOUSEEVENTF_WHEEL, WM_MOUSEWHEEL, value);
+                if crate::engine::config().broadcast_scroll() {
+                    broadcast_wheel(out, WM_MOUSEWHEEL, value);
+                }
+            }
+            ScrollAxis::HWheel => send_wheel(MOUSEEVENTF_HWHEEL, WM_MOUSEHWHEEL, value),
+            ScrollAxis::HWheelHiRes => {
+                send_wheel(MOUSEEVENTF_HWHEEL, WM_MOUSEHWHEEL, value);
+                if crate::engine::config().broadcast_scroll() {
+                    broadcast_wheel(out, WM_MOUSEHWHEEL, value);
+                }
+            }
+            ScrollAxis::Other(_) => {}
+        },
+    }
+}
+
+fn wheel_params(delta: i32, point: POINT) -> (WPARAM, LPARAM) {
+    let wparam: WPARAM = (delta as i16 as u16 as usize) << 16;
+    let lparam =
+        ((point.x as i16 as u16 as u32) | ((point.y as i16 as u16 as u32) << 16)) as LPARAM;
+    (wparam, lparam)
+}
+
+unsafe fn unfocused_window_under_cursor() -> Option<(HWND, POINT)> {
+    let mut cursor = POINT { x: 0, y: 0 };
+    if GetCursorPos(&mut cursor) == 0 {
+        return None;
+    }
+
+    let hwnd = WindowFromPoint(cursor);
+    if hwnd.is_null() {
+        return None;
+    }
+
+    let root = GetAncestor(hwnd, GA_ROOT);
+    if root.is_null() || root == GetForegroundWindow() || is_shell_window(root) {
+        return None;
+    }
+
+    let mut pid: DWORD = 0;
+    GetWindowThreadProcessId(root, &mut pid);
+    if pid == GetCurrentProcessId() {
+        return None;
+    }
+
+    Some((hwnd, cursor))
+}
+
+/// (at top, at bottom) for the nearest window under the cursor with a standard vertical scroll
+/// bar. Apps that draw their own scrolling, such as browsers, have none to read.
+fn scroll_edges_impl() -> Option<(bool, bool)> {
+    unsafe {
+        let mut cursor = POINT { x: 0, y: 0 };
+        if GetCursorPos(&mut cursor) == 0 {
+            return None;
+        }
+
+        let mut hwnd = WindowFromPoint(cursor);
+        let root = GetAncestor(hwnd, GA_ROOT);
+        while !hwnd.is_null() {
+            if GetWindowLongA(hwnd, GWL_STYLE) & WS_VSCROLL != 0 {
+                let mut info = SCROLLINFO {
+                    cb_size: mem::size_of::<SCROLLINFO>() as UINT,
+                    f_mask: SIF_RANGE | SIF_PAGE | SIF_POS,
+                    n_min: 0,
+                    n_max: 0,
+                    n_page: 0,
+                    n_pos: 0,
+                    n_track_pos: 0,
+                };
+                if GetScrollInfo(hwnd, SB_VERT, &mut info) != 0 {
+                    let last = info.n_max - info.n_page.max(1) as i32 + 1;
+                    return Some((info.n_pos <= info.n_min, info.n_pos >= last));
+                }
+            }
+            if hwnd == root {
+                break;
+            }
+            hwnd = GetAncestor(hwnd, GA_PARENT);
+        }
+        None
+    }
+}
+
+unsafe fn post_wheel_under_cursor(msg: UINT, delta: i32) -> bool {
+    let mut cursor = POINT { x: 0, y: 0 };
+    if GetCursorPos(&mut cursor) == 0 {
+        return false;
+    }
+
+    let hwnd = WindowFromPoint(cursor);
+    if hwnd.is_null() {
+        return false;
+    }
+
+    let (wparam, lparam) = wheel_params(delta, cursor);
+    PostMessageA(hwnd, msg, wparam, lparam) != 0
+}
+
+fn send_wheel(flag: DWORD, msg: UINT, delta: i32) {
+    let cfg = crate::engine::config();
+
+    if cfg.output_method() == OutputMethod::PostMessage {
+        if unsafe { post_wheel_under_cursor(msg, delta) } {
+            return;
+        }
+    } else if cfg.scroll_under_cursor() {
+        unsafe {
+            if let Some((hwnd, cursor)) = unfocused_window_under_cursor() {
+                let (wparam, lparam) = wheel_params(delta, cursor);
+                let mut result: usize = 0;
+                // A hung target must not stall the engine loop, so fall back to SendInput.
+                if SendMessageTimeoutA(
+                    hwnd,
+                    msg,
+                    wparam,
+                    lparam,
+                    SMTO_ABORTIFHUNG,
+                    WHEEL_SEND_TIMEOUT_MS,
+                    &mut result,
+                ) != 0
+                {
+                    return;
+                }
+            }
+        }
+    }
+
+    send_mouse_input(flag, delta as u32, 0, 0);
+}
+
+struct BroadcastTargets {
+    own_pid: DWORD,
+    skip_monitor: HMONITOR,
+    foreground: HWND,
+    built_at: Instant,
+    monitors: Vec<HMONITOR>,
+    windows: Vec<HWND>,
+}
+
+unsafe fn window_class_name(hwnd: HWND) -> Option<String> {
+    // Window class names are limited to 256 characters.
+    let mut class = [0u8; 257];
+    let len = GetClassNameA(hwnd, class.as_mut_ptr(), class.len() as i32);
+    if len <= 0 {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&class[..len as usize]).into_owned())
+}
+
+unsafe fn is_shell_window(hwnd: HWND) -> bool {
+    window_class_name(hwnd).is_some_and(|name| SHELL_WINDOW_CLASSES.contains(&name.as_str()))
+}
+
+unsafe fn is_scrollable_window(hwnd: HWND) -> bool {
+    GetWindowLongA(hwnd, GWL_STYLE) & (WS_VSCROLL | WS_HSCROLL) != 0
+        || window_class_name(hwnd)
+            .is_some_and(|name| SCROLLABLE_WINDOW_CLASSES.contains(&name.as_str()))
+}
+
+unsafe extern "system" fn collect_broadcast_target(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    guard_callback("window enumeration callback", 1, || {
+        let targets = &mut *(lparam as *mut BroadcastTargets);
+
+        if IsWindowVisible(hwnd) == 0 || IsIconic(hwnd) != 0 {
+            return 1;
+        }
+
+        let ex_style = GetWindowLongA(hwnd, GWL_EXSTYLE);
+        if ex_style & (WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE) != 0 {
+            return 1;
+        }
+
+        let mut pid: DWORD = 0;
+        GetWindowThreadProcessId(hwnd, &mut pid);
+        if pid == targets.own_pid {
+            return 1;
+        }
+
+        if is_shell_window(hwnd) || !is_scrollable_window(hwnd) {
+            return 1;
+        }
+
+        let monitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONULL);
+        if monitor.is_null() || monitor == targets.skip_monitor {
+            return 1;
+        }
+
+        // EnumWindows walks in z-order, so the first hit per monitor is its topmost app.
+        if !targets.monitors.contains(&monitor) {
+            targets.monitors.push(monitor);
+            targets.windows.push(hwnd);
+        }
+
+        1
+    })
+}
+
+/// Walks the window list once per gesture rather than per event: the targets are kept until
+/// the cursor changes monitor, the foreground window changes, or they grow stale.
+unsafe fn broadcast_targets(out: &mut WindowsOutputHandle) -> Option<&BroadcastTargets> {
+    let mut cursor = POINT { x: 0, y: 0 };
+    if GetCursorPos(&mut cursor) == 0 {
+        return None;
+    }
+    let skip_monitor = MonitorFromPoint(cursor, MONITOR_DEFAULTTONULL);
+    let foreground = GetForegroundWindow();
+
+    let fresh = out.broadcast.as_ref().is_some_and(|targets| {
+        targets.skip_monitor == skip_monitor
+            && targets.foreground == foreground
+            && targets.built_at.elapsed() < BROADCAST_REFRESH
+    });
+    if !fresh {
+        let mut targets = BroadcastTargets {
+            own_pid: GetCurrentProcessId(),
+            skip_monitor,
+            foreground,
+            built_at: Instant::now(),
+            monitors: Vec::new(),
+            windows: Vec::new(),
+        };
+        EnumWindows(
+            Some(collect_broadcast_target),
+            &mut targets as *mut BroadcastTargets as LPARAM,
+        );
+        out.broadcast = Some(targets);
+    }
+
+    out.broadcast.as_ref()
+}
+
+fn broadcast_wheel(out: &mut WindowsOutputHandle, msg: UINT, delta: i32) {
+    unsafe {
+        let Some(targets) = broadcast_targets(out) else {
+            return;
+        };
+
+        for &hwnd in &targets.windows {
+            let mut rect = RECT {
+                left: 0,
+                top: 0,
+                right: 0,
+                bottom: 0,
+            };
+            if GetWindowRect(hwnd, &mut rect) == 0 {
+                continue;
+            }
+
+            let center = POINT {
+                x: (rect.left + rect.right) / 2,
+                y: (rect.top + rect.bottom) / 2,
+            };
+            let (wparam, lparam) = wheel_params(delta, center);
+            let _ = PostMessageA(hwnd, msg, wparam, lparam);
+        }
     }
 }
 
@@ -311,6 +1438,68 @@ struct MSG {
     l_private: DWORD,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct POINT {
+    x: LONG,
+    y: LONG,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct RECT {
+    left: LONG,
+    top: LONG,
+    right: LONG,
+    bottom: LONG,
+}
+
+#[repr(C)]
+struct PROCESSENTRY32W {
+    dw_size: DWORD,
+    cnt_usage: DWORD,
+    th32_process_id: DWORD,
+    th32_default_heap_id: ULONG_PTR,
+    th32_module_id: DWORD,
+    cnt_threads: DWORD,
+    th32_parent_process_id: DWORD,
+    pc_pri_class_base: LONG,
+    dw_flags: DWORD,
+    sz_exe_file: [u16; MAX_PATH],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SCROLLINFO {
+    cb_size: UINT,
+    f_mask: UINT,
+    n_min: i32,
+    n_max: i32,
+    n_page: UINT,
+    n_pos: i32,
+    n_track_pos: i32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct PAINTSTRUCT {
+    hdc: HANDLE,
+    f_erase: BOOL,
+    rc_paint: RECT,
+    f_restore: BOOL,
+    f_inc_update: BOOL,
+    rgb_reserved: [u8; 32],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct MONITORINFO {
+    cb_size: DWORD,
+    rc_monitor: RECT,
+    rc_work: RECT,
+    dw_flags: DWORD,
+}
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 struct RAWINPUTDEVICE {
@@ -367,6 +1556,16 @@ struct RAWINPUT {
     data: RAWINPUT_DATA,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct MSLLHOOKSTRUCT {
+    pt: POINT,
+    mouse_data: DWORD,
+    flags: DWORD,
+    time: DWORD,
+    dw_extra_info: ULONG_PTR,
+}
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 struct WNDCLASSA {
@@ -420,12 +1619,100 @@ struct KEYBDINPUT {
 #[link(name = "user32")]
 #[link(name = "kernel32")]
 unsafe extern "system" {
+    fn RegisterWindowMessageA(lp_string: *const u8) -> UINT;
+    fn ChangeWindowMessageFilter(message: UINT, dw_flag: DWORD) -> BOOL;
     fn GetForegroundWindow() -> HWND;
     fn PostThreadMessageA(id_thread: DWORD, msg: UINT, w_param: WPARAM, l_param: LPARAM) -> BOOL;
     fn GetCurrentThreadId() -> DWORD;
 
     fn SendInput(c_inputs: UINT, p_inputs: *const INPUT, cb_size: i32) -> UINT;
 
+    fn EnumWindows(
+        lp_enum_func: Option<unsafe extern "system" fn(HWND, LPARAM) -> BOOL>,
+        l_param: LPARAM,
+    ) -> BOOL;
+    fn IsWindowVisible(hwnd: HWND) -> BOOL;
+    fn IsIconic(hwnd: HWND) -> BOOL;
+    fn GetWindowLongA(hwnd: HWND, n_index: i32) -> LONG;
+    fn GetWindowThreadProcessId(hwnd: HWND, lpdw_process_id: *mut DWORD) -> DWORD;
+    fn GetClassNameA(hwnd: HWND, lp_class_name: *mut u8, n_max_count: i32) -> i32;
+    fn GetWindowRect(hwnd: HWND, lp_rect: *mut RECT) -> BOOL;
+    fn GetCursorPos(lp_point: *mut POINT) -> BOOL;
+    fn GetDoubleClickTime() -> UINT;
+    fn MonitorFromWindow(hwnd: HWND, dw_flags: DWORD) -> HMONITOR;
+    fn MonitorFromPoint(pt: POINT, dw_flags: DWORD) -> HMONITOR;
+    fn GetMonitorInfoA(h_monitor: HMONITOR, lpmi: *mut MONITORINFO) -> BOOL;
+    fn PostMessageA(hwnd: HWND, msg: UINT, w_param: WPARAM, l_param: LPARAM) -> BOOL;
+    fn SystemParametersInfoA(
+        ui_action: UINT,
+        ui_param: UINT,
+        pv_param: *mut c_void,
+        f_win_ini: UINT,
+    ) -> BOOL;
+    fn FindWindowExA(
+        h_wnd_parent: HWND,
+        h_wnd_child_after: HWND,
+        lpsz_class: *const u8,
+        lpsz_window: *const u8,
+    ) -> HWND;
+    fn AllowSetForegroundWindow(dw_process_id: DWORD) -> BOOL;
+    fn SendMessageTimeoutA(
+        hwnd: HWND,
+        msg: UINT,
+        w_param: WPARAM,
+        l_param: LPARAM,
+        fu_flags: UINT,
+        u_timeout: UINT,
+        lpdw_result: *mut usize,
+    ) -> LPARAM;
+    fn WindowFromPoint(point: POINT) -> HWND;
+    fn GetAncestor(hwnd: HWND, ga_flags: UINT) -> HWND;
+    fn GetScrollInfo(hwnd: HWND, n_bar: i32, lpsi: *mut SCROLLINFO) -> BOOL;
+    fn GetCurrentProcessId() -> DWORD;
+    fn CreateToolhelp32Snapshot(dw_flags: DWORD, th32_process_id: DWORD) -> HANDLE;
+    fn Process32FirstW(h_snapshot: HANDLE, lppe: *mut PROCESSENTRY32W) -> BOOL;
+    fn Process32NextW(h_snapshot: HANDLE, lppe: *mut PROCESSENTRY32W) -> BOOL;
+    fn OpenMutexW(dw_desired_access: DWORD, b_inherit_handle: BOOL, lp_name: *const u16) -> HANDLE;
+    fn CloseHandle(h_object: HANDLE) -> BOOL;
+
+    fn CreateWaitableTimerExW(
+        lp_timer_attributes: *const c_void,
+        lp_timer_name: *const u16,
+        dw_flags: DWORD,
+        dw_desired_access: DWORD,
+    ) -> HANDLE;
+    fn SetWaitableTimer(
+        h_timer: HANDLE,
+        lp_due_time: *const i64,
+        l_period: LONG,
+        pfn_completion_routine: *mut c_void,
+        lp_arg_to_completion_routine: *mut c_void,
+        f_resume: BOOL,
+    ) -> BOOL;
+    fn WaitForSingleObject(h_handle: HANDLE, dw_milliseconds: DWORD) -> DWORD;
+
+    fn SetWindowPos(
+        hwnd: HWND,
+        hwnd_insert_after: HWND,
+        x: i32,
+        y: i32,
+        cx: i32,
+        cy: i32,
+        u_flags: UINT,
+    ) -> BOOL;
+    fn ShowWindow(hwnd: HWND, n_cmd_show: i32) -> BOOL;
+    fn SetLayeredWindowAttributes(hwnd: HWND, cr_key: DWORD, b_alpha: u8, dw_flags: DWORD) -> BOOL;
+    fn BeginPaint(hwnd: HWND, lp_paint: *mut PAINTSTRUCT) -> HANDLE;
+    fn EndPaint(hwnd: HWND, lp_paint: *const PAINTSTRUCT) -> BOOL;
+    fn FillRect(hdc: HANDLE, lprc: *const RECT, hbr: HANDLE) -> i32;
+
+    fn ClipCursor(lp_rect: *const RECT) -> BOOL;
+    fn GetClipCursor(lp_rect: *mut RECT) -> BOOL;
+
+    fn OutputDebugStringA(lp_output_string: *const u8);
+    fn AttachConsole(dw_process_id: DWORD) -> BOOL;
+    fn MessageBoxW(hwnd: HWND, lp_text: *const u16, lp_caption: *const u16, u_type: UINT) -> i32;
+
     fn RegisterClassA(lp_wnd_class: *const WNDCLASSA) -> u16;
     fn CreateWindowExA(
         dw_ex_style: DWORD,
@@ -443,12 +1730,26 @@ unsafe extern "system" {
     ) -> HWND;
     fn DefWindowProcA(hwnd: HWND, msg: UINT, w_param: WPARAM, l_param: LPARAM) -> LRESULT;
     fn DispatchMessageA(lp_msg: *const MSG) -> isize;
-    fn GetMessageA(lp_msg: *mut MSG, h_wnd: HWND, w_msg_filter_min: UINT, w_msg_filter_max: UINT) -> i32;
+    fn GetMessageA(
+        lp_msg: *mut MSG,
+        h_wnd: HWND,
+        w_msg_filter_min: UINT,
+        w_msg_filter_max: UINT,
+    ) -> i32;
     fn TranslateMessage(lp_msg: *const MSG) -> i32;
     fn PostQuitMessage(n_exit_code: i32);
 
     fn GetModuleHandleA(lp_module_name: *const u8) -> HMODULE;
 
+    fn SetWindowsHookExW(
+        id_hook: i32,
+        lpfn: Option<unsafe extern "system" fn(i32, WPARAM, LPARAM) -> LRESULT>,
+        h_mod: HINSTANCE,
+        dw_thread_id: DWORD,
+    ) -> HHOOK;
+    fn CallNextHookEx(hhk: HHOOK, n_code: i32, w_param: WPARAM, l_param: LPARAM) -> LRESULT;
+    fn UnhookWindowsHookEx(hhk: HHOOK) -> BOOL;
+
     fn RegisterRawInputDevices(
         p_raw_input_devices: *const RAWINPUTDEVICE,
         ui_num_devices: UINT,
@@ -464,22 +1765,242 @@ unsafe extern "system" {
     ) -> UINT;
 }
 
+#[link(name = "shell32")]
+unsafe extern "system" {
+    fn IsUserAnAdmin() -> BOOL;
+    fn ShellExecuteW(
+        hwnd: HWND,
+        lp_operation: *const u16,
+        lp_file: *const u16,
+        lp_parameters: *const u16,
+        lp_directory: *const u16,
+        n_show_cmd: i32,
+    ) -> HINSTANCE;
+}
+
+#[link(name = "winmm")]
+unsafe extern "system" {
+    fn timeBeginPeriod(u_period: UINT) -> UINT;
+    fn timeEndPeriod(u_period: UINT) -> UINT;
+    fn PlaySoundW(psz_sound: *const u16, hmod: HMODULE, fdw_sound: DWORD) -> BOOL;
+}
+
+#[link(name = "gdi32")]
+unsafe extern "system" {
+    fn CreateSolidBrush(color: DWORD) -> HANDLE;
+    fn SelectObject(hdc: HANDLE, h: HANDLE) -> HANDLE;
+    fn DeleteObject(ho: HANDLE) -> BOOL;
+    fn GetStockObject(i: i32) -> HANDLE;
+    fn Ellipse(hdc: HANDLE, left: i32, top: i32, right: i32, bottom: i32) -> BOOL;
+}
+
 unsafe extern "system" fn raw_input_wnd_proc(
     hwnd: HWND,
     msg: UINT,
     wparam: WPARAM,
     lparam: LPARAM,
 ) -> LRESULT {
-    match msg {
+    guard_callback("raw input window procedure", 0, || match msg {
         WM_INPUT => {
             handle_raw_input(lparam);
             0
         }
+        WM_SHOW_SETTINGS => {
+            crate::tray::request_settings();
+            0
+        }
         WM_DESTROY => {
             PostQuitMessage(0);
             0
         }
         _ => DefWindowProcA(hwnd, msg, wparam, lparam),
+    })
+}
+
+/// Swallows the physical wheel in replace mode so only the smoothed stream reaches apps, and
+/// physical clicks the engine acts on itself; our own injected events carry the input tag and
+/// always pass.
+unsafe extern "system" fn mouse_hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let swallow = guard_callback("mouse hook", false, || {
+        if code == HC_ACTION && wparam as UINT == WM_MOUSEMOVE {
+            note_hook_probe(&*(lparam as *const MSLLHOOKSTRUCT));
+        }
+        if code != HC_ACTION {
+            return false;
+        }
+        let info = &*(lparam as *const MSLLHOOKSTRUCT);
+        if info.dw_extra_info as DWORD == input_tag() {
+            return false;
+        }
+        if let Some((key, pressed)) = hook_button(wparam as UINT, info) {
+            return swallow_button(key, pressed);
+        }
+        if !matches!(wparam as UINT, WM_MOUSEWHEEL | WM_MOUSEHWHEEL) {
+            return false;
+        }
+        // A wheel turned into zoom keys, or switched off, never reaches the app as is.
+        let taken = crate::engine::config().wheel_mode() == WheelMode::Replace
+            || crate::engine::wheel_action() != WheelAction::Scroll;
+        taken && !crate::engine::is_suspended()
+    });
+    if swallow {
+        return 1;
+    }
+    CallNextHookEx(ptr::null_mut(), code, wparam, lparam)
+}
+
+unsafe extern "system" fn anchor_wnd_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    guard_callback("anchor window procedure", 0, || {
+        if msg == WM_SETTINGCHANGE
+            && matches!(
+                wparam as UINT,
+                SPI_SETWHEELSCROLLLINES | SPI_SETWHEELSCROLLCHARS
+            )
+        {
+            refresh_wheel_scroll_units();
+        }
+        // Never hold up shutdown or logoff; the cleanup happens once the session really ends.
+        if msg == WM_DISPLAYCHANGE {
+            display_changed(wparam, lparam);
+        }
+        if msg == WM_QUERYENDSESSION {
+            return 1;
+        }
+        if msg == WM_ENDSESSION && wparam != 0 {
+            end_session();
+            return 0;
+        }
+        if msg != WM_PAINT {
+            return DefWindowProcA(hwnd, msg, wparam, lparam);
+        }
+
+        let mut ps: PAINTSTRUCT = mem::zeroed();
+        let hdc = BeginPaint(hwnd, &mut ps);
+
+        let client = RECT {
+            left: 0,
+            top: 0,
+            right: ANCHOR_SIZE,
+            bottom: ANCHOR_SIZE,
+        };
+        let key = CreateSolidBrush(ANCHOR_KEY_COLOR);
+        FillRect(hdc, &client, key);
+        DeleteObject(key);
+
+        let fill = CreateSolidBrush(ANCHOR_FILL_COLOR);
+        let previous = SelectObject(hdc, fill);
+        Ellipse(hdc, 1, 1, ANCHOR_SIZE - 1, ANCHOR_SIZE - 1);
+
+        let center = ANCHOR_SIZE / 2;
+        SelectObject(hdc, GetStockObject(BLACK_BRUSH));
+        Ellipse(hdc, center - 3, center - 3, center + 3, center + 3);
+
+        SelectObject(hdc, previous);
+        DeleteObject(fill);
+        EndPaint(hwnd, &ps);
+        0
+    })
+}
+
+/// Reads the lines and characters Windows scrolls per wheel notch.
+fn refresh_wheel_scroll_units() {
+    let mut lines: UINT = 3;
+    let mut chars: UINT = 3;
+    unsafe {
+        SystemParametersInfoA(
+            SPI_GETWHEELSCROLLLINES,
+            0,
+            &mut lines as *mut UINT as *mut c_void,
+            0,
+        );
+        SystemParametersInfoA(
+            SPI_GETWHEELSCROLLCHARS,
+            0,
+            &mut chars as *mut UINT as *mut c_void,
+            0,
+        );
+    }
+    log_info!(
+        "system wheel setting: {} lines, {} characters per notch",
+        lines,
+        chars
+    );
+    crate::engine::set_wheel_scroll_units(lines, chars);
+}
+
+unsafe fn create_anchor_window(h_instance: HINSTANCE) -> HWND {
+    let class_name = b"NimbusScrollAnchor\0";
+
+    let wc = WNDCLASSA {
+        style: 0,
+        lpfn_wnd_proc: Some(anchor_wnd_proc),
+        cb_cls_extra: 0,
+        cb_wnd_extra: 0,
+        h_instance,
+        h_icon: ptr::null_mut(),
+        h_cursor: ptr::null_mut(),
+        hbr_background: ptr::null_mut(),
+        lpsz_menu_name: ptr::null(),
+        lpsz_class_name: class_name.as_ptr(),
+    };
+
+    if RegisterClassA(&wc) == 0 {
+        return ptr::null_mut();
+    }
+
+    let ex_style = WS_EX_LAYERED | WS_EX_TRANSPARENT | WS_EX_TOPMOST | WS_EX_TOOLWINDOW;
+    let hwnd = CreateWindowExA(
+        (ex_style | WS_EX_NOACTIVATE) as DWORD,
+        class_name.as_ptr(),
+        class_name.as_ptr(),
+        WS_POPUP,
+        0,
+        0,
+        ANCHOR_SIZE,
+        ANCHOR_SIZE,
+        ptr::null_mut(),
+        ptr::null_mut(),
+        h_instance,
+        ptr::null_mut(),
+    );
+
+    if !hwnd.is_null() {
+        SetLayeredWindowAttributes(hwnd, ANCHOR_KEY_COLOR, 255, LWA_COLORKEY);
+    }
+
+    hwnd
+}
+
+unsafe fn handle_anchor_message(anchor: HWND, msg: &MSG) -> bool {
+    match msg.message {
+        WM_ANCHOR_SHOW => {
+            if !anchor.is_null() {
+                let x = msg.w_param as i32 - ANCHOR_SIZE / 2;
+                let y = msg.l_param as i32 - ANCHOR_SIZE / 2;
+                SetWindowPos(
+                    anchor,
+                    HWND_TOPMOST,
+                    x,
+                    y,
+                    ANCHOR_SIZE,
+                    ANCHOR_SIZE,
+                    SWP_NOACTIVATE | SWP_SHOWWINDOW,
+                );
+            }
+            true
+        }
+        WM_ANCHOR_HIDE => {
+            if !anchor.is_null() {
+                ShowWindow(anchor, SW_HIDE);
+            }
+            true
+        }
+        _ => false,
     }
 }
 
@@ -520,10 +2041,21 @@ unsafe fn handle_raw_input(lparam: LPARAM) {
         RIM_TYPEMOUSE => {
             let mouse = raw.data.mouse;
 
-            if mouse.ul_extra_information as usize == MAGIC_WORD.as_ptr() as usize {
+            if mouse.ul_extra_information == input_tag() {
                 return;
             }
 
+            let flags = mouse.us_button_flags;
+
+            // High-rate mice report a button change and the motion that came with it together.
+            // Presses go before the motion and releases after it, so a drag started or ended
+            // by this report still gets the report's movement.
+            for (down, _, key) in RAW_MOUSE_BUTTONS {
+                if flags & down != 0 {
+                    st.push_mouse(InputEvent::Key { key, value: 1 });
+                }
+            }
+
             if mouse.l_last_x != 0 {
                 st.push_mouse(InputEvent::Rel {
                     axis: ScrollAxis::X,
@@ -538,67 +2070,10 @@ unsafe fn handle_raw_input(lparam: LPARAM) {
                 });
             }
 
-            let flags = mouse.us_button_flags;
-
-            if flags & RI_MOUSE_LEFT_BUTTON_DOWN != 0 {
-                st.push_mouse(InputEvent::Key {
-                    key: ScrollKey::Left,
-                    value: 1,
-                });
-            }
-            if flags & RI_MOUSE_LEFT_BUTTON_UP != 0 {
-                st.push_mouse(InputEvent::Key {
-                    key: ScrollKey::Left,
-                    value: 0,
-                });
-            }
-            if flags & RI_MOUSE_RIGHT_BUTTON_DOWN != 0 {
-                st.push_mouse(InputEvent::Key {
-                    key: ScrollKey::Right,
-                    value: 1,
-                });
-            }
-            if flags & RI_MOUSE_RIGHT_BUTTON_UP != 0 {
-                st.push_mouse(InputEvent::Key {
-                    key: ScrollKey::Right,
-                    value: 0,
-                });
-            }
-            if flags & RI_MOUSE_MIDDLE_BUTTON_DOWN != 0 {
-                st.push_mouse(InputEvent::Key {
-                    key: ScrollKey::Middle,
-                    value: 1,
-                });
-            }
-            if flags & RI_MOUSE_MIDDLE_BUTTON_UP != 0 {
-                st.push_mouse(InputEvent::Key {
-                    key: ScrollKey::Middle,
-                    value: 0,
-                });
-            }
-            if flags & RI_MOUSE_BUTTON_4_DOWN != 0 {
-                st.push_mouse(InputEvent::Key {
-                    key: ScrollKey::Back,
-                    value: 1,
-                });
-            }
-            if flags & RI_MOUSE_BUTTON_4_UP != 0 {
-                st.push_mouse(InputEvent::Key {
-                    key: ScrollKey::Back,
-                    value: 0,
-                });
-            }
-            if flags & RI_MOUSE_BUTTON_5_DOWN != 0 {
-                st.push_mouse(InputEvent::Key {
-                    key: ScrollKey::Forward,
-                    value: 1,
-                });
-            }
-            if flags & RI_MOUSE_BUTTON_5_UP != 0 {
-                st.push_mouse(InputEvent::Key {
-                    key: ScrollKey::Forward,
-                    value: 0,
-                });
+            for (_, up, key) in RAW_MOUSE_BUTTONS {
+                if flags & up != 0 {
+                    st.push_mouse(InputEvent::Key { key, value: 0 });
+                }
             }
 
             if flags & RI_MOUSE_WHEEL != 0 {
@@ -620,10 +2095,18 @@ unsafe fn handle_raw_input(lparam: LPARAM) {
 
         RIM_TYPEKEYBOARD => {
             let kb = raw.data.keyboard;
+            if kb.extra_information == input_tag() {
+                return;
+            }
 
+            let cfg = crate::engine::config();
+            let watched = |code: u64, vk: u32| code != 0 && u64::from(vk) == code;
             let key = match kb.v_key as u32 {
                 0x5B => Some(ScrollKey::LeftMeta),
                 0x5C => Some(ScrollKey::RightMeta),
+                vk if watched(cfg.temp_invert_key(), vk) || watched(cfg.fine_key(), vk) => {
+                    Some(ScrollKey::Other(kb.v_key))
+                }
                 _ => None,
             };
 
@@ -641,7 +2124,7 @@ unsafe fn raw_input_thread_main() {
     let thread_id = GetCurrentThreadId();
     let _ = RAW_THREAD_ID.set(thread_id);
 
-    let class_name = b"NimbusScrollRawInput\0";
+    let class_name = RAW_INPUT_CLASS;
     let h_instance = GetModuleHandleA(ptr::null());
 
     let wc = WNDCLASSA {
@@ -680,11 +2163,19 @@ unsafe fn raw_input_thread_main() {
         return;
     }
 
+    let mode = crate::engine::config().raw_input_mode();
+    let mouse_flags = mode.flags();
+    log_info!(
+        "registering raw mouse input with flags {:#x} ({})",
+        mouse_flags,
+        mode.label()
+    );
+
     let devices = [
         RAWINPUTDEVICE {
             us_usage_page: 0x01,
             us_usage: 0x02,
-            dw_flags: RIDEV_INPUTSINK,
+            dw_flags: mouse_flags,
             hwnd_target: hwnd,
         },
         RAWINPUTDEVICE {
@@ -696,9 +2187,23 @@ unsafe fn raw_input_thread_main() {
     ];
 
     if RegisterRawInputDevices(&devices[0], 2, mem::size_of::<RAWINPUTDEVICE>() as UINT) == 0 {
+        log_error!(
+            "RegisterRawInputDevices failed with flags {:#x}",
+            mouse_flags
+        );
         return;
     }
 
+    // Low-level hooks run on the installing thread, so this message loop also services it.
+    let mut hook = update_wheel_hook(ptr::null_mut(), h_instance);
+    if crate::engine::config().conflict_check() {
+        start_conflict_check(!hook.is_null());
+    }
+
+    // The anchor is a top-level window, so it also hears WM_SETTINGCHANGE broadcasts.
+    let anchor = create_anchor_window(h_instance);
+    refresh_wheel_scroll_units();
+
     let mut msg = MSG {
         hwnd: ptr::null_mut(),
         message: 0,
@@ -715,9 +2220,20 @@ unsafe fn raw_input_thread_main() {
         if got <= 0 {
             break;
         }
+        if msg.hwnd.is_null() && msg.message == WM_WHEEL_HOOK_SYNC {
+            hook = update_wheel_hook(hook, h_instance);
+            continue;
+        }
+        if msg.hwnd.is_null() && handle_anchor_message(anchor, &msg) {
+            continue;
+        }
         TranslateMessage(&msg);
         DispatchMessageA(&msg);
     }
+
+    if !hook.is_null() {
+        UnhookWindowsHookEx(hook);
+    }
 }
 
 impl Backend for WindowsBackend {
@@ -769,16 +2285,118 @@ impl Backend for WindowsBackend {
         reset_virtual_mouse_buttons_impl(_out)
     }
 
-    fn emit_output(_out: &mut Self::Output, event: OutputEvent) -> Result<(), Box<dyn Error>> {
-        emit_output_impl(event);
+    fn emit_output(out: &mut Self::Output, event: OutputEvent) -> Result<(), Box<dyn Error>> {
+        emit_output_impl(out, event);
+        Ok(())
+    }
+
+    fn emit_passthrough(_out: &mut Self::Output, _event: InputEvent) -> Result<(), Box<dyn Error>> {
+        // Raw input never blocks the original event and the wheel hook stands aside while
+        // suspended or in additive mode, so it already reached the application.
         Ok(())
     }
 
+    fn foreground_is_fullscreen() -> bool {
+        foreground_is_fullscreen_impl()
+    }
+
+    fn foreground_class() -> Option<String> {
+        foreground_class_impl()
+    }
+
+    fn hold_pointer(out: &mut Self::Output, grace: Duration) -> PointerHold {
+        hold_pointer_impl(out, grace)
+    }
+
+    fn release_pointer(out: &mut Self::Output) {
+        release_pointer_impl(out);
+    }
+
+    fn show_anchor(_out: &mut Self::Output) {
+        show_anchor_impl();
+    }
+
+    fn hide_anchor(_out: &mut Self::Output) {
+        hide_anchor_impl();
+    }
+
+    fn play_scroll_sound(sound_file: Option<&str>) {
+        play_scroll_sound_impl(sound_file);
+    }
+
+    fn scroll_edges() -> Option<(bool, bool)> {
+        scroll_edges_impl()
+    }
+
     fn active_window_signature() -> Option<String> {
         active_window_signature_impl()
     }
 
     fn sleep(duration: Duration) {
-        std::thread::sleep(duration);
+        let hires = crate::engine::config().hires_timer();
+        set_timer_period_raised(hires);
+        if !hires || !hires_sleep(duration) {
+            std::thread::sleep(duration);
+        }
+    }
+
+    fn wait_for_input(_mouse: &Self::Mouse, _keyboards: &[Self::Keyboard], timeout: Duration) {
+        // No point holding the 1 ms timer period while nothing is scrolling.
+        set_timer_period_raised(false);
+        state().wait_input(timeout);
+    }
+}
+
+fn set_timer_period_raised(raised: bool) {
+    if TIMER_PERIOD_RAISED.swap(raised, Ordering::Relaxed) == raised {
+        return;
+    }
+
+    unsafe {
+        if raised {
+            timeBeginPeriod(1);
+        } else {
+            timeEndPeriod(1);
+        }
+    }
+}
+
+unsafe fn hires_timer_handle() -> HANDLE {
+    HIRES_TIMER.with(|cell| {
+        if cell.get().is_null() {
+            let mut timer = CreateWaitableTimerExW(
+                ptr::null(),
+                ptr::null(),
+                CREATE_WAITABLE_TIMER_HIGH_RESOLUTION,
+                TIMER_ALL_ACCESS,
+            );
+            if timer.is_null() {
+                // High-resolution timers need Windows 10 1803; a plain one still beats Sleep.
+                timer = CreateWaitableTimerExW(ptr::null(), ptr::null(), 0, TIMER_ALL_ACCESS);
+            }
+            cell.set(timer);
+        }
+        cell.get()
+    })
+}
+
+fn hires_sleep(duration: Duration) -> bool {
+    if duration.is_zero() {
+        return true;
     }
-}
\ No newline at end of file
+
+    unsafe {
+        let timer = hires_timer_handle();
+        if timer.is_null() {
+            return false;
+        }
+
+        // Negative due times are relative, in 100ns units.
+        let due = -((duration.as_nanos() / 100).max(1) as i64);
+        if SetWaitableTimer(timer, &due, 0, ptr::null_mut(), ptr::null_mut(), 0) == 0 {
+            return false;
+        }
+
+        WaitForSingleObject(timer, INFINITE) == WAIT_OBJECT_0
+    }
+}